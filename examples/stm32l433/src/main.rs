@@ -5,11 +5,14 @@ use defmt_rtt as _;
 use panic_probe as _;
 
 use embassy_executor::Spawner;
+use embassy_stm32::exti::ExtiInput;
+use embassy_stm32::gpio::Pull;
 use embassy_stm32::i2c::I2c;
 use embassy_stm32::time::Hertz;
-use embassy_time::Timer;
 
-use ism330dhcx::{ctrl1xl, ctrl2g, Ism330Dhcx};
+use ism330dhcx::fifoctrl::{BdrGy, BdrXl, FifoMode};
+use ism330dhcx::stream::{IntPin, Stream};
+use ism330dhcx::{ctrl1xl, ctrl2g, fifo, Ism330Dhcx};
 
 #[embassy_executor::main]
 async fn main(_spawner: Spawner) {
@@ -37,20 +40,27 @@ async fn main(_spawner: Spawner) {
     // Initializing sensor
     boot_sensor(&mut sensor, &mut i2c);
 
+    //==============================================
+    // Declaring INT1, raised by the FIFO threshold interrupt
+
+    let int1 = ExtiInput::new(p.PA0, p.EXTI0, Pull::Down);
+    let mut stream = Stream::new(&mut i2c, sensor, int1, IntPin::Int1, 32).unwrap();
+    let mut values = [fifo::Value::Empty; 32];
+
     // =======================================
 
     loop {
-        defmt::info!("Temperature: {}", sensor.get_temperature(&mut i2c).unwrap());
-        defmt::info!(
-            "Gyroscope: {:?}",
-            sensor.get_gyroscope(&mut i2c).unwrap().as_dps()
-        );
-        defmt::info!(
-            "Accelerometer: {:?}",
-            sensor.get_accelerometer(&mut i2c).unwrap().as_m_ss()
-        );
-
-        Timer::after_millis(500).await;
+        let n = stream.next(&mut i2c, &mut values).await.unwrap();
+
+        for value in &values[..n] {
+            match value {
+                fifo::Value::Gyro(gyro) => defmt::info!("Gyroscope: {:?}", gyro.as_dps()),
+                fifo::Value::Accel(accel) => {
+                    defmt::info!("Accelerometer: {:?}", accel.as_m_ss())
+                }
+                other => defmt::info!("{}", other),
+            }
+        }
     }
 }
 
@@ -105,4 +115,17 @@ where
     // CTRL7_G
 
     sensor.ctrl7g.set_g_hm_mode(i2c, true).unwrap();
+
+    // =======================================
+    // FIFO_CTRL
+
+    sensor
+        .fifoctrl
+        .set_accelerometer_batch_data_rate(i2c, BdrXl::Hz52)
+        .unwrap();
+    sensor
+        .fifoctrl
+        .set_gyroscope_batch_data_rate(i2c, BdrGy::Hz52)
+        .unwrap();
+    sensor.fifoctrl.mode(i2c, FifoMode::Continuous).unwrap();
 }
@@ -0,0 +1,396 @@
+//! Several sensors driven as one.
+//!
+//! [`SensorArray`] owns N sensors, each with its own bus handle. Sensors sharing a bus get their
+//! handles from `embedded-hal-bus`, for example `RefCellDevice` or `CriticalSectionDevice`, and
+//! the two addresses selected by SA0 (6Ah and 6Bh) put two sensors on one I²C bus. Sensors on
+//! other buses can be mixed in as long as all the handles have the same type.
+//!
+//! The array applies one [`Config`] to all the sensors, reads their outputs back to back and
+//! merges their FIFOs into one sequence ordered by timestamp. The timestamp counters run off each
+//! sensor's own oscillator, so they are restarted together by [`SensorArray::start_timestamps`]
+//! and drift apart slowly afterwards.
+//!
+//! ```no_run
+//! # fn run<I2C: embedded_hal::i2c::I2c>(i2c: I2C) {
+//! use core::cell::RefCell;
+//!
+//! use embedded_hal_bus::i2c::RefCellDevice;
+//! use ism330dhcx::array::SensorArray;
+//! use ism330dhcx::config::Config;
+//! use ism330dhcx::ctrl1xl::Odr_Xl;
+//! use ism330dhcx::fifoctrl::{BdrXl, DecTs, FifoMode};
+//! use ism330dhcx::Ism330Dhcx;
+//!
+//! let bus = RefCell::new(i2c);
+//! let mut a = RefCellDevice::new(&bus);
+//! let mut b = RefCellDevice::new(&bus);
+//! let left = Ism330Dhcx::new_with_address(&mut a, 0x6a).unwrap();
+//! let right = Ism330Dhcx::new_with_address(&mut b, 0x6b).unwrap();
+//!
+//! let mut config = Config::default();
+//! config.accel.odr = Odr_Xl::Hz104;
+//! config.fifo.mode = FifoMode::Continuous;
+//! config.fifo.accel_bdr = BdrXl::Hz104;
+//!
+//! let mut array = SensorArray::new([(a, left), (b, right)]);
+//! array.apply(&config).unwrap();
+//! array.start_timestamps(DecTs::Every1).unwrap();
+//!
+//! while let Some(stamped) = array.pop().unwrap() {
+//!     // stamped.sensor, stamped.timestamp, stamped.value
+//! }
+//! # }
+//! ```
+
+use embedded_hal::i2c::I2c;
+
+use crate::config::{Config, ConfigError};
+use crate::fifoctrl::DecTs;
+use crate::{fifo, Error, ImuSample, Ism330Dhcx};
+
+/// An error from the array.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ArrayError<E> {
+    /// The configuration was rejected before any sensor was written.
+    InvalidConfig(ConfigError),
+    /// An error from one sensor of the array.
+    Sensor {
+        /// Index of the sensor in the array.
+        sensor: usize,
+        error: Error<E>,
+    },
+}
+
+/// A FIFO value tagged with its sensor and timestamp, see [`SensorArray::pop`].
+#[derive(Copy, Clone, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stamped {
+    /// Index of the sensor in the array.
+    pub sensor: usize,
+    /// Last timestamp batched before the value, in 25 µs steps.
+    pub timestamp: u32,
+    pub value: fifo::Value,
+}
+
+struct Member<I2C> {
+    i2c: I2C,
+    sensor: Ism330Dhcx,
+    /// Last timestamp popped from the FIFO.
+    timestamp: u32,
+    /// Value popped from the FIFO and not handed out yet, with its timestamp.
+    next: Option<(u32, fifo::Value)>,
+}
+
+/// N sensors, each on its own bus handle.
+pub struct SensorArray<I2C, const N: usize> {
+    members: [Member<I2C>; N],
+}
+
+impl<I2C: I2c, const N: usize> SensorArray<I2C, N> {
+    pub fn new(sensors: [(I2C, Ism330Dhcx); N]) -> Self {
+        SensorArray {
+            members: sensors.map(|(i2c, sensor)| Member {
+                i2c,
+                sensor,
+                timestamp: 0,
+                next: None,
+            }),
+        }
+    }
+
+    /// Give the bus handles and sensors back.
+    pub fn release(self) -> [(I2C, Ism330Dhcx); N] {
+        self.members.map(|member| (member.i2c, member.sensor))
+    }
+
+    pub fn sensor(&self, index: usize) -> &Ism330Dhcx {
+        &self.members[index].sensor
+    }
+
+    /// A sensor and its bus handle, for settings not covered by the array.
+    pub fn get_mut(&mut self, index: usize) -> (&mut Ism330Dhcx, &mut I2C) {
+        let member = &mut self.members[index];
+        (&mut member.sensor, &mut member.i2c)
+    }
+
+    /// Apply the same configuration to every sensor, see [`Ism330Dhcx::apply`].
+    ///
+    /// The configuration is validated first, so nothing is written if it is invalid. The sensors
+    /// are then written in order and a bus error stops there: the sensors before the failing one
+    /// have the new configuration, the failing one may be partly written and the ones after it
+    /// keep their previous configuration.
+    pub fn apply(&mut self, config: &Config) -> Result<(), ArrayError<I2C::Error>> {
+        config.validate().map_err(ArrayError::InvalidConfig)?;
+
+        for (sensor, member) in self.members.iter_mut().enumerate() {
+            member
+                .sensor
+                .apply(&mut member.i2c, config)
+                .map_err(|error| ArrayError::Sensor { sensor, error })?;
+        }
+
+        Ok(())
+    }
+
+    /// Batch timestamps in every FIFO and restart all the counters together.
+    ///
+    /// Timestamps are enabled on all the sensors first, then the counters are reset back to back
+    /// so they start within a few bus transfers of each other.
+    pub fn start_timestamps(&mut self, decimation: DecTs) -> Result<(), ArrayError<I2C::Error>> {
+        self.each(|member| {
+            member.sensor.enable_timestamp(&mut member.i2c, true)?;
+            member
+                .sensor
+                .fifoctrl
+                .set_timestamp_decimation(&mut member.i2c, decimation)
+        })?;
+
+        self.each(|member| member.sensor.reset_timestamp(&mut member.i2c))?;
+        for member in self.members.iter_mut() {
+            member.timestamp = 0;
+        }
+
+        Ok(())
+    }
+
+    /// Read temperature, gyroscope and accelerometer from every sensor.
+    ///
+    /// Block data update is enabled first where it is off, then the outputs are read in
+    /// back-to-back bursts so the samples are as close in time as the bus allows.
+    pub fn read_all(&mut self) -> Result<[ImuSample; N], ArrayError<I2C::Error>> {
+        self.each(|member| match member.sensor.ctrl3c.bdu() {
+            true => Ok(()),
+            false => member.sensor.ctrl3c.set_bdu(&mut member.i2c, true),
+        })?;
+
+        let mut measurements = [[0u8; 14]; N];
+        for (sensor, member) in self.members.iter_mut().enumerate() {
+            let address = member.sensor.address;
+            member
+                .i2c
+                .write_read(address, &[0x20], &mut measurements[sensor])
+                .map_err(|e| ArrayError::Sensor {
+                    sensor,
+                    error: Error::Bus(e),
+                })?;
+        }
+
+        Ok(core::array::from_fn(|sensor| {
+            self.members[sensor]
+                .sensor
+                .sample_from_msr(&measurements[sensor])
+        }))
+    }
+
+    /// The oldest value left in the FIFOs, or `None` once they are all empty.
+    ///
+    /// Each FIFO is read one value ahead. Values are ordered by the last timestamp batched before
+    /// them, ties going to the lowest sensor index. Anything batched after a FIFO was found empty
+    /// is newer than the values already read, so the order holds across calls.
+    pub fn pop(&mut self) -> Result<Option<Stamped>, ArrayError<I2C::Error>> {
+        self.each(|member| member.fill())?;
+
+        let oldest = self
+            .members
+            .iter()
+            .enumerate()
+            .filter_map(|(sensor, member)| member.next.map(|(timestamp, _)| (timestamp, sensor)))
+            .min();
+
+        Ok(oldest.and_then(|(_, sensor)| {
+            let (timestamp, value) = self.members[sensor].next.take()?;
+            Some(Stamped {
+                sensor,
+                timestamp,
+                value,
+            })
+        }))
+    }
+
+    /// Pop merged values into `out` until it is full or the FIFOs are empty, see
+    /// [`SensorArray::pop`]. Returns the number of values written.
+    pub fn drain(&mut self, out: &mut [Stamped]) -> Result<usize, ArrayError<I2C::Error>> {
+        for (count, slot) in out.iter_mut().enumerate() {
+            match self.pop()? {
+                Some(stamped) => *slot = stamped,
+                None => return Ok(count),
+            }
+        }
+
+        Ok(out.len())
+    }
+
+    fn each(
+        &mut self,
+        mut f: impl FnMut(&mut Member<I2C>) -> Result<(), I2C::Error>,
+    ) -> Result<(), ArrayError<I2C::Error>> {
+        for (sensor, member) in self.members.iter_mut().enumerate() {
+            f(member).map_err(|e| ArrayError::Sensor {
+                sensor,
+                error: Error::Bus(e),
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<I2C: I2c> Member<I2C> {
+    /// Read ahead one value from the FIFO, keeping track of the timestamps on the way.
+    fn fill(&mut self) -> Result<(), I2C::Error> {
+        while self.next.is_none() {
+            match self.sensor.fifo_pop(&mut self.i2c)? {
+                fifo::Value::Empty => break,
+                fifo::Value::Timestamp(timestamp) => self.timestamp = timestamp,
+                value => self.next = Some((self.timestamp, value)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use embedded_hal::i2c::{ErrorType, Operation};
+    use embedded_hal_bus::i2c::RefCellDevice;
+
+    use super::*;
+    use crate::config::FifoConfig;
+    use crate::ctrl1xl::Odr_Xl;
+    use crate::ctrl2g::Odr;
+    use crate::fifoctrl::{BdrGy, BdrXl, FifoMode};
+    use crate::sim::Simulator;
+
+    /// Two simulated sensors on one bus, at 6Ah and 6Bh.
+    struct Bus([Simulator; 2]);
+
+    impl Bus {
+        fn new() -> Self {
+            Bus([Simulator::with_address(0x6a), Simulator::with_address(0x6b)])
+        }
+
+        fn advance_us(&mut self, us: u64) {
+            self.0.iter_mut().for_each(|sim| sim.advance_us(us));
+        }
+    }
+
+    impl ErrorType for Bus {
+        type Error = <Simulator as ErrorType>::Error;
+    }
+
+    impl I2c for Bus {
+        fn transaction(
+            &mut self,
+            address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            self.0[(address & 1) as usize].transaction(address, operations)
+        }
+    }
+
+    fn array(bus: &RefCell<Bus>) -> SensorArray<RefCellDevice<'_, Bus>, 2> {
+        let mut a = RefCellDevice::new(bus);
+        let mut b = RefCellDevice::new(bus);
+        let left = Ism330Dhcx::new_with_address(&mut a, 0x6a).unwrap();
+        let right = Ism330Dhcx::new_with_address(&mut b, 0x6b).unwrap();
+
+        SensorArray::new([(a, left), (b, right)])
+    }
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config.accel.odr = Odr_Xl::Hz104;
+        config.gyro.odr = Odr::Hz52;
+        config.fifo = FifoConfig {
+            mode: FifoMode::Continuous,
+            accel_bdr: BdrXl::Hz104,
+            gyro_bdr: BdrGy::Hz52,
+            ..config.fifo
+        };
+        config
+    }
+
+    #[test]
+    fn apply_and_read_all() {
+        let bus = RefCell::new(Bus::new());
+        let mut array = array(&bus);
+
+        array.apply(&config()).unwrap();
+        for sim in bus.borrow().0.iter() {
+            assert_eq!(sim.register(crate::ctrl1xl::ADDR) >> 4, 0b0100);
+        }
+
+        bus.borrow_mut().advance_us(20_000);
+        let samples = array.read_all().unwrap();
+        assert_eq!(samples[0], samples[1]);
+        assert!(array.sensor(1).ctrl3c.bdu());
+    }
+
+    #[test]
+    fn merge_by_timestamp() {
+        let bus = RefCell::new(Bus::new());
+        let mut array = array(&bus);
+
+        // the right sensor was powered up earlier, its counter is ahead until restarted
+        bus.borrow_mut().0[1].advance_us(5_000);
+        array.apply(&config()).unwrap();
+        array.start_timestamps(DecTs::Every1).unwrap();
+        bus.borrow_mut().advance_us(100_000);
+
+        let mut merged = [Stamped {
+            sensor: 0,
+            timestamp: 0,
+            value: fifo::Value::Empty,
+        }; 64];
+        let count = array.drain(&mut merged).unwrap();
+        let merged = &merged[..count];
+
+        // 10 accelerometer and 5 gyroscope samples from each sensor
+        assert_eq!(count, 30);
+        assert!(merged.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        assert_eq!(merged.iter().filter(|s| s.sensor == 1).count(), 15);
+        assert!(merged.windows(2).any(|w| w[0].sensor != w[1].sensor));
+        assert!(array.pop().unwrap().is_none());
+    }
+
+    #[test]
+    fn errors_name_the_sensor() {
+        let bus = RefCell::new(Bus::new());
+        let a = RefCellDevice::new(&bus);
+        let mut b = RefCellDevice::new(&bus);
+        let right = Ism330Dhcx::new_with_address(&mut b, 0x6b).unwrap();
+
+        // nothing answers at 6Ch
+        let mut wrong = Ism330Dhcx::new_with_address(&mut b, 0x6b).unwrap();
+        wrong.set_address(0x6c);
+        let mut array = SensorArray::new([(b, right), (a, wrong)]);
+
+        let error = array.read_all().unwrap_err();
+        assert!(matches!(
+            error,
+            ArrayError::Sensor {
+                sensor: 1,
+                error: Error::Bus(_)
+            }
+        ));
+    }
+
+    #[test]
+    fn invalid_config() {
+        let bus = RefCell::new(Bus::new());
+        let mut array = array(&bus);
+
+        let mut config = config();
+        config.fifo.watermark = 1000;
+        assert_eq!(
+            array.apply(&config),
+            Err(ArrayError::InvalidConfig(ConfigError::WatermarkOutOfRange))
+        );
+        for sim in bus.borrow().0.iter() {
+            assert_eq!(sim.register(crate::ctrl1xl::ADDR), 0);
+        }
+    }
+}
@@ -0,0 +1,561 @@
+//! Command line tool for an ISM330DHCX attached to a Linux `i2c-dev` or `spidev` bus, or to the
+//! simulator.
+//!
+//! ```text
+//! ism330dhcx-cli [--device <path> | --sim] [--interface <i2c|spi>] [--address 0x6b] [settings]
+//!     <dump | stream | fifo>
+//! ```
+//!
+//! The device defaults to `/dev/i2c-1`, or `/dev/spidev0.0` with `--interface spi`. The address
+//! only applies to I²C.
+//!
+//! Settings are applied in order before the command runs:
+//!
+//! - `--accel-odr <Hz|off>`, `--accel-fs <2|4|8|16>`
+//! - `--gyro-odr <Hz|off>`, `--gyro-fs <125|250|500|1000|2000|4000>`
+//! - `--fifo-mode <bypass|fifo|continuous>`, `--accel-bdr <Hz|off>`, `--gyro-bdr <Hz|off>`
+//!
+//! `stream` and `fifo` take `--format <csv|json>`, `--count <n>` and `--interval-ms <ms>`. With
+//! `--sim` waiting advances the simulated time instead of sleeping.
+
+use std::fmt;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use embedded_hal::i2c::{self, I2c, Operation};
+use embedded_hal::spi::{self, SpiDevice};
+use ism330dhcx::sim::Simulator;
+use ism330dhcx::{ctrl1xl, ctrl2g, fifo, fifoctrl, Ism330Dhcx};
+
+const USAGE: &str = "usage: ism330dhcx-cli [--device <path> | --sim] [--interface <i2c|spi>] \
+[--address <addr>] [--accel-odr <hz>] [--accel-fs <g>] [--gyro-odr <hz>] [--gyro-fs <dps>] \
+[--fifo-mode <mode>] [--accel-bdr <hz>] [--gyro-bdr <hz>] \
+[--format <csv|json>] [--count <n>] [--interval-ms <ms>] <dump|stream|fifo>";
+
+#[derive(Debug)]
+enum BusError {
+    #[cfg(target_os = "linux")]
+    I2c(linux_embedded_hal::I2CError),
+    #[cfg(target_os = "linux")]
+    Spi(linux_embedded_hal::SPIError),
+    Sim(i2c::ErrorKind),
+}
+
+impl fmt::Display for BusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(target_os = "linux")]
+            BusError::I2c(e) => write!(f, "{}", e),
+            #[cfg(target_os = "linux")]
+            BusError::Spi(e) => write!(f, "{}", e),
+            BusError::Sim(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl i2c::Error for BusError {
+    fn kind(&self) -> i2c::ErrorKind {
+        match self {
+            #[cfg(target_os = "linux")]
+            BusError::I2c(e) => e.kind(),
+            #[cfg(target_os = "linux")]
+            BusError::Spi(_) => i2c::ErrorKind::Other,
+            BusError::Sim(e) => *e,
+        }
+    }
+}
+
+/// Either a Linux `i2c-dev` or `spidev` bus, or the simulator on either interface.
+enum Bus {
+    #[cfg(target_os = "linux")]
+    I2c(linux_embedded_hal::I2cdev),
+    #[cfg(target_os = "linux")]
+    Spi(linux_embedded_hal::SpidevDevice),
+    Sim {
+        sim: Box<Simulator>,
+        spi: bool,
+    },
+}
+
+impl Bus {
+    fn wait(&mut self, duration: Duration) {
+        match self {
+            Bus::Sim { sim, .. } => sim.advance_us(duration.as_micros() as u64),
+            #[cfg(target_os = "linux")]
+            _ => std::thread::sleep(duration),
+        }
+    }
+
+    fn elapsed_us(&self, start: Instant) -> u64 {
+        match self {
+            Bus::Sim { sim, .. } => sim.elapsed_us(),
+            #[cfg(target_os = "linux")]
+            _ => start.elapsed().as_micros() as u64,
+        }
+    }
+}
+
+/// Run the register accesses of an I²C transaction over SPI.
+///
+/// The bytes written go out as one command, the register address first with the read bit set
+/// when the transaction reads, then the reads follow in the same chip select.
+fn spi_transaction<SPI: SpiDevice>(
+    spi: &mut SPI,
+    operations: &mut [Operation<'_>],
+) -> Result<(), SPI::Error> {
+    let mut command = Vec::new();
+    let mut reads = Vec::new();
+    for operation in operations.iter_mut() {
+        match operation {
+            Operation::Write(bytes) => command.extend_from_slice(bytes),
+            Operation::Read(buffer) => reads.push(spi::Operation::Read(buffer)),
+        }
+    }
+
+    if let Some(register) = command.first_mut() {
+        *register = *register & 0x7f | u8::from(!reads.is_empty()) << 7;
+    }
+
+    let mut spi_operations = vec![spi::Operation::Write(&command)];
+    spi_operations.append(&mut reads);
+    spi.transaction(&mut spi_operations)
+}
+
+impl i2c::ErrorType for Bus {
+    type Error = BusError;
+}
+
+impl I2c for Bus {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Bus::I2c(bus) => bus.transaction(address, operations).map_err(BusError::I2c),
+            #[cfg(target_os = "linux")]
+            Bus::Spi(spi) => spi_transaction(spi, operations).map_err(BusError::Spi),
+            Bus::Sim { sim, spi: false } => {
+                I2c::transaction(sim.as_mut(), address, operations).map_err(BusError::Sim)
+            }
+            Bus::Sim { sim, spi: true } => {
+                spi_transaction(sim.as_mut(), operations).map_err(|e| match e {})
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Format {
+    Csv,
+    Json,
+}
+
+enum Command {
+    Help,
+    Dump,
+    Stream,
+    Fifo,
+}
+
+enum Setting {
+    AccelOdr(ctrl1xl::Odr_Xl),
+    AccelFs(ctrl1xl::Fs_Xl),
+    GyroOdr(ctrl2g::Odr),
+    GyroFs(ctrl2g::Fs),
+    FifoMode(fifoctrl::FifoMode),
+    AccelBdr(fifoctrl::BdrXl),
+    GyroBdr(fifoctrl::BdrGy),
+}
+
+struct Options {
+    /// Device path, the interface default when `None`.
+    device: Option<String>,
+    sim: bool,
+    spi: bool,
+    address: u8,
+    settings: Vec<Setting>,
+    format: Format,
+    count: u32,
+    interval: Duration,
+    command: Command,
+}
+
+fn parse_odr(value: &str) -> Result<u8, String> {
+    Ok(match value {
+        "off" | "0" => 0,
+        "12.5" => 1,
+        "26" => 2,
+        "52" => 3,
+        "104" => 4,
+        "208" => 5,
+        "416" | "417" => 6,
+        "833" => 7,
+        "1666" | "1667" => 8,
+        "3333" => 9,
+        "6666" | "6667" => 10,
+        _ => return Err(format!("unsupported rate: {}", value)),
+    })
+}
+
+fn parse_accel_odr(value: &str) -> Result<ctrl1xl::Odr_Xl, String> {
+    use ctrl1xl::Odr_Xl::*;
+    let odrs = [
+        Off, Hz125, Hz26, Hz52, Hz104, Hz208, Hz416, Hz833, Hz1667, Hz3333, Hz6667,
+    ];
+    Ok(odrs[parse_odr(value)? as usize])
+}
+
+fn parse_gyro_odr(value: &str) -> Result<ctrl2g::Odr, String> {
+    use ctrl2g::Odr::*;
+    let odrs = [
+        Off, Hz125, Hz26, Hz52, Hz104, Hz208, Hz416, Hz833, Hz1667, Hz3333, Hz6667,
+    ];
+    Ok(odrs[parse_odr(value)? as usize])
+}
+
+fn parse_accel_bdr(value: &str) -> Result<fifoctrl::BdrXl, String> {
+    use fifoctrl::BdrXl::*;
+    if value == "6.5" {
+        return Ok(Hz6_5);
+    }
+    let bdrs = [
+        Off, Hz12_5, Hz26, Hz52, Hz104, Hz208, Hz417, Hz833, Hz1667, Hz3333, Hz6667,
+    ];
+    Ok(bdrs[parse_odr(value)? as usize])
+}
+
+fn parse_gyro_bdr(value: &str) -> Result<fifoctrl::BdrGy, String> {
+    use fifoctrl::BdrGy::*;
+    if value == "6.5" {
+        return Ok(Hz6_5);
+    }
+    let bdrs = [
+        Off, Hz12_5, Hz26, Hz52, Hz104, Hz208, Hz417, Hz833, Hz1667, Hz3333, Hz6667,
+    ];
+    Ok(bdrs[parse_odr(value)? as usize])
+}
+
+fn parse_accel_fs(value: &str) -> Result<ctrl1xl::Fs_Xl, String> {
+    use ctrl1xl::Fs_Xl::*;
+    match value {
+        "2" => Ok(G2),
+        "4" => Ok(G4),
+        "8" => Ok(G8),
+        "16" => Ok(G16),
+        _ => Err(format!("unsupported accelerometer full scale: {}", value)),
+    }
+}
+
+fn parse_gyro_fs(value: &str) -> Result<ctrl2g::Fs, String> {
+    use ctrl2g::Fs::*;
+    match value {
+        "125" => Ok(Dps125),
+        "250" => Ok(Dps250),
+        "500" => Ok(Dps500),
+        "1000" => Ok(Dps1000),
+        "2000" => Ok(Dps2000),
+        "4000" => Ok(Dps4000),
+        _ => Err(format!("unsupported gyroscope full scale: {}", value)),
+    }
+}
+
+fn parse_fifo_mode(value: &str) -> Result<fifoctrl::FifoMode, String> {
+    match value {
+        "bypass" => Ok(fifoctrl::FifoMode::Bypass),
+        "fifo" => Ok(fifoctrl::FifoMode::FifoMode),
+        "continuous" => Ok(fifoctrl::FifoMode::Continuous),
+        _ => Err(format!("unsupported FIFO mode: {}", value)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("not a number: {}", value))
+}
+
+fn parse_address(value: &str) -> Result<u8, String> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16).map_err(|_| format!("bad address: {}", value)),
+        None => parse_number(value),
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        device: None,
+        sim: false,
+        spi: false,
+        address: ism330dhcx::DEFAULT_I2C_ADDRESS,
+        settings: Vec::new(),
+        format: Format::Csv,
+        count: 10,
+        interval: Duration::from_millis(100),
+        command: Command::Dump,
+    };
+    let mut command = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+
+        match arg.as_str() {
+            "--device" => {
+                options.device = Some(value()?);
+                options.sim = false;
+            }
+            "--sim" => options.sim = true,
+            "--interface" => {
+                options.spi = match value()?.as_str() {
+                    "i2c" => false,
+                    "spi" => true,
+                    other => return Err(format!("unsupported interface: {}", other)),
+                }
+            }
+            "--address" => options.address = parse_address(&value()?)?,
+            "--accel-odr" => options
+                .settings
+                .push(Setting::AccelOdr(parse_accel_odr(&value()?)?)),
+            "--accel-fs" => options
+                .settings
+                .push(Setting::AccelFs(parse_accel_fs(&value()?)?)),
+            "--gyro-odr" => options
+                .settings
+                .push(Setting::GyroOdr(parse_gyro_odr(&value()?)?)),
+            "--gyro-fs" => options
+                .settings
+                .push(Setting::GyroFs(parse_gyro_fs(&value()?)?)),
+            "--fifo-mode" => options
+                .settings
+                .push(Setting::FifoMode(parse_fifo_mode(&value()?)?)),
+            "--accel-bdr" => options
+                .settings
+                .push(Setting::AccelBdr(parse_accel_bdr(&value()?)?)),
+            "--gyro-bdr" => options
+                .settings
+                .push(Setting::GyroBdr(parse_gyro_bdr(&value()?)?)),
+            "--format" => {
+                options.format = match value()?.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    other => return Err(format!("unsupported format: {}", other)),
+                }
+            }
+            "--count" => options.count = parse_number(&value()?)?,
+            "--interval-ms" => options.interval = Duration::from_millis(parse_number(&value()?)?),
+            "dump" => command = Some(Command::Dump),
+            "stream" => command = Some(Command::Stream),
+            "fifo" => command = Some(Command::Fifo),
+            "--help" | "-h" => {
+                options.command = Command::Help;
+                return Ok(options);
+            }
+            other => return Err(format!("unknown argument: {}\n{}", other, USAGE)),
+        }
+    }
+
+    options.command = command.ok_or_else(|| USAGE.to_string())?;
+    Ok(options)
+}
+
+fn open(options: &Options) -> Result<Bus, String> {
+    if options.sim {
+        return Ok(Bus::Sim {
+            sim: Box::new(Simulator::with_address(options.address)),
+            spi: options.spi,
+        });
+    }
+
+    let path = match (&options.device, options.spi) {
+        (Some(path), _) => path.as_str(),
+        (None, false) => "/dev/i2c-1",
+        (None, true) => "/dev/spidev0.0",
+    };
+
+    #[cfg(target_os = "linux")]
+    {
+        let bus = match options.spi {
+            false => linux_embedded_hal::I2cdev::new(path)
+                .map(Bus::I2c)
+                .map_err(|e| e.to_string()),
+            true => linux_embedded_hal::SpidevDevice::open(path)
+                .and_then(|mut spi| {
+                    use linux_embedded_hal::spidev::{SpiModeFlags, SpidevOptions};
+
+                    let spi_options = SpidevOptions::new()
+                        .mode(SpiModeFlags::SPI_MODE_3)
+                        .max_speed_hz(10_000_000)
+                        .build();
+                    spi.configure(&spi_options)?;
+                    Ok(Bus::Spi(spi))
+                })
+                .map_err(|e| e.to_string()),
+        };
+        bus.map_err(|e| format!("{}: {}", path, e))
+    }
+    #[cfg(not(target_os = "linux"))]
+    Err(format!(
+        "{}: i2c-dev and spidev are only available on Linux",
+        path
+    ))
+}
+
+fn apply(sensor: &mut Ism330Dhcx, bus: &mut Bus, setting: Setting) -> Result<(), BusError> {
+    match setting {
+        Setting::AccelOdr(odr) => sensor.ctrl1xl.set_accelerometer_data_rate(bus, odr),
+        Setting::AccelFs(fs) => sensor.set_accel_full_scale(bus, fs),
+        Setting::GyroOdr(odr) => sensor.ctrl2g.set_gyroscope_data_rate(bus, odr),
+        Setting::GyroFs(fs) => sensor.set_gyro_full_scale(bus, fs),
+        Setting::FifoMode(mode) => sensor.fifoctrl.mode(bus, mode),
+        Setting::AccelBdr(bdr) => sensor.fifoctrl.set_accelerometer_batch_data_rate(bus, bdr),
+        Setting::GyroBdr(bdr) => sensor.fifoctrl.set_gyroscope_batch_data_rate(bus, bdr),
+    }
+}
+
+fn dump(sensor: &mut Ism330Dhcx, bus: &mut Bus) -> Result<(), BusError> {
+    print!("{}", sensor.dump(bus)?);
+    Ok(())
+}
+
+fn axes(values: [f64; 3], format: Format) -> String {
+    match format {
+        Format::Csv => format!("{:.4},{:.4},{:.4}", values[0], values[1], values[2]),
+        Format::Json => format!("[{:.4},{:.4},{:.4}]", values[0], values[1], values[2]),
+    }
+}
+
+fn stream(sensor: &mut Ism330Dhcx, bus: &mut Bus, options: &Options) -> Result<(), BusError> {
+    let start = Instant::now();
+
+    if options.format == Format::Csv {
+        println!("t_us,temp_c,gx_dps,gy_dps,gz_dps,ax_g,ay_g,az_g");
+    }
+
+    for _ in 0..options.count {
+        bus.wait(options.interval);
+        let elapsed_us = bus.elapsed_us(start);
+
+        let sample = sensor.read_all(bus)?;
+        let temp = match (sample.temp, options.format) {
+            (Some(temp), _) => format!("{:.2}", temp),
+            (None, Format::Csv) => String::new(),
+            (None, Format::Json) => "null".to_string(),
+        };
+        let gyro = axes(sample.gyro.as_dps(), options.format);
+        let accel = axes(sample.accel.as_g(), options.format);
+
+        match options.format {
+            Format::Csv => println!("{},{},{},{}", elapsed_us, temp, gyro, accel),
+            Format::Json => println!(
+                "{{\"t_us\":{},\"temp_c\":{},\"gyro_dps\":{},\"accel_g\":{}}}",
+                elapsed_us, temp, gyro, accel
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+fn drain(sensor: &mut Ism330Dhcx, bus: &mut Bus, options: &Options) -> Result<(), BusError> {
+    if options.format == Format::Csv {
+        println!("kind,x,y,z");
+    }
+
+    for _ in 0..options.count {
+        bus.wait(options.interval);
+
+        for _ in 0..sensor.fifostatus.diff_fifo(bus)? {
+            let (kind, values) = match sensor.fifo_pop(bus)? {
+                fifo::Value::Empty => break,
+                fifo::Value::Gyro(gyro) => ("gyro_dps", gyro.as_dps()),
+                fifo::Value::Accel(accel) => ("accel_g", accel.as_g()),
+                fifo::Value::Temperature(t) => {
+                    match options.format {
+                        Format::Csv => println!("temp_c,{},,", t),
+                        Format::Json => println!("{{\"kind\":\"temp_c\",\"value\":{}}}", t),
+                    }
+                    continue;
+                }
+                fifo::Value::Timestamp(ts) => {
+                    match options.format {
+                        Format::Csv => println!("timestamp,{},,", ts),
+                        Format::Json => println!("{{\"kind\":\"timestamp\",\"value\":{}}}", ts),
+                    }
+                    continue;
+                }
+                fifo::Value::ConfigChange(_) => {
+                    match options.format {
+                        Format::Csv => println!("config_change,,,"),
+                        Format::Json => println!("{{\"kind\":\"config_change\"}}"),
+                    }
+                    continue;
+                }
+                fifo::Value::Gap { lost } => {
+                    let lost = lost.map_or(String::new(), |n| n.to_string());
+                    match options.format {
+                        Format::Csv => println!("gap,{},,", lost),
+                        Format::Json if lost.is_empty() => println!("{{\"kind\":\"gap\"}}"),
+                        Format::Json => println!("{{\"kind\":\"gap\",\"value\":{}}}", lost),
+                    }
+                    continue;
+                }
+                fifo::Value::Other(tag, _) => {
+                    match options.format {
+                        Format::Csv => println!("tag_{},,,", tag),
+                        Format::Json => println!("{{\"kind\":\"tag_{}\"}}", tag),
+                    }
+                    continue;
+                }
+            };
+
+            match options.format {
+                Format::Csv => println!("{},{}", kind, axes(values, options.format)),
+                Format::Json => println!(
+                    "{{\"kind\":\"{}\",\"value\":{}}}",
+                    kind,
+                    axes(values, options.format)
+                ),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run(mut options: Options) -> Result<(), String> {
+    if let Command::Help = options.command {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let mut bus = open(&options)?;
+    let error = |e: BusError| e.to_string();
+
+    let mut sensor = Ism330Dhcx::new_with_address(&mut bus, options.address).map_err(error)?;
+
+    for setting in std::mem::take(&mut options.settings) {
+        apply(&mut sensor, &mut bus, setting).map_err(error)?;
+    }
+
+    match options.command {
+        Command::Help => Ok(()),
+        Command::Dump => dump(&mut sensor, &mut bus),
+        Command::Stream => stream(&mut sensor, &mut bus, &options),
+        Command::Fifo => drain(&mut sensor, &mut bus, &options),
+    }
+    .map_err(error)
+}
+
+fn main() -> ExitCode {
+    match parse_args(std::env::args().skip(1)).and_then(run) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
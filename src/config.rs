@@ -0,0 +1,713 @@
+//! Whole-device configuration, applied and read back in bursts.
+//!
+//! A [`Config`] describes the data rates, full scales, filters, FIFO and interrupt routing of the
+//! sensor. [`crate::Ism330Dhcx::apply`] validates it and writes it with two auto-increment bursts
+//! (`CTRL1_XL`..`CTRL10_C`, then `FIFO_CTRL1`..`INT2_CTRL`) instead of one write per setting, and
+//! [`crate::Ism330Dhcx::read_config`] recovers it from the device.
+//!
+//! Bits not described by a [`Config`] are read from the device and written back untouched.
+
+use crate::ctrl1xl::{Fs_Xl, Odr_Xl};
+use crate::ctrl2g::{Fs, Odr};
+use crate::fifoctrl::{BdrGy, BdrXl, FifoMode};
+use crate::filter::{AccelFilter, GyroFilter};
+use crate::power::{accel_current_ua, gyro_current_ua, PowerMode};
+use crate::{
+    ctrl1xl, ctrl2g, ctrl3c, ctrl4c, ctrl6c, ctrl7g, ctrl8xl, fifoctrl, int1ctrl, int2ctrl,
+    CONTROL_BLOCK,
+};
+
+/// First register of the control block, `FIFO_CTRL1`.
+pub(crate) const CONTROL_START: u8 = fifoctrl::ADDR;
+
+/// `FIFO_CTRL1` to `INT2_CTRL`, written in one burst.
+const FIFO_BURST: core::ops::Range<usize> = 0..8;
+
+/// `CTRL1_XL` to `CTRL10_C`, written in one burst.
+const CTRL_BURST: core::ops::Range<usize> = 9..CONTROL_BLOCK;
+
+/// The largest FIFO watermark, in words.
+pub const WATERMARK_MAX: u16 = 511;
+
+/// Why a [`Config`] was rejected.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConfigError {
+    /// The FIFO watermark is larger than [`WATERMARK_MAX`].
+    WatermarkOutOfRange,
+    /// The accelerometer is batched faster than it samples, or batched while off.
+    AccelBdrAboveOdr,
+    /// The gyroscope is batched faster than it samples, or batched while off.
+    GyroBdrAboveOdr,
+    /// FIFO compression only makes sense with the FIFO enabled.
+    CompressionWithoutFifo,
+    /// The accelerometer data rate is not available in the requested power mode.
+    AccelModeOdr,
+    /// The gyroscope data rate is not available in the requested power mode.
+    GyroModeOdr,
+    /// Accelerometer high-pass reference mode needs the high-pass path.
+    ReferenceWithoutHighPass,
+    /// The gyroscope high-pass filter only works in high-performance mode.
+    HighPassWithoutHighPerformance,
+    /// A sensor was started with its data rate set to off.
+    SensorOff,
+    /// The BDR counter threshold is 0 or larger than [`crate::counterbdr::THRESHOLD_MAX`].
+    BdrCounterOutOfRange,
+}
+
+/// Accelerometer settings.
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccelConfig {
+    pub odr: Odr_Xl,
+    pub scale: Fs_Xl,
+    /// Operating mode, `XL_HM_MODE` is set for the low-power and normal modes.
+    pub mode: PowerMode,
+    /// Filtering chain, `LPF2_XL_EN` in `CTRL1_XL` and `CTRL8_XL`.
+    pub filter: AccelFilter,
+}
+
+/// Gyroscope settings.
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GyroConfig {
+    pub odr: Odr,
+    pub scale: Fs,
+    /// Operating mode, `G_HM_MODE` is set for the low-power and normal modes.
+    pub mode: PowerMode,
+    /// LPF1 and high-pass filter, `FTYPE` in `CTRL6_C`, `LPF1_SEL_G` in `CTRL4_C` and `CTRL7_G`.
+    pub filter: GyroFilter,
+}
+
+/// FIFO settings.
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FifoConfig {
+    pub mode: FifoMode,
+    pub accel_bdr: BdrXl,
+    pub gyro_bdr: BdrGy,
+    /// FIFO threshold in words, up to [`WATERMARK_MAX`].
+    pub watermark: u16,
+    pub compression: bool,
+    /// Batch a `CFG-Change` word when an ODR, BDR or full scale changes, `ODR_CHG_EN`.
+    pub odr_change: bool,
+}
+
+/// Signals routed to one interrupt pin.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Interrupts {
+    pub accel_data_ready: bool,
+    pub gyro_data_ready: bool,
+    pub fifo_threshold: bool,
+    pub fifo_overrun: bool,
+    pub fifo_full: bool,
+    pub counter_bdr: bool,
+}
+
+/// Complete sensor configuration.
+///
+/// The default is the power-on configuration of the device: both sensors off and the FIFO in bypass.
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Config {
+    pub accel: AccelConfig,
+    pub gyro: GyroConfig,
+    pub fifo: FifoConfig,
+    pub int1: Interrupts,
+    pub int2: Interrupts,
+    /// Block data update, output registers are not updated until MSB and LSB have been read.
+    pub bdu: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            accel: AccelConfig {
+                odr: Odr_Xl::Off,
+                scale: Fs_Xl::G2,
+                mode: PowerMode::HighPerformance,
+                filter: AccelFilter::lpf1(),
+            },
+            gyro: GyroConfig {
+                odr: Odr::Off,
+                scale: Fs::Dps250,
+                mode: PowerMode::HighPerformance,
+                filter: GyroFilter::new(),
+            },
+            fifo: FifoConfig {
+                mode: FifoMode::Bypass,
+                accel_bdr: BdrXl::Off,
+                gyro_bdr: BdrGy::Off,
+                watermark: 0,
+                compression: false,
+                odr_change: false,
+            },
+            int1: Interrupts::default(),
+            int2: Interrupts::default(),
+            bdu: false,
+        }
+    }
+}
+
+/// Index of a register in the control block.
+const fn index(addr: u8) -> usize {
+    (addr - CONTROL_START) as usize
+}
+
+/// Rates of the ODR and BDR codes 0 to 10, in Hz.
+///
+/// Shared by both, so a BDR compares equal to the matching ODR: the datasheet rounds 416.67 Hz to
+/// 416 Hz for the ODR but 417 Hz for the BDR.
+const RATES_HZ: [f32; 11] = [
+    0., 12.5, 26., 52., 104., 208., 416., 833., 1667., 3333., 6667.,
+];
+
+pub(crate) fn accel_odr_hz(odr: Odr_Xl) -> f32 {
+    match odr {
+        Odr_Xl::Hz1_6 => 1.6,
+        odr => RATES_HZ[odr as usize],
+    }
+}
+
+pub(crate) fn gyro_odr_hz(odr: Odr) -> f32 {
+    RATES_HZ[odr as usize]
+}
+
+/// The mode a sensor runs in at `rate_hz` with its `XL_HM_MODE` or `G_HM_MODE` bit.
+///
+/// A sensor that is off keeps the bit only, which decodes to the low-power mode when set.
+fn power_mode(rate_hz: f32, hm_mode: bool) -> PowerMode {
+    PowerMode::at(rate_hz, hm_mode).unwrap_or(match hm_mode {
+        true => PowerMode::LowPower,
+        false => PowerMode::HighPerformance,
+    })
+}
+
+pub(crate) fn bdr_hz(bits: u8) -> f32 {
+    match bits {
+        0b1011 => 6.5,
+        bits => RATES_HZ.get(bits as usize).copied().unwrap_or(0.),
+    }
+}
+
+pub(crate) fn accel_bdr(bits: u8) -> BdrXl {
+    match bits {
+        0b0001 => BdrXl::Hz12_5,
+        0b0010 => BdrXl::Hz26,
+        0b0011 => BdrXl::Hz52,
+        0b0100 => BdrXl::Hz104,
+        0b0101 => BdrXl::Hz208,
+        0b0110 => BdrXl::Hz417,
+        0b0111 => BdrXl::Hz833,
+        0b1000 => BdrXl::Hz1667,
+        0b1001 => BdrXl::Hz3333,
+        0b1010 => BdrXl::Hz6667,
+        0b1011 => BdrXl::Hz6_5,
+        _ => BdrXl::Off,
+    }
+}
+
+pub(crate) fn gyro_bdr(bits: u8) -> BdrGy {
+    match bits {
+        0b0001 => BdrGy::Hz12_5,
+        0b0010 => BdrGy::Hz26,
+        0b0011 => BdrGy::Hz52,
+        0b0100 => BdrGy::Hz104,
+        0b0101 => BdrGy::Hz208,
+        0b0110 => BdrGy::Hz417,
+        0b0111 => BdrGy::Hz833,
+        0b1000 => BdrGy::Hz1667,
+        0b1001 => BdrGy::Hz3333,
+        0b1010 => BdrGy::Hz6667,
+        0b1011 => BdrGy::Hz6_5,
+        _ => BdrGy::Off,
+    }
+}
+
+fn fifo_mode(bits: u8) -> FifoMode {
+    match bits {
+        0b001 => FifoMode::FifoMode,
+        0b011 => FifoMode::ContinuousToFifo,
+        0b100 => FifoMode::BypassToContinuous,
+        0b110 => FifoMode::Continuous,
+        0b111 => FifoMode::BypassToFifo,
+        _ => FifoMode::Bypass,
+    }
+}
+
+/// Interrupt bits shared by `INT1_CTRL` and `INT2_CTRL`.
+const INTERRUPTS_MASK: u8 = 0b0111_1011;
+
+impl Interrupts {
+    fn bits(&self) -> u8 {
+        u8::from(self.accel_data_ready) << int1ctrl::INT1_DRDY_XL
+            | u8::from(self.gyro_data_ready) << int1ctrl::INT1_DRDY_G
+            | u8::from(self.fifo_threshold) << int1ctrl::INT1_FIFO_TH
+            | u8::from(self.fifo_overrun) << int1ctrl::INT1_FIFO_OVR
+            | u8::from(self.fifo_full) << int1ctrl::INT1_FIFO_FULL
+            | u8::from(self.counter_bdr) << int1ctrl::INT1_CNT_BDR
+    }
+
+    fn from_bits(bits: u8) -> Self {
+        Interrupts {
+            accel_data_ready: bits & (1 << int2ctrl::INT2_DRDY_XL) != 0,
+            gyro_data_ready: bits & (1 << int2ctrl::INT2_DRDY_G) != 0,
+            fifo_threshold: bits & (1 << int2ctrl::INT2_FIFO_TH) != 0,
+            fifo_overrun: bits & (1 << int2ctrl::INT2_FIFO_OVR) != 0,
+            fifo_full: bits & (1 << int2ctrl::INT2_FIFO_FULL) != 0,
+            counter_bdr: bits & (1 << int2ctrl::INT2_CNT_BDR) != 0,
+        }
+    }
+}
+
+impl Config {
+    /// Check that the settings can work together.
+    ///
+    /// Each data rate must be available in the power mode of its sensor, so the 1.6 Hz
+    /// accelerometer rate needs [`PowerMode::LowPower`].
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.fifo.watermark > WATERMARK_MAX {
+            return Err(ConfigError::WatermarkOutOfRange);
+        }
+
+        accel_current_ua(self.accel.odr, self.accel.mode)?;
+        gyro_current_ua(self.gyro.odr, self.gyro.mode)?;
+        self.accel.filter.validate()?;
+
+        if self.gyro.filter.high_pass.is_some() && self.gyro.mode != PowerMode::HighPerformance {
+            return Err(ConfigError::HighPassWithoutHighPerformance);
+        }
+
+        if bdr_hz(self.fifo.accel_bdr as u8) > accel_odr_hz(self.accel.odr) {
+            return Err(ConfigError::AccelBdrAboveOdr);
+        }
+
+        if bdr_hz(self.fifo.gyro_bdr as u8) > gyro_odr_hz(self.gyro.odr) {
+            return Err(ConfigError::GyroBdrAboveOdr);
+        }
+
+        if self.fifo.compression && self.fifo.mode == FifoMode::Bypass {
+            return Err(ConfigError::CompressionWithoutFifo);
+        }
+
+        Ok(())
+    }
+
+    /// Decode the settings from the control block, `FIFO_CTRL1` to `CTRL10_C`.
+    ///
+    /// Reserved values decode to their power-on default, and the 1.6 Hz accelerometer code to the
+    /// data rate it runs at in the power mode set by `CTRL6_C`. The power modes are the ones the
+    /// sensors run in, see [`PowerMode::at`].
+    pub(crate) fn decode(registers: &[u8; CONTROL_BLOCK]) -> Self {
+        let fifo_ctrl2 = registers[index(fifoctrl::ADDR + 1)];
+        let fifo_ctrl3 = registers[index(fifoctrl::ADDR + 2)];
+        let ctrl1_xl = registers[index(ctrl1xl::ADDR)];
+        let ctrl2_g = registers[index(ctrl2g::ADDR)];
+        let ctrl4_c = registers[index(ctrl4c::ADDR)];
+        let ctrl6_c = registers[index(ctrl6c::ADDR)];
+        let ctrl7_g = registers[index(ctrl7g::ADDR)];
+        let ctrl8_xl = registers[index(ctrl8xl::ADDR)];
+
+        let gyro_scale = if ctrl2_g & (1 << ctrl2g::FS4000) != 0 {
+            Fs::Dps4000
+        } else if ctrl2_g & (1 << ctrl2g::FS125) != 0 {
+            Fs::Dps125
+        } else {
+            [Fs::Dps250, Fs::Dps500, Fs::Dps1000, Fs::Dps2000][(ctrl2_g >> 2 & 0b11) as usize]
+        };
+
+        let xl_hm_mode = ctrl6_c & (1 << ctrl6c::XL_HM_MODE) != 0;
+        let accel_odr = ctrl1xl::Ctrl1Xl::new(ctrl1_xl, 0).effective_odr(xl_hm_mode);
+        let gyro_odr = ctrl2g::Ctrl2G::new(ctrl2_g, 0).gyroscope_odr();
+
+        Config {
+            accel: AccelConfig {
+                odr: accel_odr,
+                scale: [Fs_Xl::G2, Fs_Xl::G16, Fs_Xl::G4, Fs_Xl::G8]
+                    [(ctrl1_xl >> 2 & 0b11) as usize],
+                mode: power_mode(accel_odr_hz(accel_odr), xl_hm_mode),
+                filter: AccelFilter::from_registers(
+                    &ctrl1xl::Ctrl1Xl::new(ctrl1_xl, 0),
+                    &ctrl8xl::Ctrl8Xl::new(ctrl8_xl, 0),
+                ),
+            },
+            gyro: GyroConfig {
+                odr: gyro_odr,
+                scale: gyro_scale,
+                mode: power_mode(
+                    gyro_odr_hz(gyro_odr),
+                    ctrl7_g & (1 << ctrl7g::G_HM_MODE) != 0,
+                ),
+                filter: GyroFilter::from_registers(
+                    &ctrl4c::Ctrl4C::new(ctrl4_c, 0),
+                    &ctrl6c::Ctrl6C::new(ctrl6_c, 0),
+                    &ctrl7g::Ctrl7G::new(ctrl7_g, 0),
+                ),
+            },
+            fifo: FifoConfig {
+                mode: fifo_mode(registers[index(fifoctrl::ADDR + 3)] & 0b111),
+                accel_bdr: accel_bdr(fifo_ctrl3 & 0b1111),
+                gyro_bdr: gyro_bdr(fifo_ctrl3 >> 4),
+                watermark: u16::from(registers[index(fifoctrl::ADDR)])
+                    | u16::from(fifo_ctrl2 & 1) << 8,
+                compression: fifo_ctrl2 & (1 << 6) != 0,
+                odr_change: fifo_ctrl2 & (1 << 4) != 0,
+            },
+            int1: Interrupts::from_bits(registers[index(int1ctrl::ADDR)]),
+            int2: Interrupts::from_bits(registers[index(int2ctrl::ADDR)]),
+            bdu: registers[index(ctrl3c::ADDR)] & (1 << ctrl3c::BDU) != 0,
+        }
+    }
+
+    /// Encode the settings into the control block, leaving other bits untouched.
+    ///
+    /// `IF_INC` is always set since the driver relies on bursts, and the self-clearing `BOOT` and
+    /// `SW_RESET` bits are always cleared.
+    pub(crate) fn encode(&self, registers: &mut [u8; CONTROL_BLOCK]) {
+        let mut set = |addr: u8, mask: u8, bits: u8| {
+            let r = &mut registers[index(addr)];
+            *r = (*r & !mask) | (bits & mask);
+        };
+
+        set(fifoctrl::ADDR, 0xff, self.fifo.watermark as u8);
+        set(
+            fifoctrl::ADDR + 1,
+            0b0101_0001,
+            u8::from(self.fifo.compression) << 6
+                | u8::from(self.fifo.odr_change) << 4
+                | (self.fifo.watermark >> 8) as u8,
+        );
+        set(
+            fifoctrl::ADDR + 2,
+            0xff,
+            (self.fifo.gyro_bdr as u8) << 4 | self.fifo.accel_bdr as u8,
+        );
+        set(fifoctrl::ADDR + 3, 0b111, self.fifo.mode as u8);
+        set(int1ctrl::ADDR, INTERRUPTS_MASK, self.int1.bits());
+        set(int2ctrl::ADDR, INTERRUPTS_MASK, self.int2.bits());
+
+        let (lpf2, slope, cutoff) = self.accel.filter.path_bits();
+        set(
+            ctrl1xl::ADDR,
+            0b1111_1110,
+            (self.accel.odr as u8) << 4 | (self.accel.scale as u8) << 2 | u8::from(lpf2) << 1,
+        );
+
+        let gyro_scale = match self.gyro.scale {
+            Fs::Dps4000 => 1 << ctrl2g::FS4000,
+            Fs::Dps125 => 1 << ctrl2g::FS125,
+            scale => (scale as u8) << 2,
+        };
+        set(ctrl2g::ADDR, 0xff, (self.gyro.odr as u8) << 4 | gyro_scale);
+
+        set(
+            ctrl3c::ADDR,
+            1 << ctrl3c::BOOT | 1 << ctrl3c::BDU | 1 << ctrl3c::IF_INC | 1 << ctrl3c::SW_RESET,
+            u8::from(self.bdu) << ctrl3c::BDU | 1 << ctrl3c::IF_INC,
+        );
+
+        set(
+            ctrl4c::ADDR,
+            1 << ctrl4c::LPF1_SEL_G,
+            u8::from(self.gyro.filter.lpf1.is_some()) << ctrl4c::LPF1_SEL_G,
+        );
+
+        // FTYPE is left alone while LPF1 is disabled, it only matters once it is selected.
+        let ftype = match self.gyro.filter.lpf1 {
+            Some(ftype) => (0b111, ftype as u8),
+            None => (0, 0),
+        };
+        set(
+            ctrl6c::ADDR,
+            1 << ctrl6c::XL_HM_MODE | ftype.0,
+            u8::from(self.accel.mode != PowerMode::HighPerformance) << ctrl6c::XL_HM_MODE | ftype.1,
+        );
+
+        let high_pass = match self.gyro.filter.high_pass {
+            Some(cutoff) => 1 << ctrl7g::HP_EN_G | (cutoff as u8) << 4,
+            None => 0,
+        };
+        set(
+            ctrl7g::ADDR,
+            1 << ctrl7g::G_HM_MODE | 1 << ctrl7g::HP_EN_G | 0b11 << 4,
+            u8::from(self.gyro.mode != PowerMode::HighPerformance) << ctrl7g::G_HM_MODE | high_pass,
+        );
+
+        set(
+            ctrl8xl::ADDR,
+            0b1111_1100,
+            (cutoff as u8) << 5
+                | u8::from(self.accel.filter.reference) << ctrl8xl::HP_REF_MODE_XL
+                | u8::from(self.accel.filter.fast_settling) << ctrl8xl::FASTSETTL_MODE_XL
+                | u8::from(slope) << ctrl8xl::HP_SLOPE_XL_EN,
+        );
+    }
+
+    /// Split the control block into the bursts written by [`crate::Ism330Dhcx::apply`], each
+    /// prefixed with its start address.
+    pub(crate) fn bursts(registers: &[u8; CONTROL_BLOCK]) -> ([u8; 11], [u8; 9]) {
+        let mut ctrl = [0u8; 11];
+        ctrl[0] = ctrl1xl::ADDR;
+        ctrl[1..].copy_from_slice(&registers[CTRL_BURST]);
+
+        let mut fifo = [0u8; 9];
+        fifo[0] = CONTROL_START;
+        fifo[1..].copy_from_slice(&registers[FIFO_BURST]);
+
+        (ctrl, fifo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctrl6c::Ftype;
+    use crate::ctrl7g::Hpm_g;
+    use crate::ctrl8xl::Hpcf_Xl;
+    use crate::sim::Simulator;
+    use crate::{Error, Ism330Dhcx};
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    fn power_on() -> [u8; CONTROL_BLOCK] {
+        let mut registers = [0u8; CONTROL_BLOCK];
+        registers[index(ctrl3c::ADDR)] = 0b0000_0100;
+        registers[index(0x0f)] = 0x6b;
+        registers[index(0x18)] = 0b1110_0000;
+        registers
+    }
+
+    fn sample() -> Config {
+        Config {
+            accel: AccelConfig {
+                odr: Odr_Xl::Hz104,
+                scale: Fs_Xl::G8,
+                mode: PowerMode::Normal,
+                filter: AccelFilter::lpf2(Hpcf_Xl::Odr45).fast_settling(true),
+            },
+            gyro: GyroConfig {
+                odr: Odr::Hz208,
+                scale: Fs::Dps125,
+                mode: PowerMode::HighPerformance,
+                filter: GyroFilter::new()
+                    .lpf1(Ftype::Ftype101)
+                    .high_pass(Hpm_g::Hpmg260),
+            },
+            fifo: FifoConfig {
+                mode: FifoMode::Continuous,
+                accel_bdr: BdrXl::Hz52,
+                gyro_bdr: BdrGy::Hz208,
+                watermark: 300,
+                compression: true,
+                odr_change: true,
+            },
+            int1: Interrupts {
+                fifo_threshold: true,
+                ..Default::default()
+            },
+            int2: Interrupts {
+                accel_data_ready: true,
+                gyro_data_ready: true,
+                ..Default::default()
+            },
+            bdu: true,
+        }
+    }
+
+    #[test]
+    fn default_is_power_on() {
+        assert_eq!(Config::decode(&power_on()), Config::default());
+    }
+
+    #[test]
+    fn encode_decode() {
+        for config in [Config::default(), sample()] {
+            let mut registers = power_on();
+            config.encode(&mut registers);
+            assert_eq!(Config::decode(&registers), config);
+        }
+    }
+
+    #[test]
+    fn encode_keeps_other_bits() {
+        let mut registers = power_on();
+        registers[index(ctrl3c::ADDR)] |= 1 << ctrl3c::SW_RESET | 1 << ctrl3c::H_LACTIVE;
+        sample().encode(&mut registers);
+
+        assert_eq!(registers[index(0x18)], 0b1110_0000);
+        assert_eq!(registers[index(ctrl3c::ADDR)], 0b0110_0100);
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(sample().validate(), Ok(()));
+
+        let mut c = sample();
+        c.fifo.watermark = 512;
+        assert_eq!(c.validate(), Err(ConfigError::WatermarkOutOfRange));
+
+        let mut c = sample();
+        c.fifo.accel_bdr = BdrXl::Hz208;
+        assert_eq!(c.validate(), Err(ConfigError::AccelBdrAboveOdr));
+
+        let mut c = sample();
+        c.gyro.odr = Odr::Off;
+        assert_eq!(c.validate(), Err(ConfigError::GyroBdrAboveOdr));
+
+        let mut c = sample();
+        c.fifo.mode = FifoMode::Bypass;
+        assert_eq!(c.validate(), Err(ConfigError::CompressionWithoutFifo));
+
+        let mut c = sample();
+        c.accel.odr = Odr_Xl::Hz1_6;
+        c.fifo.accel_bdr = BdrXl::Off;
+        assert_eq!(c.validate(), Err(ConfigError::AccelModeOdr));
+
+        let mut c = sample();
+        c.gyro.mode = PowerMode::LowPower;
+        assert_eq!(c.validate(), Err(ConfigError::GyroModeOdr));
+
+        let mut c = sample();
+        c.accel.filter = c.accel.filter.reference(true);
+        assert_eq!(c.validate(), Err(ConfigError::ReferenceWithoutHighPass));
+
+        let mut c = sample();
+        c.gyro.odr = Odr::Hz104;
+        c.gyro.mode = PowerMode::Normal;
+        c.fifo.gyro_bdr = BdrGy::Hz104;
+        assert_eq!(
+            c.validate(),
+            Err(ConfigError::HighPassWithoutHighPerformance)
+        );
+    }
+
+    #[test]
+    fn bdr_at_odr() {
+        let accel = [
+            (Odr_Xl::Hz125, BdrXl::Hz12_5),
+            (Odr_Xl::Hz26, BdrXl::Hz26),
+            (Odr_Xl::Hz52, BdrXl::Hz52),
+            (Odr_Xl::Hz104, BdrXl::Hz104),
+            (Odr_Xl::Hz208, BdrXl::Hz208),
+            (Odr_Xl::Hz416, BdrXl::Hz417),
+            (Odr_Xl::Hz833, BdrXl::Hz833),
+            (Odr_Xl::Hz1667, BdrXl::Hz1667),
+            (Odr_Xl::Hz3333, BdrXl::Hz3333),
+            (Odr_Xl::Hz6667, BdrXl::Hz6667),
+        ];
+        let gyro = [
+            (Odr::Hz125, BdrGy::Hz12_5),
+            (Odr::Hz26, BdrGy::Hz26),
+            (Odr::Hz52, BdrGy::Hz52),
+            (Odr::Hz104, BdrGy::Hz104),
+            (Odr::Hz208, BdrGy::Hz208),
+            (Odr::Hz416, BdrGy::Hz417),
+            (Odr::Hz833, BdrGy::Hz833),
+            (Odr::Hz1667, BdrGy::Hz1667),
+            (Odr::Hz3333, BdrGy::Hz3333),
+            (Odr::Hz6667, BdrGy::Hz6667),
+        ];
+
+        for ((accel_odr, accel_bdr), (gyro_odr, gyro_bdr)) in accel.into_iter().zip(gyro) {
+            let mut c = sample();
+            c.accel.odr = accel_odr;
+            c.fifo.accel_bdr = accel_bdr;
+            c.gyro.odr = gyro_odr;
+            c.fifo.gyro_bdr = gyro_bdr;
+            c.accel.mode = PowerMode::HighPerformance;
+            assert_eq!(c.validate(), Ok(()), "{:?}", accel_odr);
+        }
+    }
+
+    #[test]
+    fn apply_in_bursts() {
+        let mut registers = power_on();
+        sample().encode(&mut registers);
+        let (ctrl, fifo) = Config::bursts(&registers);
+
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x6b, vec![0x07], power_on().to_vec()),
+            Transaction::write_read(0x6b, vec![0x07], power_on().to_vec()),
+            Transaction::write(0x6b, ctrl.to_vec()),
+            Transaction::write(0x6b, fifo.to_vec()),
+        ]);
+
+        let mut sensor = Ism330Dhcx::new(&mut i2c).unwrap();
+        sensor.apply(&mut i2c, &sample()).unwrap();
+        assert_eq!(sensor.ctrl1xl.accelerometer_data_rate(), 104.);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn apply_rejects_before_writing() {
+        let mut i2c = Mock::new(&[Transaction::write_read(
+            0x6b,
+            vec![0x07],
+            power_on().to_vec(),
+        )]);
+
+        let mut sensor = Ism330Dhcx::new(&mut i2c).unwrap();
+        let mut config = sample();
+        config.fifo.watermark = 1000;
+        assert!(matches!(
+            sensor.apply(&mut i2c, &config),
+            Err(Error::InvalidConfig(ConfigError::WatermarkOutOfRange))
+        ));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn apply_read_config() {
+        let mut sim = Simulator::new();
+
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor.apply(&mut sim, &sample()).unwrap();
+        assert_eq!(sensor.read_config(&mut sim).unwrap(), sample());
+
+        let mut other = Ism330Dhcx::new(&mut sim).unwrap();
+        assert_eq!(other.read_config(&mut sim).unwrap(), sample());
+        assert_eq!(other.ctrl2g.chain_full_scale(), Fs::Dps125);
+        assert_eq!(other.accel_filter(), sample().accel.filter);
+        assert_eq!(other.gyro_filter(), sample().gyro.filter);
+
+        let mut config = sample();
+        config.accel.filter = AccelFilter::high_pass(Hpcf_Xl::Odr100).reference(true);
+        config.gyro.filter = GyroFilter::new();
+        sensor.apply(&mut sim, &config).unwrap();
+        assert_eq!(
+            sim.register(ctrl8xl::ADDR),
+            0b100 << 5 | 1 << ctrl8xl::HP_REF_MODE_XL | 1 << ctrl8xl::HP_SLOPE_XL_EN
+        );
+        assert_eq!(sim.register(ctrl4c::ADDR) & 1 << ctrl4c::LPF1_SEL_G, 0);
+        assert_eq!(sensor.read_config(&mut sim).unwrap(), config);
+    }
+
+    #[test]
+    fn low_power_round_trip() {
+        let mut config = sample();
+        config.accel.odr = Odr_Xl::Hz1_6;
+        config.accel.mode = PowerMode::LowPower;
+        config.fifo.accel_bdr = BdrXl::Off;
+        config.gyro.odr = Odr::Hz26;
+        config.gyro.mode = PowerMode::LowPower;
+        config.gyro.filter = GyroFilter::new().lpf1(Ftype::Ftype010);
+        config.fifo.gyro_bdr = BdrGy::Hz26;
+
+        let mut sim = Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor.apply(&mut sim, &config).unwrap();
+        assert_eq!(sim.register(ctrl6c::ADDR), 1 << ctrl6c::XL_HM_MODE | 0b010);
+        assert_eq!(sensor.accel_data_rate_hz(), 1.6);
+        assert_eq!(sensor.read_config(&mut sim).unwrap(), config);
+
+        // In high-performance mode the same code would run at 12.5 Hz.
+        config.accel.mode = PowerMode::HighPerformance;
+        assert!(matches!(
+            sensor.apply(&mut sim, &config),
+            Err(Error::InvalidConfig(ConfigError::AccelModeOdr))
+        ));
+        assert_eq!(sim.register(ctrl6c::ADDR), 1 << ctrl6c::XL_HM_MODE | 0b010);
+    }
+}
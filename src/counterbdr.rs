@@ -0,0 +1,191 @@
+use core::fmt;
+
+use crate::Register;
+
+/// The COUNTER_BDR_REG1 and COUNTER_BDR_REG2 registers.
+///
+/// Handled as one, since the batch data rate counter threshold is split across both. The first
+/// register also selects pulsed data-ready.
+pub struct CounterBdr {
+    pub address: u8,
+    value: [u8; 2],
+}
+
+impl fmt::Display for CounterBdr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for r in self.value.iter() {
+            write!(f, "{}", r)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Binary for CounterBdr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for r in self.value.iter() {
+            write!(f, "{:b}", r)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::LowerHex for CounterBdr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for r in self.value.iter() {
+            fmt::LowerHex::fmt(&r, f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Sub-address of COUNTER_BDR_REG1, COUNTER_BDR_REG2 follows.
+pub const ADDR: u8 = 0x0bu8;
+
+/// Enables pulsed data-ready mode.
+///
+/// Data-ready and the BDR counter interrupt are pulses of about 75 µs instead of staying up until
+/// the data is read.
+///
+/// Default value: 0
+///
+/// (0: data-ready latched mode; 1: data-ready pulsed mode)
+pub const DATAREADY_PULSED: u8 = 7;
+
+/// Resets the internal counter of batch events for a single sensor. Self-clearing.
+///
+/// Default value: 0
+pub const RST_COUNTER_BDR: u8 = 6;
+
+/// Selects the trigger for the internal counter of batch events.
+///
+/// Default value: 0
+///
+/// (0: accelerometer batch event; 1: gyroscope batch event)
+pub const TRIG_COUNTER_BDR: u8 = 5;
+
+/// The largest BDR counter threshold, `CNT_BDR_TH_[10:0]`.
+pub const THRESHOLD_MAX: u16 = 2047;
+
+/// Sensor whose batch events are counted.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrigCounterBdr {
+    Accelerometer,
+    Gyroscope,
+}
+
+impl Register for CounterBdr {}
+
+impl CounterBdr {
+    pub fn new(value: [u8; 2], address: u8) -> Self {
+        CounterBdr { address, value }
+    }
+
+    /// Cached value of the registers.
+    pub fn value(&self) -> [u8; 2] {
+        self.value
+    }
+
+    pub fn dataready_pulsed(&self) -> bool {
+        self.value[0] & (1 << DATAREADY_PULSED) != 0
+    }
+
+    pub fn set_dataready_pulsed<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        value: bool,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value[0] &= !(1 << DATAREADY_PULSED);
+        self.value[0] |= (value as u8) << DATAREADY_PULSED;
+        self.write(i2c, self.address, ADDR, self.value[0])
+    }
+
+    pub fn trig_counter_bdr(&self) -> TrigCounterBdr {
+        match self.value[0] & (1 << TRIG_COUNTER_BDR) != 0 {
+            true => TrigCounterBdr::Gyroscope,
+            false => TrigCounterBdr::Accelerometer,
+        }
+    }
+
+    pub fn set_trig_counter_bdr<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        value: TrigCounterBdr,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value[0] &= !(1 << TRIG_COUNTER_BDR);
+        self.value[0] |= (value as u8) << TRIG_COUNTER_BDR;
+        self.write(i2c, self.address, ADDR, self.value[0])
+    }
+
+    /// BDR counter threshold, in batch events.
+    pub fn threshold(&self) -> u16 {
+        u16::from(self.value[0] & 0b111) << 8 | u16::from(self.value[1])
+    }
+
+    /// Set the BDR counter threshold, in batch events. Only the 11 low bits are used, up to
+    /// [`THRESHOLD_MAX`].
+    pub fn set_threshold<I2C>(&mut self, i2c: &mut I2C, value: u16) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value[0] &= !0b111;
+        self.value[0] |= (value >> 8) as u8 & 0b111;
+        self.value[1] = value as u8;
+        self.write(i2c, self.address, ADDR, self.value[0])?;
+        self.write(i2c, self.address, ADDR + 1, self.value[1])
+    }
+
+    /// Restart counting batch events from zero.
+    pub fn reset_counter<I2C>(&mut self, i2c: &mut I2C) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.write(
+            i2c,
+            self.address,
+            ADDR,
+            self.value[0] | 1 << RST_COUNTER_BDR,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    #[test]
+    fn threshold_and_reset() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(0x6b, vec![0x0b, 0b0010_0000]),
+            Transaction::write(0x6b, vec![0x0b, 0b0010_0101]),
+            Transaction::write(0x6b, vec![0x0c, 0x12]),
+            Transaction::write(0x6b, vec![0x0b, 0b0110_0101]),
+            Transaction::write(0x6b, vec![0x0b, 0b1010_0101]),
+        ]);
+        let mut r = CounterBdr::new([0; 2], crate::DEFAULT_I2C_ADDRESS);
+
+        r.set_trig_counter_bdr(&mut i2c, TrigCounterBdr::Gyroscope)
+            .unwrap();
+        r.set_threshold(&mut i2c, 0x512).unwrap();
+        assert_eq!(r.threshold(), 0x512);
+        assert_eq!(r.trig_counter_bdr(), TrigCounterBdr::Gyroscope);
+
+        r.reset_counter(&mut i2c).unwrap();
+        assert_eq!(r.value()[0], 0b0010_0101);
+
+        r.set_dataready_pulsed(&mut i2c, true).unwrap();
+        assert!(r.dataready_pulsed());
+
+        i2c.done();
+    }
+}
@@ -0,0 +1,73 @@
+use core::fmt;
+
+use crate::Register;
+
+/// The CTRL10_C (control 10) register
+pub struct Ctrl10C {
+    pub address: u8,
+    value: u8,
+}
+
+impl fmt::Display for Ctrl10C {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl fmt::Binary for Ctrl10C {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:b}", self.value)
+    }
+}
+
+impl fmt::LowerHex for Ctrl10C {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.value, f)
+    }
+}
+
+pub const ADDR: u8 = 0x19u8;
+
+/// Enables timestamp counter. The counter is readable in TIMESTAMP0 (40h), TIMESTAMP1 (41h),
+/// TIMESTAMP2 (42h) and TIMESTAMP3 (43h).
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const TIMESTAMP_EN: u8 = 5;
+
+/// Sub-address of TIMESTAMP0, the low byte of the 32-bit timestamp counter in TIMESTAMP0 to
+/// TIMESTAMP3.
+pub const TIMESTAMP0: u8 = 0x40u8;
+
+/// Writing [`TIMESTAMP_RESET`] to TIMESTAMP2 resets the timestamp counter.
+pub const TIMESTAMP2: u8 = 0x42u8;
+
+/// Value written to TIMESTAMP2 to reset the timestamp counter.
+pub const TIMESTAMP_RESET: u8 = 0xaau8;
+
+impl Register for Ctrl10C {}
+
+impl Ctrl10C {
+    pub fn new(value: u8, address: u8) -> Self {
+        Ctrl10C { address, value }
+    }
+
+    /// Cached value of the register.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn timestamp_en(&self) -> bool {
+        self.value & (1 << TIMESTAMP_EN) != 0
+    }
+
+    pub fn set_timestamp_en<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << TIMESTAMP_EN);
+        self.value |= (value as u8) << TIMESTAMP_EN;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+}
@@ -8,6 +8,7 @@ use crate::Register;
 pub struct Ctrl1Xl {
     pub address: u8,
     value: u8,
+    parked: Odr_Xl,
 }
 
 impl fmt::Display for Ctrl1Xl {
@@ -46,6 +47,7 @@ const FS_OFFSET: u8 = 2;
 /// (00: ±2 g; 01: ±16 g; 10: ±4 g; 11: ±8 g)
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Fs_Xl {
     G2,  // ±2  g
     G16, // ±16 g
@@ -64,6 +66,16 @@ impl Fs_Xl {
         }
     }
 
+    /// Sensitivity micro-g / LSB, exact integer form of [`Fs_Xl::sensitivity`].
+    pub fn sensitivity_ug(&self) -> i32 {
+        match self {
+            Fs_Xl::G2 => 61,
+            Fs_Xl::G4 => 122,
+            Fs_Xl::G8 => 244,
+            Fs_Xl::G16 => 488,
+        }
+    }
+
     pub fn g(&self) -> f32 {
         match self {
             Fs_Xl::G2 => 2.,
@@ -80,8 +92,13 @@ const ODR_XL_OFFSET: u8 = 4;
 /// Accelerometer ODR
 ///
 /// Default value: `Off`
+///
+/// `Hz1_6` only exists in low-power mode, the same code selects 12.5 Hz in high-performance mode,
+/// see [`Ctrl1Xl::effective_odr`]. The variants are ordered by data rate, `Hz1_6` between `Off`
+/// and `Hz125`.
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Odr_Xl {
     Off,    // off
     Hz125,  // 12.5 Hz
@@ -94,15 +111,90 @@ pub enum Odr_Xl {
     Hz1667, // 1.66 kHz
     Hz3333, // 3.33 kHz
     Hz6667, // 6.66 kHz
+    Hz1_6,  // 1.6  Hz, low-power mode only
+}
+
+impl Odr_Xl {
+    /// Position by data rate, the register code puts `Hz1_6` last.
+    fn rank(self) -> u8 {
+        match self {
+            Odr_Xl::Off => 0,
+            Odr_Xl::Hz1_6 => 1,
+            odr => odr as u8 + 1,
+        }
+    }
+}
+
+impl Ord for Odr_Xl {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl PartialOrd for Odr_Xl {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl Register for Ctrl1Xl {}
 
 impl Ctrl1Xl {
     pub fn new(value: u8, address: u8) -> Self {
-        Ctrl1Xl { value, address }
+        Ctrl1Xl {
+            value,
+            address,
+            parked: Odr_Xl::Off,
+        }
+    }
+
+    /// Cached value of the register.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    /// Replace the cached value with `value` read from the device.
+    ///
+    /// The parked data rate is kept while the accelerometer stays off, and dropped once it runs again.
+    pub(crate) fn refresh(&mut self, value: u8) {
+        self.value = value;
+        if self.accelerometer_odr() != Odr_Xl::Off {
+            self.parked = Odr_Xl::Off;
+        }
     }
 
+    /// The data rate [`Ctrl1Xl::resume`] goes back to, `Off` when not parked.
+    pub fn parked_odr(&self) -> Odr_Xl {
+        self.parked
+    }
+
+    /// Power the accelerometer down, remembering its data rate.
+    pub fn park<I2C>(&mut self, i2c: &mut I2C) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let odr = self.accelerometer_odr();
+        if odr != Odr_Xl::Off {
+            self.set_accelerometer_data_rate(i2c, Odr_Xl::Off)?;
+            self.parked = odr;
+        }
+        Ok(())
+    }
+
+    /// Restore the data rate saved by [`Ctrl1Xl::park`], if any.
+    pub fn resume<I2C>(&mut self, i2c: &mut I2C) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        if self.parked != Odr_Xl::Off {
+            self.set_accelerometer_data_rate(i2c, self.parked)?;
+            self.parked = Odr_Xl::Off;
+        }
+        Ok(())
+    }
+
+    /// Data rate of the ODR_XL code, 1.6 Hz for code 11 whatever the power mode, see
+    /// [`Ctrl1Xl::effective_odr`].
     pub fn accelerometer_data_rate(&self) -> f32 {
         match (self.value >> ODR_XL_OFFSET) & ODR_XL_MASK {
             0 => 0.0,
@@ -116,10 +208,37 @@ impl Ctrl1Xl {
             8 => 1667.0,
             9 => 3333.0,
             10 => 6667.0,
+            11 => 1.6,
             _ => panic!("Unreachable"),
         }
     }
 
+    pub fn accelerometer_odr(&self) -> Odr_Xl {
+        match (self.value >> ODR_XL_OFFSET) & ODR_XL_MASK {
+            1 => Odr_Xl::Hz125,
+            2 => Odr_Xl::Hz26,
+            3 => Odr_Xl::Hz52,
+            4 => Odr_Xl::Hz104,
+            5 => Odr_Xl::Hz208,
+            6 => Odr_Xl::Hz416,
+            7 => Odr_Xl::Hz833,
+            8 => Odr_Xl::Hz1667,
+            9 => Odr_Xl::Hz3333,
+            10 => Odr_Xl::Hz6667,
+            11 => Odr_Xl::Hz1_6,
+            _ => Odr_Xl::Off,
+        }
+    }
+
+    /// The data rate in effect, with `xl_hm_mode` from CTRL6_C: code 11 runs at 12.5 Hz when
+    /// it leaves high-performance mode on, and only means 1.6 Hz in low-power mode.
+    pub fn effective_odr(&self, xl_hm_mode: bool) -> Odr_Xl {
+        match self.accelerometer_odr() {
+            Odr_Xl::Hz1_6 if !xl_hm_mode => Odr_Xl::Hz125,
+            odr => odr,
+        }
+    }
+
     pub fn set_accelerometer_data_rate<I2C>(
         &mut self,
         i2c: &mut I2C,
@@ -143,6 +262,8 @@ impl Ctrl1Xl {
         }
     }
 
+    /// Samples already in the FIFO are decoded with the new full scale, use
+    /// [`crate::Ism330Dhcx::set_accel_full_scale`] to keep them on the old one.
     pub fn set_chain_full_scale<I2C>(
         &mut self,
         i2c: &mut I2C,
@@ -156,7 +277,7 @@ impl Ctrl1Xl {
         self.write(i2c, self.address, ADDR, self.value)
     }
 
-    pub fn lpf2_xl_en(&mut self) -> bool {
+    pub fn lpf2_xl_en(&self) -> bool {
         self.value & (1 << LPF2_XL_EN) != 0
     }
 
@@ -8,6 +8,7 @@ use crate::Register;
 pub struct Ctrl2G {
     pub address: u8,
     value: u8,
+    parked: Odr,
 }
 
 impl fmt::Display for Ctrl2G {
@@ -46,6 +47,7 @@ const FS_OFFSET: u8 = 2;
 
 /// Gyroscope chain full-scale selection in dps
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Fs {
     Dps250,  // ±250 dps
     Dps500,  // ±500 dps
@@ -68,6 +70,18 @@ impl Fs {
         }
     }
 
+    /// Sensitivity micro-dps / LSB, exact integer form of [`Fs::sensitivity`].
+    pub fn sensitivity_udps(&self) -> i32 {
+        match self {
+            Fs::Dps125 => 4_375,
+            Fs::Dps250 => 8_750,
+            Fs::Dps500 => 17_500,
+            Fs::Dps1000 => 35_000,
+            Fs::Dps2000 => 70_000,
+            Fs::Dps4000 => 140_000,
+        }
+    }
+
     pub fn dps(&self) -> f32 {
         match self {
             Fs::Dps125 => 125.,
@@ -86,7 +100,8 @@ const ODR_OFFSET: u8 = 4;
 /// Gyroscope ODR selection
 ///
 /// Default value: `Off`
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Odr {
     Off,    // off
     Hz125,  // 12.5 Hz
@@ -105,7 +120,56 @@ impl Register for Ctrl2G {}
 
 impl Ctrl2G {
     pub fn new(value: u8, address: u8) -> Self {
-        Ctrl2G { address, value }
+        Ctrl2G {
+            address,
+            value,
+            parked: Odr::Off,
+        }
+    }
+
+    /// Cached value of the register.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    /// Replace the cached value with `value` read from the device.
+    ///
+    /// The parked data rate is kept while the gyroscope stays off, and dropped once it runs again.
+    pub(crate) fn refresh(&mut self, value: u8) {
+        self.value = value;
+        if self.gyroscope_odr() != Odr::Off {
+            self.parked = Odr::Off;
+        }
+    }
+
+    /// The data rate [`Ctrl2G::resume`] goes back to, `Off` when not parked.
+    pub fn parked_odr(&self) -> Odr {
+        self.parked
+    }
+
+    /// Power the gyroscope down, remembering its data rate.
+    pub fn park<I2C>(&mut self, i2c: &mut I2C) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let odr = self.gyroscope_odr();
+        if odr != Odr::Off {
+            self.set_gyroscope_data_rate(i2c, Odr::Off)?;
+            self.parked = odr;
+        }
+        Ok(())
+    }
+
+    /// Restore the data rate saved by [`Ctrl2G::park`], if any.
+    pub fn resume<I2C>(&mut self, i2c: &mut I2C) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        if self.parked != Odr::Off {
+            self.set_gyroscope_data_rate(i2c, self.parked)?;
+            self.parked = Odr::Off;
+        }
+        Ok(())
     }
 
     pub fn gyroscope_data_rate(&self) -> f32 {
@@ -125,6 +189,22 @@ impl Ctrl2G {
         }
     }
 
+    pub fn gyroscope_odr(&self) -> Odr {
+        match (self.value >> ODR_OFFSET) & ODR_MASK {
+            1 => Odr::Hz125,
+            2 => Odr::Hz26,
+            3 => Odr::Hz52,
+            4 => Odr::Hz104,
+            5 => Odr::Hz208,
+            6 => Odr::Hz416,
+            7 => Odr::Hz833,
+            8 => Odr::Hz1667,
+            9 => Odr::Hz3333,
+            10 => Odr::Hz6667,
+            _ => Odr::Off,
+        }
+    }
+
     pub fn set_gyroscope_data_rate<I2C>(
         &mut self,
         i2c: &mut I2C,
@@ -156,6 +236,8 @@ impl Ctrl2G {
         }
     }
 
+    /// Samples already in the FIFO are decoded with the new full scale, use
+    /// [`crate::Ism330Dhcx::set_gyro_full_scale`] to keep them on the old one.
     pub fn set_chain_full_scale<I2C>(&mut self, i2c: &mut I2C, value: Fs) -> Result<(), I2C::Error>
     where
         I2C: embedded_hal::i2c::I2c,
@@ -86,6 +86,17 @@ pub const IF_INC: u8 = 2;
 ///This bit is automatically cleared.
 pub const SW_RESET: u8 = 0;
 
+/// Electrical mode of the INT1 and INT2 pins, `PP_OD` and `H_LACTIVE` together.
+///
+/// Open-drain only exists active high, since `PP_OD` must be 0 when `H_LACTIVE` is 1.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InterruptOutput {
+    PushPullActiveHigh,
+    PushPullActiveLow,
+    OpenDrainActiveHigh,
+}
+
 impl Register for Ctrl3C {}
 
 impl Ctrl3C {
@@ -93,6 +104,11 @@ impl Ctrl3C {
         Ctrl3C { address, value }
     }
 
+    /// Cached value of the register.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
     pub fn boot(&mut self) -> bool {
         self.value & (1 << BOOT) != 0
     }
@@ -106,7 +122,7 @@ impl Ctrl3C {
         self.write(i2c, self.address, ADDR, self.value)
     }
 
-    pub fn bdu(&mut self) -> bool {
+    pub fn bdu(&self) -> bool {
         self.value & (1 << BDU) != 0
     }
 
@@ -119,6 +135,19 @@ impl Ctrl3C {
         self.write(i2c, self.address, ADDR, self.value)
     }
 
+    pub fn h_lactive(&mut self) -> bool {
+        self.value & (1 << H_LACTIVE) != 0
+    }
+
+    pub fn set_h_lactive<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << H_LACTIVE);
+        self.value |= (value as u8) << H_LACTIVE;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
     pub fn sw_reset<I2C>(&mut self, i2c: &mut I2C) -> Result<(), I2C::Error>
     where
         I2C: embedded_hal::i2c::I2c,
@@ -139,4 +168,37 @@ impl Ctrl3C {
         self.value |= (value as u8) << IF_INC;
         self.write(i2c, self.address, ADDR, self.value)
     }
+
+    pub fn pp_od(&self) -> bool {
+        self.value & (1 << PP_OD) != 0
+    }
+
+    /// The interrupt pin mode, `None` for the forbidden open-drain active-low combination.
+    pub fn interrupt_output(&self) -> Option<InterruptOutput> {
+        match (self.pp_od(), self.value & (1 << H_LACTIVE) != 0) {
+            (false, false) => Some(InterruptOutput::PushPullActiveHigh),
+            (false, true) => Some(InterruptOutput::PushPullActiveLow),
+            (true, false) => Some(InterruptOutput::OpenDrainActiveHigh),
+            (true, true) => None,
+        }
+    }
+
+    /// Set `PP_OD` and `H_LACTIVE` in a single write.
+    pub fn set_interrupt_output<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        value: InterruptOutput,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let (pp_od, h_lactive) = match value {
+            InterruptOutput::PushPullActiveHigh => (0, 0),
+            InterruptOutput::PushPullActiveLow => (0, 1),
+            InterruptOutput::OpenDrainActiveHigh => (1, 0),
+        };
+        self.value &= !(1 << PP_OD | 1 << H_LACTIVE);
+        self.value |= pp_od << PP_OD | h_lactive << H_LACTIVE;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
 }
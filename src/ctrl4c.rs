@@ -0,0 +1,109 @@
+use core::fmt;
+
+use crate::Register;
+
+/// The CTRL4_C register. Control register 4.
+///
+/// Contains gyroscope sleep mode, interrupt routing on INT1, data-ready masking, I²C disabling and
+/// the gyroscope LPF1 enable
+pub struct Ctrl4C {
+    pub address: u8,
+    value: u8,
+}
+
+impl fmt::Display for Ctrl4C {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl fmt::Binary for Ctrl4C {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:b}", self.value)
+    }
+}
+
+impl fmt::LowerHex for Ctrl4C {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.value, f)
+    }
+}
+
+/// Sub-address of the register.
+pub const ADDR: u8 = 0x13u8;
+
+/// Enables gyroscope sleep mode.
+///
+/// The drive stays on so the gyroscope wakes up faster than from power-down.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const SLEEP_G: u8 = 6;
+
+/// All interrupt signals available on INT1 pin enable.
+///
+/// Default value: 0
+///
+/// (0: interrupt signals divided between INT1 and INT2 pins; 1: all interrupt signals in logic or on INT1 pin)
+pub const INT2_ON_INT1: u8 = 5;
+
+/// Enables data available.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: mask DRDY on pin (both XL & Gyro) until filter settling ends (XL and Gyro independently masked))
+pub const DRDY_MASK: u8 = 3;
+
+/// Disables I²C interface.
+///
+/// Default value: 0
+///
+/// (0: SPI, I²C interfaces enabled; 1: I²C interface disabled)
+pub const I2C_DISABLE: u8 = 2;
+
+/// Enables gyroscope digital LPF1; the bandwidth can be selected through FTYPE in CTRL6_C.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const LPF1_SEL_G: u8 = 1;
+
+impl Register for Ctrl4C {}
+
+impl Ctrl4C {
+    pub fn new(value: u8, address: u8) -> Self {
+        Ctrl4C { address, value }
+    }
+
+    /// Cached value of the register.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn sleep_g(&self) -> bool {
+        self.value & (1 << SLEEP_G) != 0
+    }
+
+    pub fn set_sleep_g<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << SLEEP_G);
+        self.value |= (value as u8) << SLEEP_G;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn lpf1_sel_g(&self) -> bool {
+        self.value & (1 << LPF1_SEL_G) != 0
+    }
+
+    pub fn set_lpf1_sel_g<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << LPF1_SEL_G);
+        self.value |= (value as u8) << LPF1_SEL_G;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+}
@@ -0,0 +1,191 @@
+use core::fmt;
+
+use crate::Register;
+
+/// The CTRL6_C register. Control register 6.
+///
+/// Contains the DEN trigger modes, the accelerometer high-performance operating mode, the weight of
+/// the user offsets and the gyroscope LPF1 bandwidth selection
+pub struct Ctrl6C {
+    pub address: u8,
+    value: u8,
+}
+
+impl fmt::Display for Ctrl6C {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl fmt::Binary for Ctrl6C {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:b}", self.value)
+    }
+}
+
+impl fmt::LowerHex for Ctrl6C {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.value, f)
+    }
+}
+
+/// Sub-address of the register.
+pub const ADDR: u8 = 0x15u8;
+
+/// DEN data edge-sensitive trigger enable.
+pub const TRIG_EN: u8 = 7;
+
+/// DEN data level-sensitive trigger enable.
+pub const LVL1_EN: u8 = 6;
+
+/// DEN level-sensitive latched enable.
+pub const LVL2_EN: u8 = 5;
+
+/// Disables high-performance operating mode for accelerometer.
+///
+/// With the bit set, 1.6 to 52 Hz run in low-power mode and 104 to 208 Hz in normal mode. Faster
+/// rates stay in high-performance mode, see [`crate::power`].
+///
+/// Default value: 0
+///
+/// (0: high-performance operating mode enabled; 1: high-performance operating mode disabled)
+pub const XL_HM_MODE: u8 = 4;
+
+/// Weight of XL user offset bits of registers X_OFS_USR, Y_OFS_USR, Z_OFS_USR.
+///
+/// (0: 2^-10 g/LSB; 1: 2^-6 g/LSB)
+pub const USR_OFF_W: u8 = 3;
+
+const FTYPE_MASK: u8 = 0b111;
+const FTYPE_OFFSET: u8 = 0;
+
+/// Gyroscope low-pass filter (LPF1) bandwidth selection, see [`crate::filter`] for the cutoffs.
+///
+/// Default value: 000
+///
+/// The variants are named after their `FTYPE` code. The cutoff depends on the ODR, the one given
+/// for each is at 6.66 kHz.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ftype {
+    /// 297 Hz at 6.66 kHz ODR
+    Ftype000,
+    /// 223 Hz at 6.66 kHz ODR
+    Ftype001,
+    /// 154 Hz at 6.66 kHz ODR
+    Ftype010,
+    /// 470 Hz at 6.66 kHz ODR
+    Ftype011,
+    /// 100 Hz at 6.66 kHz ODR
+    Ftype100,
+    /// 50 Hz at 6.66 kHz ODR
+    Ftype101,
+    /// 30 Hz at 6.66 kHz ODR
+    Ftype110,
+    /// 20 Hz at 6.66 kHz ODR
+    Ftype111,
+}
+
+const DEN_MASK: u8 = 0b111;
+const DEN_OFFSET: u8 = 5;
+
+/// DEN trigger mode, from `TRIG_EN`, `LVL1_EN` and `LVL2_EN`, see [`crate::den`].
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DenMode {
+    /// Edge-sensitive trigger, `100`.
+    Edge = 0b100,
+    /// Level-sensitive trigger, `010`.
+    Level = 0b010,
+    /// Level-sensitive latched, `011`.
+    LevelLatched = 0b011,
+    /// Level-sensitive FIFO enable, `110`.
+    LevelFifo = 0b110,
+}
+
+impl Register for Ctrl6C {}
+
+impl Ctrl6C {
+    pub fn new(value: u8, address: u8) -> Self {
+        Ctrl6C { address, value }
+    }
+
+    /// Cached value of the register.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn xl_hm_mode(&self) -> bool {
+        self.value & (1 << XL_HM_MODE) != 0
+    }
+
+    pub fn set_xl_hm_mode<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << XL_HM_MODE);
+        self.value |= (value as u8) << XL_HM_MODE;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn usr_off_w(&self) -> bool {
+        self.value & (1 << USR_OFF_W) != 0
+    }
+
+    pub fn set_usr_off_w<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << USR_OFF_W);
+        self.value |= (value as u8) << USR_OFF_W;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn ftype(&self) -> Ftype {
+        match (self.value >> FTYPE_OFFSET) & FTYPE_MASK {
+            0 => Ftype::Ftype000,
+            1 => Ftype::Ftype001,
+            2 => Ftype::Ftype010,
+            3 => Ftype::Ftype011,
+            4 => Ftype::Ftype100,
+            5 => Ftype::Ftype101,
+            6 => Ftype::Ftype110,
+            7 => Ftype::Ftype111,
+            _ => panic!("Unreachable"),
+        }
+    }
+
+    pub fn set_ftype<I2C>(&mut self, i2c: &mut I2C, value: Ftype) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(FTYPE_MASK << FTYPE_OFFSET);
+        self.value |= (value as u8) << FTYPE_OFFSET;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    /// DEN trigger mode, `None` with DEN off or an undocumented combination.
+    pub fn den_mode(&self) -> Option<DenMode> {
+        match (self.value >> DEN_OFFSET) & DEN_MASK {
+            0b100 => Some(DenMode::Edge),
+            0b010 => Some(DenMode::Level),
+            0b011 => Some(DenMode::LevelLatched),
+            0b110 => Some(DenMode::LevelFifo),
+            _ => None,
+        }
+    }
+
+    /// Select a DEN trigger mode, or turn DEN off with `None`.
+    pub fn set_den_mode<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        value: Option<DenMode>,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(DEN_MASK << DEN_OFFSET);
+        self.value |= value.map_or(0, |mode| mode as u8) << DEN_OFFSET;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+}
@@ -35,6 +35,9 @@ pub const ADDR: u8 = 0x16u8;
 
 /// Disables high-performance operating mode for gyroscope.
 ///
+/// With the bit set, 12.5 to 52 Hz run in low-power mode and 104 to 208 Hz in normal mode. Faster
+/// rates stay in high-performance mode, see [`crate::power`].
+///
 /// Default: 0
 ///
 ///(0: high-performance operating mode enabled; 1: high-performance operating mode disabled)
@@ -48,17 +51,30 @@ pub const G_HM_MODE: u8 = 7;
 pub const HP_EN_G: u8 = 6;
 
 const HPM_G_MASK: u8 = 0b11;
-const HPM_G_OFFSET: u8 = 3;
+const HPM_G_OFFSET: u8 = 4;
 /// Gyroscope digital HP filter cutoff selection.
 ///
 /// Default: 00
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Hpm_g {
-    Hpmg16,  // ±250 mHz
-    Hpmg65,  // ±500 mHz
-    Hpmg260, // ±1000 mHz
-    Hpmg104, // ±4000 Hz
+    Hpmg16,  // 16   mHz
+    Hpmg65,  // 65   mHz
+    Hpmg260, // 260  mHz
+    Hpmg104, // 1.04 Hz
+}
+
+impl Hpm_g {
+    /// Cutoff frequency in Hz.
+    pub fn hz(&self) -> f32 {
+        match self {
+            Hpm_g::Hpmg16 => 0.016,
+            Hpm_g::Hpmg65 => 0.065,
+            Hpm_g::Hpmg260 => 0.26,
+            Hpm_g::Hpmg104 => 1.04,
+        }
+    }
 }
 
 /// Selects how to enable and disable the OIS chain, after first configuration and enabling through SPI2.
@@ -86,12 +102,22 @@ impl Ctrl7G {
         Ctrl7G { address, value }
     }
 
+    /// Cached value of the register.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    /// High-pass filter cutoff frequency in Hz.
     pub fn hpm_g(&self) -> f32 {
+        self.high_pass_cutoff().hz()
+    }
+
+    pub fn high_pass_cutoff(&self) -> Hpm_g {
         match (self.value >> HPM_G_OFFSET) & HPM_G_MASK {
-            0 => 250.0,
-            1 => 500.0,
-            2 => 1000.0,
-            3 => 4000.0,
+            0 => Hpm_g::Hpmg16,
+            1 => Hpm_g::Hpmg65,
+            2 => Hpm_g::Hpmg260,
+            3 => Hpm_g::Hpmg104,
             _ => panic!("Unreachable"),
         }
     }
@@ -105,7 +131,37 @@ impl Ctrl7G {
         self.write(i2c, self.address, ADDR, self.value)
     }
 
-    pub fn g_hm_mode(&mut self) -> bool {
+    pub fn hp_en_g(&self) -> bool {
+        self.value & (1 << HP_EN_G) != 0
+    }
+
+    pub fn set_hp_en_g<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << HP_EN_G);
+        self.value |= (value as u8) << HP_EN_G;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    /// Set `HP_EN_G` and `HPM_G` in a single write. Disabling the filter keeps the last cutoff.
+    pub fn set_high_pass<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        cutoff: Option<Hpm_g>,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << HP_EN_G);
+        if let Some(cutoff) = cutoff {
+            self.value &= !(HPM_G_MASK << HPM_G_OFFSET);
+            self.value |= 1 << HP_EN_G | (cutoff as u8) << HPM_G_OFFSET;
+        }
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn g_hm_mode(&self) -> bool {
         self.value & (1 << G_HM_MODE) != 0
     }
 
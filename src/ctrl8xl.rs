@@ -0,0 +1,218 @@
+use core::fmt;
+
+use crate::Register;
+
+/// The CTRL8_XL register. Control register 8.
+///
+/// Contains the accelerometer LPF2 and high-pass filter cutoff, the high-pass filter reference and
+/// fast-settling modes, the slope/high-pass path selection and the 6D low-pass filter routing
+pub struct Ctrl8Xl {
+    pub address: u8,
+    value: u8,
+}
+
+impl fmt::Display for Ctrl8Xl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl fmt::Binary for Ctrl8Xl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:b}", self.value)
+    }
+}
+
+impl fmt::LowerHex for Ctrl8Xl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.value, f)
+    }
+}
+
+/// Sub-address of the register.
+pub const ADDR: u8 = 0x17u8;
+
+const HPCF_XL_MASK: u8 = 0b111;
+const HPCF_XL_OFFSET: u8 = 5;
+
+/// Accelerometer LPF2 and high-pass filter configuration and cutoff setting, as a fraction of the
+/// accelerometer ODR.
+///
+/// Default value: 000
+///
+/// `Odr4` selects the slope filter on the high-pass path.
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Hpcf_Xl {
+    Odr4,   // ODR/4
+    Odr10,  // ODR/10
+    Odr20,  // ODR/20
+    Odr45,  // ODR/45
+    Odr100, // ODR/100
+    Odr200, // ODR/200
+    Odr400, // ODR/400
+    Odr800, // ODR/800
+}
+
+impl Hpcf_Xl {
+    /// The ODR divided by this is the cutoff frequency.
+    pub fn divisor(&self) -> u16 {
+        match self {
+            Hpcf_Xl::Odr4 => 4,
+            Hpcf_Xl::Odr10 => 10,
+            Hpcf_Xl::Odr20 => 20,
+            Hpcf_Xl::Odr45 => 45,
+            Hpcf_Xl::Odr100 => 100,
+            Hpcf_Xl::Odr200 => 200,
+            Hpcf_Xl::Odr400 => 400,
+            Hpcf_Xl::Odr800 => 800,
+        }
+    }
+}
+
+/// Enables accelerometer high-pass filter reference mode (valid for high-pass path - HP_SLOPE_XL_EN
+/// bit must be '1').
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const HP_REF_MODE_XL: u8 = 4;
+
+/// Enables accelerometer LPF2 and HPF fast-settling mode. The filter sets the second sample after
+/// writing this bit.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const FASTSETTL_MODE_XL: u8 = 3;
+
+/// Accelerometer slope filter / high-pass filter selection.
+///
+/// Default value: 0
+///
+/// (0: low-pass path; 1: high-pass path)
+pub const HP_SLOPE_XL_EN: u8 = 2;
+
+/// LPF2 on 6D function selection.
+///
+/// Default value: 0
+///
+/// (0: ODR/2 low-pass filtered data sent to 6D interrupt function; 1: LPF2 output data sent to 6D interrupt function)
+pub const LOW_PASS_ON_6D: u8 = 0;
+
+impl Register for Ctrl8Xl {}
+
+impl Ctrl8Xl {
+    pub fn new(value: u8, address: u8) -> Self {
+        Ctrl8Xl { address, value }
+    }
+
+    /// Cached value of the register.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn hpcf_xl(&self) -> Hpcf_Xl {
+        match (self.value >> HPCF_XL_OFFSET) & HPCF_XL_MASK {
+            0 => Hpcf_Xl::Odr4,
+            1 => Hpcf_Xl::Odr10,
+            2 => Hpcf_Xl::Odr20,
+            3 => Hpcf_Xl::Odr45,
+            4 => Hpcf_Xl::Odr100,
+            5 => Hpcf_Xl::Odr200,
+            6 => Hpcf_Xl::Odr400,
+            7 => Hpcf_Xl::Odr800,
+            _ => panic!("Unreachable"),
+        }
+    }
+
+    pub fn set_hpcf_xl<I2C>(&mut self, i2c: &mut I2C, value: Hpcf_Xl) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(HPCF_XL_MASK << HPCF_XL_OFFSET);
+        self.value |= (value as u8) << HPCF_XL_OFFSET;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn hp_ref_mode_xl(&self) -> bool {
+        self.value & (1 << HP_REF_MODE_XL) != 0
+    }
+
+    pub fn set_hp_ref_mode_xl<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << HP_REF_MODE_XL);
+        self.value |= (value as u8) << HP_REF_MODE_XL;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn fastsettl_mode_xl(&self) -> bool {
+        self.value & (1 << FASTSETTL_MODE_XL) != 0
+    }
+
+    pub fn set_fastsettl_mode_xl<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        value: bool,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << FASTSETTL_MODE_XL);
+        self.value |= (value as u8) << FASTSETTL_MODE_XL;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn hp_slope_xl_en(&self) -> bool {
+        self.value & (1 << HP_SLOPE_XL_EN) != 0
+    }
+
+    pub fn set_hp_slope_xl_en<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << HP_SLOPE_XL_EN);
+        self.value |= (value as u8) << HP_SLOPE_XL_EN;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    /// Set `HPCF_XL`, `HP_SLOPE_XL_EN`, `HP_REF_MODE_XL` and `FASTSETTL_MODE_XL` in a single
+    /// write, so the path and reference mode never disagree in between.
+    pub fn set_filtering<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        cutoff: Hpcf_Xl,
+        high_pass: bool,
+        reference: bool,
+        fast_settling: bool,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(HPCF_XL_MASK << HPCF_XL_OFFSET
+            | 1 << HP_SLOPE_XL_EN
+            | 1 << HP_REF_MODE_XL
+            | 1 << FASTSETTL_MODE_XL);
+        self.value |= (cutoff as u8) << HPCF_XL_OFFSET
+            | (high_pass as u8) << HP_SLOPE_XL_EN
+            | (reference as u8) << HP_REF_MODE_XL
+            | (fast_settling as u8) << FASTSETTL_MODE_XL;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn low_pass_on_6d(&self) -> bool {
+        self.value & (1 << LOW_PASS_ON_6D) != 0
+    }
+
+    pub fn set_low_pass_on_6d<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << LOW_PASS_ON_6D);
+        self.value |= (value as u8) << LOW_PASS_ON_6D;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+}
@@ -28,12 +28,12 @@ impl fmt::LowerHex for Ctrl9Xl {
 
 pub const ADDR: u8 = 0x18u8;
 
-/// DEN value stored in LSB of Z-axis.
+/// DEN value stored in LSB of X-axis.
 ///
 /// Default value: 1
 ///
-///(0: DEN not stored in Z-axis LSB; 1: DEN stored in Z-axis LSB)
-pub const DEN_Z: u8 = 7;
+///(0: DEN not stored in X-axis LSB; 1: DEN stored in X-axis LSB)
+pub const DEN_X: u8 = 7;
 
 /// DEN value stored in LSB of Y-axis.
 ///
@@ -42,12 +42,12 @@ pub const DEN_Z: u8 = 7;
 ///(0: DEN not stored in Y-axis LSB; 1: DEN stored in Y-axis LSB)
 pub const DEN_Y: u8 = 6;
 
-/// DEN value stored in LSB of X-axis.
+/// DEN value stored in LSB of Z-axis.
 ///
 /// Default value: 1
 ///
-///(0: DEN not stored in X-axis LSB; 1: DEN stored in X-axis LSB)
-pub const DEN_X: u8 = 5;
+///(0: DEN not stored in Z-axis LSB; 1: DEN stored in Z-axis LSB)
+pub const DEN_Z: u8 = 5;
 
 /// DEN stamping sensor selection.
 ///
@@ -86,7 +86,12 @@ impl Ctrl9Xl {
         Ctrl9Xl { address, value }
     }
 
-    pub fn den_x(&mut self) -> bool {
+    /// Cached value of the register.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn den_x(&self) -> bool {
         self.value & (1 << DEN_X) != 0
     }
 
@@ -99,7 +104,7 @@ impl Ctrl9Xl {
         self.write(i2c, self.address, ADDR, self.value)
     }
 
-    pub fn den_y(&mut self) -> bool {
+    pub fn den_y(&self) -> bool {
         self.value & (1 << DEN_Y) != 0
     }
 
@@ -112,7 +117,7 @@ impl Ctrl9Xl {
         self.write(i2c, self.address, ADDR, self.value)
     }
 
-    pub fn den_z(&mut self) -> bool {
+    pub fn den_z(&self) -> bool {
         self.value & (1 << DEN_Z) != 0
     }
 
@@ -125,7 +130,7 @@ impl Ctrl9Xl {
         self.write(i2c, self.address, ADDR, self.value)
     }
 
-    pub fn den_xl_g(&mut self) -> bool {
+    pub fn den_xl_g(&self) -> bool {
         self.value & (1 << DEN_XL_G) != 0
     }
 
@@ -138,7 +143,7 @@ impl Ctrl9Xl {
         self.write(i2c, self.address, ADDR, self.value)
     }
 
-    pub fn den_xl_en(&mut self) -> bool {
+    pub fn den_xl_en(&self) -> bool {
         self.value & (1 << DEN_XL_EN) != 0
     }
 
@@ -151,7 +156,7 @@ impl Ctrl9Xl {
         self.write(i2c, self.address, ADDR, self.value)
     }
 
-    pub fn den_lh(&mut self) -> bool {
+    pub fn den_lh(&self) -> bool {
         self.value & (1 << DEN_LH) != 0
     }
 
@@ -164,6 +169,30 @@ impl Ctrl9Xl {
         self.write(i2c, self.address, ADDR, self.value)
     }
 
+    /// Set `DEN_X`, `DEN_Y`, `DEN_Z`, `DEN_XL_G`, `DEN_XL_EN` and `DEN_LH` in a single write.
+    pub fn set_den<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        axes: [bool; 3],
+        den_xl_g: bool,
+        den_xl_en: bool,
+        den_lh: bool,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let [x, y, z] = axes;
+        self.value &=
+            !(1 << DEN_X | 1 << DEN_Y | 1 << DEN_Z | 1 << DEN_XL_G | 1 << DEN_XL_EN | 1 << DEN_LH);
+        self.value |= (x as u8) << DEN_X
+            | (y as u8) << DEN_Y
+            | (z as u8) << DEN_Z
+            | (den_xl_g as u8) << DEN_XL_G
+            | (den_xl_en as u8) << DEN_XL_EN
+            | (den_lh as u8) << DEN_LH;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
     pub fn device_conf(&mut self) -> bool {
         self.value & (1 << DEVICE_CONF) != 0
     }
@@ -0,0 +1,379 @@
+//! FIFO reading by time slot.
+//!
+//! The FIFO interleaves the words of all the batched sensors. The words batched at the same time
+//! share a `TAG_CNT`, so a [`Drain`] groups them back into [`Slot`]s with one channel per sensor,
+//! and a [`Pairing`] turns the slots into [`ImuSample`]s, even with different gyroscope and
+//! accelerometer batch data rates.
+//!
+//! ```no_run
+//! # fn run<I2C: embedded_hal::i2c::I2c>(i2c: &mut I2C) -> Result<(), ism330dhcx::Error<I2C::Error>> {
+//! use ism330dhcx::demux::Pairing;
+//! use ism330dhcx::Ism330Dhcx;
+//!
+//! let mut sensor = Ism330Dhcx::new(i2c)?;
+//! let mut pairing = Pairing::new(&sensor);
+//!
+//! loop {
+//!     for sample in sensor.fifo_drain(i2c)?.imu(&mut pairing) {
+//!         println!("{:?}", sample?);
+//!     }
+//! }
+//! # }
+//! ```
+
+use embedded_hal::i2c::I2c;
+
+use crate::{config, fifo, AccelValue, GyroValue, ImuSample, Ism330Dhcx};
+
+/// Words batched in the same time slot, one per sensor.
+#[derive(Copy, Clone, PartialEq, Debug, Default, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Slot {
+    /// Timestamp counter, when batched ahead of the slot.
+    pub timestamp: Option<u32>,
+    pub gyro: Option<GyroValue>,
+    pub accel: Option<AccelValue>,
+    /// Temperature in Celsius.
+    pub temperature: Option<f32>,
+}
+
+/// Iterator over the time slots in the FIFO, see [`Ism330Dhcx::fifo_drain`].
+///
+/// Only the words in the FIFO when the drain started are read. A slot still being batched then is
+/// completed by the next drain, as a slot of its own.
+pub struct Drain<'a, I2C> {
+    sensor: &'a mut Ism330Dhcx,
+    i2c: &'a mut I2C,
+    /// Words left to read.
+    words: u16,
+    /// Timestamp counter when the drain started.
+    timestamp: u32,
+    /// Words lost to an overrun before the drain, see [`Drain::lost`].
+    overrun: Option<Option<u32>>,
+    /// First word of the next slot, with its `TAG_CNT`.
+    next: Option<(u8, fifo::Value)>,
+}
+
+impl<I2C> Drain<'_, I2C>
+where
+    I2C: I2c,
+{
+    /// The FIFO had overrun when the drain started, some slots were lost before the first one.
+    pub fn overrun(&self) -> bool {
+        self.overrun.is_some()
+    }
+
+    /// Words lost to the overrun, estimated as for [`fifo::Value::Gap`]. `None` without an
+    /// overrun, or with the timestamp counter stopped.
+    pub fn lost(&self) -> Option<u32> {
+        self.overrun.flatten()
+    }
+
+    /// Pair the slots into IMU samples, see [`Pairing`].
+    pub fn imu<'p>(
+        self,
+        pairing: &'p mut Pairing,
+    ) -> impl Iterator<Item = Result<ImuSample, I2C::Error>> + 'p
+    where
+        Self: 'p,
+    {
+        self.filter_map(move |slot| match slot {
+            Ok(slot) => pairing.push(&slot).map(Ok),
+            Err(e) => Some(Err(e)),
+        })
+    }
+
+    fn pop(&mut self) -> Result<Option<(u8, fifo::Value)>, I2C::Error> {
+        if self.words == 0 {
+            return Ok(None);
+        }
+
+        self.words -= 1;
+        let value = self.sensor.fifo_pop(self.i2c)?;
+        if value == fifo::Value::Empty {
+            self.words = 0;
+        }
+
+        self.sensor.fifoout.last_read = Some((self.timestamp, self.words));
+        if value == fifo::Value::Empty {
+            return Ok(None);
+        }
+
+        Ok(Some((self.sensor.fifoout.tag_cnt(), value)))
+    }
+
+    fn slot(&mut self) -> Result<Option<Slot>, I2C::Error> {
+        let (tag_cnt, mut value) = match self.next.take() {
+            Some(word) => word,
+            None => match self.pop()? {
+                Some(word) => word,
+                None => return Ok(None),
+            },
+        };
+
+        let mut slot = Slot::default();
+        loop {
+            match value {
+                fifo::Value::Gyro(gyro) => slot.gyro = Some(gyro),
+                fifo::Value::Accel(accel) => slot.accel = Some(accel),
+                fifo::Value::Temperature(t) => slot.temperature = Some(t),
+                fifo::Value::Timestamp(ts) => slot.timestamp = Some(ts),
+                // Full scale changes are tracked by the pop, the rest has no channel.
+                _ => {}
+            }
+
+            match self.pop()? {
+                Some((cnt, v)) if cnt == tag_cnt => value = v,
+                Some(word) => {
+                    self.next = Some(word);
+                    break;
+                }
+                None => break,
+            }
+        }
+
+        Ok(Some(slot))
+    }
+}
+
+impl<I2C> Iterator for Drain<'_, I2C>
+where
+    I2C: I2c,
+{
+    type Item = Result<Slot, I2C::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.slot() {
+            Ok(slot) => slot.map(Ok),
+            Err(e) => {
+                self.words = 0;
+                self.next = None;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Pairs gyroscope and accelerometer samples into [`ImuSample`]s.
+///
+/// One sample is made for each slot of the sensor with the faster batch data rate, the gyroscope
+/// with equal rates, together with the latest sample of the other sensor. Slots before the other
+/// sensor has been seen are skipped. The temperature is the latest one batched, `None` before any,
+/// so the samples do not wait for a temperature that may not be batched at all.
+///
+/// The pairing keeps the latest samples between drains, so the same one should be used for the
+/// whole stream.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Pairing {
+    /// The gyroscope leads, rather than the accelerometer.
+    gyro_leads: bool,
+    gyro: Option<GyroValue>,
+    accel: Option<AccelValue>,
+    temperature: Option<f32>,
+}
+
+impl Pairing {
+    /// Pairing for the batch data rates in the register cache of `sensor`.
+    pub fn new(sensor: &Ism330Dhcx) -> Self {
+        let bdr = sensor.fifoctrl.value()[2];
+
+        Pairing {
+            gyro_leads: config::bdr_hz(bdr >> 4) >= config::bdr_hz(bdr & 0b1111),
+            gyro: None,
+            accel: None,
+            temperature: None,
+        }
+    }
+
+    /// Take in the next slot, returning a sample when it has one from the leading sensor.
+    pub fn push(&mut self, slot: &Slot) -> Option<ImuSample> {
+        self.gyro = slot.gyro.or(self.gyro);
+        self.accel = slot.accel.or(self.accel);
+        self.temperature = slot.temperature.or(self.temperature);
+
+        let lead = match self.gyro_leads {
+            true => slot.gyro.is_some(),
+            false => slot.accel.is_some(),
+        };
+        if !lead {
+            return None;
+        }
+
+        Some(ImuSample {
+            temp: self.temperature,
+            gyro: self.gyro?,
+            accel: self.accel?,
+        })
+    }
+}
+
+impl Ism330Dhcx {
+    /// Read the words in the FIFO by time slot.
+    ///
+    /// The FIFO level and the timestamp counter are read once, then each slot is read as the
+    /// iterator advances. An overrun is reported by [`Drain::overrun`] and [`Drain::lost`] as
+    /// [`Ism330Dhcx::fifo_read`] does with [`fifo::Recovery::Continue`], and the words read count
+    /// for the estimate of the next one.
+    pub fn fifo_drain<'a, I2C>(&'a mut self, i2c: &'a mut I2C) -> Result<Drain<'a, I2C>, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        let (words, timestamp) = self.fifo_level_at(i2c)?;
+        let overrun = self.take_lost(timestamp, words);
+        self.fifoout.last_read = Some((timestamp, words));
+
+        Ok(Drain {
+            sensor: self,
+            i2c,
+            words,
+            timestamp,
+            overrun,
+            next: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctrl1xl::Odr_Xl;
+    use crate::ctrl2g::Odr;
+    use crate::fifoctrl::{BdrGy, BdrXl, DecTs, FifoMode};
+    use crate::sim::Simulator;
+
+    fn batching(sim: &mut Simulator, gyro: BdrGy, accel: BdrXl) -> Ism330Dhcx {
+        let mut sensor = Ism330Dhcx::new(sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(sim, Odr_Xl::Hz104)
+            .unwrap();
+        sensor
+            .ctrl2g
+            .set_gyroscope_data_rate(sim, Odr::Hz104)
+            .unwrap();
+        sensor
+            .fifoctrl
+            .set_accelerometer_batch_data_rate(sim, accel)
+            .unwrap();
+        sensor
+            .fifoctrl
+            .set_gyroscope_batch_data_rate(sim, gyro)
+            .unwrap();
+        sensor.fifoctrl.mode(sim, FifoMode::Continuous).unwrap();
+        sensor
+    }
+
+    #[test]
+    fn slots() {
+        let mut sim = Simulator::new();
+        let mut sensor = batching(&mut sim, BdrGy::Hz104, BdrXl::Hz52);
+        sensor.enable_timestamp(&mut sim, true).unwrap();
+        sensor
+            .fifoctrl
+            .set_timestamp_decimation(&mut sim, DecTs::Every1)
+            .unwrap();
+
+        sim.advance_us(100_000);
+        let mut slots = 0;
+        let mut accel = 0;
+        let mut last = 0;
+        for slot in sensor.fifo_drain(&mut sim).unwrap() {
+            let slot = slot.unwrap();
+            assert!(slot.gyro.is_some());
+            accel += slot.accel.is_some() as usize;
+
+            let timestamp = slot.timestamp.unwrap();
+            assert!(timestamp > last);
+            last = timestamp;
+            slots += 1;
+        }
+        assert_eq!((slots, accel), (10, 5));
+        assert_eq!(sim.fifo_level(), 0);
+    }
+
+    #[test]
+    fn overrun() {
+        let mut sim = Simulator::new();
+        let mut sensor = batching(&mut sim, BdrGy::Hz104, BdrXl::Hz104);
+        sensor.enable_timestamp(&mut sim, true).unwrap();
+
+        sim.advance_us(100_000);
+        let drain = sensor.fifo_drain(&mut sim).unwrap();
+        assert_eq!((drain.overrun(), drain.lost()), (false, None));
+        assert_eq!(drain.take(4).count(), 4);
+
+        // 12 words left, 1040 batched, 512 kept
+        sim.advance_us(5_000_000);
+        let drain = sensor.fifo_drain(&mut sim).unwrap();
+        assert!(drain.overrun());
+        let lost = drain.lost().unwrap();
+        assert!((538..=542).contains(&lost), "{}", lost);
+        assert_eq!(drain.count(), 256);
+
+        sim.advance_us(100_000);
+        let drain = sensor.fifo_drain(&mut sim).unwrap();
+        assert!(!drain.overrun());
+        assert_eq!(drain.count(), 10);
+    }
+
+    #[test]
+    fn pairing() {
+        let mut sim = Simulator::new();
+        let mut sensor = batching(&mut sim, BdrGy::Hz52, BdrXl::Hz104);
+        let mut pairing = Pairing::new(&sensor);
+
+        sim.advance_us(100_000);
+        let drain = sensor.fifo_drain(&mut sim).unwrap();
+        assert!(!drain.overrun());
+        // the first accelerometer slot comes before any gyroscope sample
+        let samples = drain.imu(&mut pairing).map(Result::unwrap).count();
+        assert_eq!(samples, 9);
+
+        sim.advance_us(50_000);
+        for sample in sensor.fifo_drain(&mut sim).unwrap().imu(&mut pairing) {
+            let sample = sample.unwrap();
+            assert!((sample.accel.as_g()[2] - 1.).abs() < 0.01);
+            assert_eq!(sample.temp, None);
+        }
+    }
+
+    #[test]
+    fn pairing_waits_for_both() {
+        let gyro = GyroValue::new(crate::ctrl2g::Fs::Dps250, [1, 2, 3]);
+        let accel = AccelValue::new(crate::ctrl1xl::Fs_Xl::G2, [4, 5, 6]);
+        let mut pairing = Pairing {
+            gyro_leads: true,
+            gyro: None,
+            accel: None,
+            temperature: None,
+        };
+
+        let lead = Slot {
+            gyro: Some(gyro),
+            ..Slot::default()
+        };
+        assert_eq!(pairing.push(&lead), None);
+
+        let both = Slot {
+            accel: Some(accel),
+            temperature: Some(30.),
+            ..lead
+        };
+        assert_eq!(
+            pairing.push(&both),
+            Some(ImuSample {
+                temp: Some(30.),
+                gyro,
+                accel
+            })
+        );
+        assert_eq!(
+            pairing.push(&Slot {
+                accel: Some(accel),
+                ..Slot::default()
+            }),
+            None
+        );
+        assert_eq!(pairing.push(&lead).map(|s| s.temp), Some(Some(30.)));
+    }
+}
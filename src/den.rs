@@ -0,0 +1,263 @@
+//! Data-enable (DEN) pin triggered acquisition.
+//!
+//! An external signal on the DEN pin, a camera shutter for example, triggers or gates the
+//! acquisition in one of the [`DenMode`]s selected in `CTRL6_C`. The DEN level is also stamped
+//! in the least significant bit of the chosen axes of the gyroscope or accelerometer samples,
+//! set in `CTRL9_XL`, so samples can be matched with the external events afterwards.
+//!
+//! ```no_run
+//! # fn run<I2C: embedded_hal::i2c::I2c>(i2c: &mut I2C) -> Result<(), ism330dhcx::Error<I2C::Error>> {
+//! use ism330dhcx::den::{DenConfig, DenMode};
+//! use ism330dhcx::Ism330Dhcx;
+//!
+//! let mut sensor = Ism330Dhcx::new(i2c)?;
+//! sensor.set_den(i2c, Some(DenConfig::new(DenMode::LevelFifo)))?;
+//!
+//! let stamp = sensor.den_stamp().unwrap();
+//! if let Some(true) = sensor.fifo_pop(i2c)?.den(stamp) {
+//!     // sampled while the shutter was open
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::ctrl6c::DenMode;
+use crate::{fifo, AccelValue, GyroValue, Ism330Dhcx};
+
+/// Sensor whose samples carry the DEN bit, `DEN_XL_G` in `CTRL9_XL`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DenSensor {
+    Gyro,
+    Accel,
+}
+
+/// Where the DEN bit is stamped.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DenStamp {
+    pub sensor: DenSensor,
+    /// Axes whose least significant bit is replaced by the DEN bit, X, Y and Z, `DEN_X`, `DEN_Y`
+    /// and `DEN_Z`.
+    pub axes: [bool; 3],
+}
+
+impl Default for DenStamp {
+    /// Power-on stamping, in all the gyroscope axes.
+    fn default() -> Self {
+        DenStamp {
+            sensor: DenSensor::Gyro,
+            axes: [true; 3],
+        }
+    }
+}
+
+impl DenStamp {
+    /// The DEN bit of a sample, from the first stamped axis.
+    fn marker(&self, sensor: DenSensor, count: [i16; 3]) -> Option<bool> {
+        if sensor != self.sensor {
+            return None;
+        }
+
+        let axis = self.axes.iter().position(|&stamped| stamped)?;
+        Some(count[axis] & 1 != 0)
+    }
+}
+
+/// DEN settings, see [`Ism330Dhcx::set_den`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DenConfig {
+    pub mode: DenMode,
+    /// DEN is active high, `DEN_LH`. Active low otherwise.
+    pub active_high: bool,
+    pub stamp: DenStamp,
+    /// The trigger also applies to the accelerometer, `DEN_XL_EN`. Otherwise it only applies to
+    /// the gyroscope.
+    pub accel: bool,
+}
+
+impl DenConfig {
+    /// Active low DEN in `mode`, stamped in the gyroscope axes, the power-on settings.
+    pub fn new(mode: DenMode) -> Self {
+        DenConfig {
+            mode,
+            active_high: false,
+            stamp: DenStamp::default(),
+            accel: false,
+        }
+    }
+
+    pub fn active_high(self, active_high: bool) -> Self {
+        DenConfig {
+            active_high,
+            ..self
+        }
+    }
+
+    pub fn stamp(self, stamp: DenStamp) -> Self {
+        DenConfig { stamp, ..self }
+    }
+
+    pub fn accel(self, accel: bool) -> Self {
+        DenConfig { accel, ..self }
+    }
+}
+
+impl Ism330Dhcx {
+    /// Configure DEN triggered acquisition, or turn it off with `None`.
+    ///
+    /// The level and stamping are set before the trigger mode, so DEN is never active with stale
+    /// settings. Turning DEN off leaves them as they are.
+    pub fn set_den<I2C>(&mut self, i2c: &mut I2C, den: Option<DenConfig>) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        if let Some(den) = den {
+            self.ctrl9xl.set_den(
+                i2c,
+                den.stamp.axes,
+                den.stamp.sensor == DenSensor::Accel,
+                den.accel,
+                den.active_high,
+            )?;
+        }
+
+        self.ctrl6c.set_den_mode(i2c, den.map(|den| den.mode))
+    }
+
+    /// DEN settings from the register caches, `None` with DEN off.
+    pub fn den(&self) -> Option<DenConfig> {
+        Some(DenConfig {
+            mode: self.ctrl6c.den_mode()?,
+            active_high: self.ctrl9xl.den_lh(),
+            stamp: DenStamp {
+                sensor: match self.ctrl9xl.den_xl_g() {
+                    true => DenSensor::Accel,
+                    false => DenSensor::Gyro,
+                },
+                axes: [
+                    self.ctrl9xl.den_x(),
+                    self.ctrl9xl.den_y(),
+                    self.ctrl9xl.den_z(),
+                ],
+            },
+            accel: self.ctrl9xl.den_xl_en(),
+        })
+    }
+
+    /// Where the DEN bit is stamped, `None` with DEN off or no axis selected.
+    pub fn den_stamp(&self) -> Option<DenStamp> {
+        self.den()
+            .map(|den| den.stamp)
+            .filter(|stamp| stamp.axes.contains(&true))
+    }
+}
+
+impl GyroValue {
+    /// The DEN bit stamped in the sample, `None` if the stamp goes to the accelerometer.
+    pub fn den(&self, stamp: DenStamp) -> Option<bool> {
+        stamp.marker(DenSensor::Gyro, self.count)
+    }
+}
+
+impl AccelValue {
+    /// The DEN bit stamped in the sample, `None` if the stamp goes to the gyroscope.
+    pub fn den(&self, stamp: DenStamp) -> Option<bool> {
+        stamp.marker(DenSensor::Accel, self.count)
+    }
+}
+
+impl fifo::Value {
+    /// The DEN bit stamped in a gyroscope or accelerometer sample, `None` for other values.
+    pub fn den(&self, stamp: DenStamp) -> Option<bool> {
+        match self {
+            fifo::Value::Gyro(gyro) => gyro.den(stamp),
+            fifo::Value::Accel(accel) => accel.den(stamp),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctrl1xl::Fs_Xl;
+    use crate::ctrl2g::Fs;
+    use crate::sim::Simulator;
+    use crate::{ctrl6c, ctrl9xl};
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    #[test]
+    fn set_den() {
+        let mut sim = Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        assert_eq!(sensor.den(), None);
+        assert_eq!(sensor.den_stamp(), None);
+
+        let den = DenConfig::new(DenMode::LevelFifo)
+            .active_high(true)
+            .accel(true)
+            .stamp(DenStamp {
+                sensor: DenSensor::Accel,
+                axes: [false, false, true],
+            });
+        sensor.set_den(&mut sim, Some(den)).unwrap();
+        assert_eq!(sim.register(ctrl6c::ADDR) >> 5, 0b110);
+        assert_eq!(sim.register(ctrl9xl::ADDR) >> 2, 0b001111);
+        assert_eq!(sensor.den(), Some(den));
+
+        sensor.sync(&mut sim).unwrap();
+        assert_eq!(sensor.den(), Some(den));
+        assert_eq!(sensor.den_stamp(), Some(den.stamp));
+
+        sensor.set_den(&mut sim, None).unwrap();
+        assert_eq!(sim.register(ctrl6c::ADDR) >> 5, 0);
+        assert_eq!(sensor.den(), None);
+    }
+
+    #[test]
+    fn set_den_writes_once() {
+        let den = DenConfig::new(DenMode::Edge)
+            .active_high(true)
+            .stamp(DenStamp {
+                sensor: DenSensor::Accel,
+                axes: [true, true, false],
+            });
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x6b, vec![0x07], vec![0; crate::CONTROL_BLOCK]),
+            Transaction::write(0x6b, vec![ctrl9xl::ADDR, 0b1101_0100]),
+            Transaction::write(0x6b, vec![ctrl6c::ADDR, 0b100 << 5]),
+        ]);
+
+        let mut sensor = Ism330Dhcx::new(&mut i2c).unwrap();
+        sensor.set_den(&mut i2c, Some(den)).unwrap();
+        assert_eq!(sensor.den(), Some(den));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn markers() {
+        let stamp = DenStamp::default();
+        let gyro = GyroValue::new(Fs::Dps250, [3, 2, 0]);
+        let accel = AccelValue::new(Fs_Xl::G2, [0, 1, 0]);
+
+        assert_eq!(gyro.den(stamp), Some(true));
+        assert_eq!(accel.den(stamp), None);
+
+        let stamp = DenStamp {
+            sensor: DenSensor::Accel,
+            axes: [false, true, true],
+        };
+        assert_eq!(fifo::Value::Accel(accel).den(stamp), Some(true));
+        assert_eq!(fifo::Value::Gyro(gyro).den(stamp), None);
+        assert_eq!(fifo::Value::Timestamp(1).den(stamp), None);
+
+        let stamp = DenStamp {
+            axes: [false; 3],
+            ..stamp
+        };
+        assert_eq!(accel.den(stamp), None);
+    }
+}
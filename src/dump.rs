@@ -0,0 +1,1166 @@
+//! Snapshot, compare and restore the register map.
+//!
+//! [`RegisterDump`] holds every register documented in [`REGISTERS`], on the main page and on the
+//! embedded functions and sensor hub pages reached through `FUNC_CFG_ACCESS`. Its `Display` is a
+//! field-decoded report, and two dumps can be compared with [`RegisterDump::diff`].
+//!
+//! ```no_run
+//! # fn run<I2C: embedded_hal::i2c::I2c>(i2c: &mut I2C) -> Result<(), ism330dhcx::Error<I2C::Error>> {
+//! use ism330dhcx::ctrl1xl::Odr_Xl;
+//! use ism330dhcx::Ism330Dhcx;
+//!
+//! let mut sensor = Ism330Dhcx::new(i2c)?;
+//! let before = sensor.dump(i2c)?;
+//! sensor.ctrl1xl.set_accelerometer_data_rate(i2c, Odr_Xl::Hz104)?;
+//! let after = sensor.dump(i2c)?;
+//!
+//! for change in before.diff(&after) {
+//!     println!("{}", change);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `FIFO_DATA_OUT` is left out since reading it pops the FIFO. Registers cleared by reading them,
+//! the interrupt sources and the status registers, are marked [`RegisterInfo::clear_on_read`] and
+//! only read on request, see [`RegisterDump::read_with`]. Other reads keep their usual side
+//! effects, for example reading `FIFO_STATUS2` clears `FIFO_OVR_LATCHED`.
+
+use core::fmt;
+
+use embedded_hal::i2c::I2c;
+
+/// Address of `FUNC_CFG_ACCESS`, present on every page.
+const FUNC_CFG_ACCESS: u8 = 0x01;
+
+/// A register page, selected through `FUNC_CFG_ACCESS`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Page {
+    Main,
+    /// Embedded functions registers, `FUNC_CFG_ACCESS` bit 7.
+    Embedded,
+    /// Sensor hub registers, `SHUB_REG_ACCESS` bit 6.
+    SensorHub,
+}
+
+impl Page {
+    /// All pages, in the order they are read and restored.
+    pub const ALL: [Page; 3] = [Page::Main, Page::Embedded, Page::SensorHub];
+
+    /// Value of `FUNC_CFG_ACCESS` selecting the page.
+    pub fn access(&self) -> u8 {
+        match self {
+            Page::Main => 0,
+            Page::Embedded => 1 << 7,
+            Page::SensorHub => 1 << 6,
+        }
+    }
+
+    /// Short name used in reports.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Page::Main => "main",
+            Page::Embedded => "embedded functions",
+            Page::SensorHub => "sensor hub",
+        }
+    }
+}
+
+/// A named group of bits in a register.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Field {
+    pub name: &'static str,
+    /// Position of the least significant bit.
+    pub shift: u8,
+    /// Number of bits.
+    pub width: u8,
+}
+
+impl Field {
+    const fn new(name: &'static str, shift: u8, width: u8) -> Self {
+        Field { name, shift, width }
+    }
+
+    /// Mask of the field in the register.
+    pub fn mask(&self) -> u8 {
+        (((1u16 << self.width) - 1) as u8) << self.shift
+    }
+
+    /// Value of the field in a register value.
+    pub fn get(&self, value: u8) -> u8 {
+        (value & self.mask()) >> self.shift
+    }
+}
+
+/// A documented register.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct RegisterInfo {
+    pub page: Page,
+    pub addr: u8,
+    pub name: &'static str,
+    /// Bits written back on restore. Read-only registers, self-clearing bits and triggers are left
+    /// out.
+    pub writable: u8,
+    /// Decoded fields, empty when the register holds a single value.
+    pub fields: &'static [Field],
+    /// Reading the register clears its flags, so it is skipped by [`RegisterDump::read`].
+    pub clear_on_read: bool,
+}
+
+const fn r(addr: u8, name: &'static str, fields: &'static [Field]) -> RegisterInfo {
+    RegisterInfo {
+        page: Page::Main,
+        addr,
+        name,
+        writable: 0,
+        fields,
+        clear_on_read: false,
+    }
+}
+
+const fn rw(addr: u8, name: &'static str, fields: &'static [Field]) -> RegisterInfo {
+    RegisterInfo {
+        writable: 0xff,
+        ..r(addr, name, fields)
+    }
+}
+
+const fn on(page: Page, register: RegisterInfo) -> RegisterInfo {
+    RegisterInfo { page, ..register }
+}
+
+const fn writable(mask: u8, register: RegisterInfo) -> RegisterInfo {
+    RegisterInfo {
+        writable: mask,
+        ..register
+    }
+}
+
+const fn cleared(register: RegisterInfo) -> RegisterInfo {
+    RegisterInfo {
+        clear_on_read: true,
+        ..register
+    }
+}
+
+const fn b(name: &'static str, bit: u8) -> Field {
+    Field::new(name, bit, 1)
+}
+
+const fn bits(name: &'static str, msb: u8, lsb: u8) -> Field {
+    Field::new(name, lsb, msb - lsb + 1)
+}
+
+const E: Page = Page::Embedded;
+const S: Page = Page::SensorHub;
+
+/// Every documented register, ordered by page and address.
+pub const REGISTERS: &[RegisterInfo] = &[
+    rw(
+        0x01,
+        "FUNC_CFG_ACCESS",
+        &[b("FUNC_CFG_ACCESS", 7), b("SHUB_REG_ACCESS", 6)],
+    ),
+    rw(0x02, "PIN_CTRL", &[b("OIS_PU_DIS", 7), b("SDO_PU_EN", 6)]),
+    rw(0x07, "FIFO_CTRL1", &[bits("WTM", 7, 0)]),
+    rw(
+        0x08,
+        "FIFO_CTRL2",
+        &[
+            b("STOP_ON_WTM", 7),
+            b("FIFO_COMPR_RT_EN", 6),
+            b("ODRCHG_EN", 4),
+            bits("UNCOPTR_RATE", 2, 1),
+            b("WTM8", 0),
+        ],
+    ),
+    rw(
+        0x09,
+        "FIFO_CTRL3",
+        &[bits("BDR_GY", 7, 4), bits("BDR_XL", 3, 0)],
+    ),
+    rw(
+        0x0a,
+        "FIFO_CTRL4",
+        &[
+            bits("DEC_TS_BATCH", 7, 6),
+            bits("ODR_T_BATCH", 5, 4),
+            bits("FIFO_MODE", 2, 0),
+        ],
+    ),
+    writable(
+        0b1010_0111,
+        r(
+            0x0b,
+            "COUNTER_BDR_REG1",
+            &[
+                b("DATAREADY_PULSED", 7),
+                b("RST_COUNTER_BDR", 6),
+                b("TRIG_COUNTER_BDR", 5),
+                bits("CNT_BDR_TH_HI", 2, 0),
+            ],
+        ),
+    ),
+    rw(0x0c, "COUNTER_BDR_REG2", &[bits("CNT_BDR_TH_LO", 7, 0)]),
+    rw(
+        0x0d,
+        "INT1_CTRL",
+        &[
+            b("DEN_DRDY_FLAG", 7),
+            b("INT1_CNT_BDR", 6),
+            b("INT1_FIFO_FULL", 5),
+            b("INT1_FIFO_OVR", 4),
+            b("INT1_FIFO_TH", 3),
+            b("INT1_BOOT", 2),
+            b("INT1_DRDY_G", 1),
+            b("INT1_DRDY_XL", 0),
+        ],
+    ),
+    rw(
+        0x0e,
+        "INT2_CTRL",
+        &[
+            b("INT2_CNT_BDR", 6),
+            b("INT2_FIFO_FULL", 5),
+            b("INT2_FIFO_OVR", 4),
+            b("INT2_FIFO_TH", 3),
+            b("INT2_DRDY_TEMP", 2),
+            b("INT2_DRDY_G", 1),
+            b("INT2_DRDY_XL", 0),
+        ],
+    ),
+    r(0x0f, "WHO_AM_I", &[]),
+    rw(
+        0x10,
+        "CTRL1_XL",
+        &[
+            bits("ODR_XL", 7, 4),
+            bits("FS_XL", 3, 2),
+            b("LPF2_XL_EN", 1),
+        ],
+    ),
+    rw(
+        0x11,
+        "CTRL2_G",
+        &[
+            bits("ODR_G", 7, 4),
+            bits("FS_G", 3, 2),
+            b("FS_125", 1),
+            b("FS_4000", 0),
+        ],
+    ),
+    writable(
+        0b0111_1110,
+        r(
+            0x12,
+            "CTRL3_C",
+            &[
+                b("BOOT", 7),
+                b("BDU", 6),
+                b("H_LACTIVE", 5),
+                b("PP_OD", 4),
+                b("SIM", 3),
+                b("IF_INC", 2),
+                b("SW_RESET", 0),
+            ],
+        ),
+    ),
+    rw(
+        0x13,
+        "CTRL4_C",
+        &[
+            b("SLEEP_G", 6),
+            b("INT2_ON_INT1", 5),
+            b("DRDY_MASK", 3),
+            b("I2C_DISABLE", 2),
+            b("LPF1_SEL_G", 1),
+        ],
+    ),
+    rw(
+        0x14,
+        "CTRL5_C",
+        &[
+            bits("ROUNDING", 6, 5),
+            bits("ST_G", 3, 2),
+            bits("ST_XL", 1, 0),
+        ],
+    ),
+    rw(
+        0x15,
+        "CTRL6_C",
+        &[
+            b("TRIG_EN", 7),
+            b("LVL1_EN", 6),
+            b("LVL2_EN", 5),
+            b("XL_HM_MODE", 4),
+            b("USR_OFF_W", 3),
+            bits("FTYPE", 2, 0),
+        ],
+    ),
+    rw(
+        0x16,
+        "CTRL7_G",
+        &[
+            b("G_HM_MODE", 7),
+            b("HP_EN_G", 6),
+            bits("HPM_G", 5, 4),
+            b("OIS_ON_EN", 2),
+            b("USR_OFF_ON_OUT", 1),
+            b("OIS_ON", 0),
+        ],
+    ),
+    rw(
+        0x17,
+        "CTRL8_XL",
+        &[
+            bits("HPCF_XL", 7, 5),
+            b("HP_REF_MODE_XL", 4),
+            b("FASTSETTL_MODE_XL", 3),
+            b("HP_SLOPE_XL_EN", 2),
+            b("LOW_PASS_ON_6D", 0),
+        ],
+    ),
+    rw(
+        0x18,
+        "CTRL9_XL",
+        &[
+            b("DEN_X", 7),
+            b("DEN_Y", 6),
+            b("DEN_Z", 5),
+            b("DEN_XL_G", 4),
+            b("DEN_XL_EN", 3),
+            b("DEN_LH", 2),
+            b("DEVICE_CONF", 1),
+        ],
+    ),
+    rw(0x19, "CTRL10_C", &[b("TIMESTAMP_EN", 5)]),
+    cleared(r(
+        0x1a,
+        "ALL_INT_SRC",
+        &[
+            b("TIMESTAMP_ENDCOUNT", 7),
+            b("SLEEP_CHANGE_IA", 5),
+            b("D6D_IA", 4),
+            b("DOUBLE_TAP", 3),
+            b("SINGLE_TAP", 2),
+            b("WU_IA", 1),
+            b("FF_IA", 0),
+        ],
+    )),
+    cleared(r(
+        0x1b,
+        "WAKE_UP_SRC",
+        &[
+            b("SLEEP_CHANGE_IA", 6),
+            b("FF_IA", 5),
+            b("SLEEP_STATE", 4),
+            b("WU_IA", 3),
+            b("X_WU", 2),
+            b("Y_WU", 1),
+            b("Z_WU", 0),
+        ],
+    )),
+    cleared(r(
+        0x1c,
+        "TAP_SRC",
+        &[
+            b("TAP_IA", 6),
+            b("SINGLE_TAP", 5),
+            b("DOUBLE_TAP", 4),
+            b("TAP_SIGN", 3),
+            b("X_TAP", 2),
+            b("Y_TAP", 1),
+            b("Z_TAP", 0),
+        ],
+    )),
+    cleared(r(
+        0x1d,
+        "D6D_SRC",
+        &[
+            b("DEN_DRDY", 7),
+            b("D6D_IA", 6),
+            b("ZH", 5),
+            b("ZL", 4),
+            b("YH", 3),
+            b("YL", 2),
+            b("XH", 1),
+            b("XL", 0),
+        ],
+    )),
+    cleared(r(
+        0x1e,
+        "STATUS_REG",
+        &[b("TDA", 2), b("GDA", 1), b("XLDA", 0)],
+    )),
+    r(0x20, "OUT_TEMP_L", &[]),
+    r(0x21, "OUT_TEMP_H", &[]),
+    r(0x22, "OUTX_L_G", &[]),
+    r(0x23, "OUTX_H_G", &[]),
+    r(0x24, "OUTY_L_G", &[]),
+    r(0x25, "OUTY_H_G", &[]),
+    r(0x26, "OUTZ_L_G", &[]),
+    r(0x27, "OUTZ_H_G", &[]),
+    r(0x28, "OUTX_L_A", &[]),
+    r(0x29, "OUTX_H_A", &[]),
+    r(0x2a, "OUTY_L_A", &[]),
+    r(0x2b, "OUTY_H_A", &[]),
+    r(0x2c, "OUTZ_L_A", &[]),
+    r(0x2d, "OUTZ_H_A", &[]),
+    cleared(r(
+        0x35,
+        "EMB_FUNC_STATUS_MAINPAGE",
+        &[
+            b("IS_FSM_LC", 7),
+            b("IS_SIGMOT", 5),
+            b("IS_TILT", 4),
+            b("IS_STEP_DET", 3),
+        ],
+    )),
+    cleared(r(0x36, "FSM_STATUS_A_MAINPAGE", &[])),
+    cleared(r(0x37, "FSM_STATUS_B_MAINPAGE", &[])),
+    cleared(r(0x38, "MLC_STATUS_MAINPAGE", &[])),
+    cleared(r(
+        0x39,
+        "STATUS_MASTER_MAINPAGE",
+        &[
+            b("WR_ONCE_DONE", 7),
+            b("SLAVE3_NACK", 6),
+            b("SLAVE2_NACK", 5),
+            b("SLAVE1_NACK", 4),
+            b("SLAVE0_NACK", 3),
+            b("SENS_HUB_ENDOP", 0),
+        ],
+    )),
+    r(0x3a, "FIFO_STATUS1", &[bits("DIFF_FIFO_LO", 7, 0)]),
+    r(
+        0x3b,
+        "FIFO_STATUS2",
+        &[
+            b("FIFO_WTM_IA", 7),
+            b("FIFO_OVR_IA", 6),
+            b("FIFO_FULL_IA", 5),
+            b("COUNTER_BDR_IA", 4),
+            b("FIFO_OVR_LATCHED", 3),
+            bits("DIFF_FIFO_HI", 1, 0),
+        ],
+    ),
+    r(0x40, "TIMESTAMP0", &[]),
+    r(0x41, "TIMESTAMP1", &[]),
+    r(0x42, "TIMESTAMP2", &[]),
+    r(0x43, "TIMESTAMP3", &[]),
+    rw(
+        0x56,
+        "TAP_CFG0",
+        &[
+            b("INT_CLR_ON_READ", 6),
+            b("SLEEP_STATUS_ON_INT", 5),
+            b("SLOPE_FDS", 4),
+            b("TAP_X_EN", 3),
+            b("TAP_Y_EN", 2),
+            b("TAP_Z_EN", 1),
+            b("LIR", 0),
+        ],
+    ),
+    rw(
+        0x57,
+        "TAP_CFG1",
+        &[bits("TAP_PRIORITY", 7, 5), bits("TAP_THS_X", 4, 0)],
+    ),
+    rw(
+        0x58,
+        "TAP_CFG2",
+        &[
+            b("INTERRUPTS_ENABLE", 7),
+            bits("INACT_EN", 6, 5),
+            bits("TAP_THS_Y", 4, 0),
+        ],
+    ),
+    rw(
+        0x59,
+        "TAP_THS_6D",
+        &[
+            b("D4D_EN", 7),
+            bits("SIXD_THS", 6, 5),
+            bits("TAP_THS_Z", 4, 0),
+        ],
+    ),
+    rw(
+        0x5a,
+        "INT_DUR2",
+        &[bits("DUR", 7, 4), bits("QUIET", 3, 2), bits("SHOCK", 1, 0)],
+    ),
+    rw(
+        0x5b,
+        "WAKE_UP_THS",
+        &[
+            b("SINGLE_DOUBLE_TAP", 7),
+            b("USR_OFF_ON_WU", 6),
+            bits("WK_THS", 5, 0),
+        ],
+    ),
+    rw(
+        0x5c,
+        "WAKE_UP_DUR",
+        &[
+            b("FF_DUR5", 7),
+            bits("WAKE_DUR", 6, 5),
+            b("WAKE_THS_W", 4),
+            bits("SLEEP_DUR", 3, 0),
+        ],
+    ),
+    rw(
+        0x5d,
+        "FREE_FALL",
+        &[bits("FF_DUR", 7, 3), bits("FF_THS", 2, 0)],
+    ),
+    rw(
+        0x5e,
+        "MD1_CFG",
+        &[
+            b("INT1_SLEEP_CHANGE", 7),
+            b("INT1_SINGLE_TAP", 6),
+            b("INT1_WU", 5),
+            b("INT1_FF", 4),
+            b("INT1_DOUBLE_TAP", 3),
+            b("INT1_6D", 2),
+            b("INT1_EMB_FUNC", 1),
+            b("INT1_SHUB", 0),
+        ],
+    ),
+    rw(
+        0x5f,
+        "MD2_CFG",
+        &[
+            b("INT2_SLEEP_CHANGE", 7),
+            b("INT2_SINGLE_TAP", 6),
+            b("INT2_WU", 5),
+            b("INT2_FF", 4),
+            b("INT2_DOUBLE_TAP", 3),
+            b("INT2_6D", 2),
+            b("INT2_EMB_FUNC", 1),
+            b("INT2_TIMESTAMP", 0),
+        ],
+    ),
+    r(0x62, "INTERNAL_FREQ_FINE", &[bits("FREQ_FINE", 7, 0)]),
+    r(
+        0x6f,
+        "INT_OIS",
+        &[
+            b("INT2_DRDY_OIS", 7),
+            b("LVL2_OIS", 6),
+            b("DEN_LH_OIS", 5),
+            bits("ST_XL_OIS", 1, 0),
+        ],
+    ),
+    r(0x70, "CTRL1_OIS", &[]),
+    r(0x71, "CTRL2_OIS", &[]),
+    r(0x72, "CTRL3_OIS", &[]),
+    rw(0x73, "X_OFS_USR", &[]),
+    rw(0x74, "Y_OFS_USR", &[]),
+    rw(0x75, "Z_OFS_USR", &[]),
+    // Embedded functions page.
+    on(E, rw(0x02, "PAGE_SEL", &[bits("PAGE_SEL", 7, 4)])),
+    on(
+        E,
+        rw(
+            0x04,
+            "EMB_FUNC_EN_A",
+            &[b("SIGN_MOTION_EN", 5), b("TILT_EN", 4), b("PEDO_EN", 3)],
+        ),
+    ),
+    on(
+        E,
+        rw(
+            0x05,
+            "EMB_FUNC_EN_B",
+            &[b("MLC_EN", 4), b("FIFO_COMPR_EN", 3), b("FSM_EN", 0)],
+        ),
+    ),
+    on(
+        E,
+        rw(
+            0x0a,
+            "EMB_FUNC_INT1",
+            &[
+                b("INT1_FSM_LC", 7),
+                b("INT1_SIG_MOT", 5),
+                b("INT1_TILT", 4),
+                b("INT1_STEP_DETECTOR", 3),
+            ],
+        ),
+    ),
+    on(E, rw(0x0b, "FSM_INT1_A", &[])),
+    on(E, rw(0x0c, "FSM_INT1_B", &[])),
+    on(E, rw(0x0d, "MLC_INT1", &[])),
+    on(
+        E,
+        rw(
+            0x0e,
+            "EMB_FUNC_INT2",
+            &[
+                b("INT2_FSM_LC", 7),
+                b("INT2_SIG_MOT", 5),
+                b("INT2_TILT", 4),
+                b("INT2_STEP_DETECTOR", 3),
+            ],
+        ),
+    ),
+    on(E, rw(0x0f, "FSM_INT2_A", &[])),
+    on(E, rw(0x10, "FSM_INT2_B", &[])),
+    on(E, rw(0x11, "MLC_INT2", &[])),
+    on(
+        E,
+        cleared(r(
+            0x12,
+            "EMB_FUNC_STATUS",
+            &[
+                b("IS_FSM_LC", 7),
+                b("IS_SIGMOT", 5),
+                b("IS_TILT", 4),
+                b("IS_STEP_DET", 3),
+            ],
+        )),
+    ),
+    on(E, cleared(r(0x13, "FSM_STATUS_A", &[]))),
+    on(E, cleared(r(0x14, "FSM_STATUS_B", &[]))),
+    on(E, cleared(r(0x15, "MLC_STATUS", &[]))),
+    on(
+        E,
+        writable(
+            0b1000_0000,
+            r(
+                0x17,
+                "PAGE_RW",
+                &[b("EMB_FUNC_LIR", 7), b("PAGE_WRITE", 6), b("PAGE_READ", 5)],
+            ),
+        ),
+    ),
+    on(E, rw(0x44, "EMB_FUNC_FIFO_CFG", &[b("PEDO_FIFO_EN", 6)])),
+    on(E, rw(0x46, "FSM_ENABLE_A", &[])),
+    on(E, rw(0x47, "FSM_ENABLE_B", &[])),
+    on(E, rw(0x48, "FSM_LONG_COUNTER_L", &[])),
+    on(E, rw(0x49, "FSM_LONG_COUNTER_H", &[])),
+    on(
+        E,
+        r(
+            0x4b,
+            "FSM_LONG_COUNTER_CLEAR",
+            &[b("FSM_LC_CLEARED", 1), b("FSM_LC_CLEAR", 0)],
+        ),
+    ),
+    on(E, r(0x4c, "FSM_OUTS1", &[])),
+    on(E, r(0x4d, "FSM_OUTS2", &[])),
+    on(E, r(0x4e, "FSM_OUTS3", &[])),
+    on(E, r(0x4f, "FSM_OUTS4", &[])),
+    on(E, r(0x50, "FSM_OUTS5", &[])),
+    on(E, r(0x51, "FSM_OUTS6", &[])),
+    on(E, r(0x52, "FSM_OUTS7", &[])),
+    on(E, r(0x53, "FSM_OUTS8", &[])),
+    on(E, r(0x54, "FSM_OUTS9", &[])),
+    on(E, r(0x55, "FSM_OUTS10", &[])),
+    on(E, r(0x56, "FSM_OUTS11", &[])),
+    on(E, r(0x57, "FSM_OUTS12", &[])),
+    on(E, r(0x58, "FSM_OUTS13", &[])),
+    on(E, r(0x59, "FSM_OUTS14", &[])),
+    on(E, r(0x5a, "FSM_OUTS15", &[])),
+    on(E, r(0x5b, "FSM_OUTS16", &[])),
+    on(E, rw(0x5f, "EMB_FUNC_ODR_CFG_B", &[bits("FSM_ODR", 5, 3)])),
+    on(E, rw(0x60, "EMB_FUNC_ODR_CFG_C", &[bits("MLC_ODR", 5, 4)])),
+    on(E, r(0x62, "STEP_COUNTER_L", &[])),
+    on(E, r(0x63, "STEP_COUNTER_H", &[])),
+    on(
+        E,
+        r(
+            0x64,
+            "EMB_FUNC_SRC",
+            &[
+                b("PEDO_RST_STEP", 7),
+                b("STEP_DETECTED", 5),
+                b("STEP_COUNT_DELTA_IA", 4),
+                b("STEP_OVERFLOW", 3),
+                b("STEPCOUNTER_BIT_SET", 2),
+            ],
+        ),
+    ),
+    on(
+        E,
+        r(
+            0x66,
+            "EMB_FUNC_INIT_A",
+            &[
+                b("SIG_MOT_INIT", 5),
+                b("TILT_INIT", 4),
+                b("STEP_DET_INIT", 3),
+            ],
+        ),
+    ),
+    on(
+        E,
+        r(
+            0x67,
+            "EMB_FUNC_INIT_B",
+            &[b("MLC_INIT", 4), b("FIFO_COMPR_INIT", 3), b("FSM_INIT", 0)],
+        ),
+    ),
+    on(E, r(0x70, "MLC0_SRC", &[])),
+    on(E, r(0x71, "MLC1_SRC", &[])),
+    on(E, r(0x72, "MLC2_SRC", &[])),
+    on(E, r(0x73, "MLC3_SRC", &[])),
+    on(E, r(0x74, "MLC4_SRC", &[])),
+    on(E, r(0x75, "MLC5_SRC", &[])),
+    on(E, r(0x76, "MLC6_SRC", &[])),
+    on(E, r(0x77, "MLC7_SRC", &[])),
+    // Sensor hub page.
+    on(S, r(0x02, "SENSOR_HUB_1", &[])),
+    on(S, r(0x03, "SENSOR_HUB_2", &[])),
+    on(S, r(0x04, "SENSOR_HUB_3", &[])),
+    on(S, r(0x05, "SENSOR_HUB_4", &[])),
+    on(S, r(0x06, "SENSOR_HUB_5", &[])),
+    on(S, r(0x07, "SENSOR_HUB_6", &[])),
+    on(S, r(0x08, "SENSOR_HUB_7", &[])),
+    on(S, r(0x09, "SENSOR_HUB_8", &[])),
+    on(S, r(0x0a, "SENSOR_HUB_9", &[])),
+    on(S, r(0x0b, "SENSOR_HUB_10", &[])),
+    on(S, r(0x0c, "SENSOR_HUB_11", &[])),
+    on(S, r(0x0d, "SENSOR_HUB_12", &[])),
+    on(S, r(0x0e, "SENSOR_HUB_13", &[])),
+    on(S, r(0x0f, "SENSOR_HUB_14", &[])),
+    on(S, r(0x10, "SENSOR_HUB_15", &[])),
+    on(S, r(0x11, "SENSOR_HUB_16", &[])),
+    on(S, r(0x12, "SENSOR_HUB_17", &[])),
+    on(S, r(0x13, "SENSOR_HUB_18", &[])),
+    on(
+        S,
+        writable(
+            0b0111_1111,
+            r(
+                0x14,
+                "MASTER_CONFIG",
+                &[
+                    b("RST_MASTER_REGS", 7),
+                    b("WRITE_ONCE", 6),
+                    b("START_CONFIG", 5),
+                    b("PASS_THROUGH_MODE", 4),
+                    b("SHUB_PU_EN", 3),
+                    b("MASTER_ON", 2),
+                    bits("AUX_SENS_ON", 1, 0),
+                ],
+            ),
+        ),
+    ),
+    on(
+        S,
+        rw(0x15, "SLV0_ADD", &[bits("SLAVE0_ADD", 7, 1), b("RW_0", 0)]),
+    ),
+    on(S, rw(0x16, "SLV0_SUBADD", &[])),
+    on(
+        S,
+        rw(
+            0x17,
+            "SLV0_CONFIG",
+            &[
+                bits("SHUB_ODR", 7, 6),
+                b("BATCH_EXT_SENS_0_EN", 3),
+                bits("SLAVE0_NUMOP", 2, 0),
+            ],
+        ),
+    ),
+    on(
+        S,
+        rw(0x18, "SLV1_ADD", &[bits("SLAVE1_ADD", 7, 1), b("R_1", 0)]),
+    ),
+    on(S, rw(0x19, "SLV1_SUBADD", &[])),
+    on(
+        S,
+        rw(
+            0x1a,
+            "SLV1_CONFIG",
+            &[b("BATCH_EXT_SENS_1_EN", 3), bits("SLAVE1_NUMOP", 2, 0)],
+        ),
+    ),
+    on(
+        S,
+        rw(0x1b, "SLV2_ADD", &[bits("SLAVE2_ADD", 7, 1), b("R_2", 0)]),
+    ),
+    on(S, rw(0x1c, "SLV2_SUBADD", &[])),
+    on(
+        S,
+        rw(
+            0x1d,
+            "SLV2_CONFIG",
+            &[b("BATCH_EXT_SENS_2_EN", 3), bits("SLAVE2_NUMOP", 2, 0)],
+        ),
+    ),
+    on(
+        S,
+        rw(0x1e, "SLV3_ADD", &[bits("SLAVE3_ADD", 7, 1), b("R_3", 0)]),
+    ),
+    on(S, rw(0x1f, "SLV3_SUBADD", &[])),
+    on(
+        S,
+        rw(
+            0x20,
+            "SLV3_CONFIG",
+            &[b("BATCH_EXT_SENS_3_EN", 3), bits("SLAVE3_NUMOP", 2, 0)],
+        ),
+    ),
+    on(S, rw(0x21, "DATAWRITE_SLV0", &[])),
+    on(
+        S,
+        cleared(r(
+            0x22,
+            "STATUS_MASTER",
+            &[
+                b("WR_ONCE_DONE", 7),
+                b("SLAVE3_NACK", 6),
+                b("SLAVE2_NACK", 5),
+                b("SLAVE1_NACK", 4),
+                b("SLAVE0_NACK", 3),
+                b("SENS_HUB_ENDOP", 0),
+            ],
+        )),
+    ),
+];
+
+/// Look up a documented register.
+pub fn find(page: Page, addr: u8) -> Option<&'static RegisterInfo> {
+    REGISTERS.iter().find(|r| r.page == page && r.addr == addr)
+}
+
+fn select<I2C: I2c>(i2c: &mut I2C, address: u8, access: u8) -> Result<(), I2C::Error> {
+    i2c.write(address, &[FUNC_CFG_ACCESS, access])
+}
+
+/// Values of all the registers in [`REGISTERS`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RegisterDump {
+    values: [u8; REGISTERS.len()],
+    /// The [`RegisterInfo::clear_on_read`] registers were read.
+    clear_on_read: bool,
+}
+
+impl RegisterDump {
+    /// Read every documented register, one at a time, except the clear-on-read ones.
+    ///
+    /// `FUNC_CFG_ACCESS` is restored once the other pages have been read.
+    pub fn read<I2C: I2c>(i2c: &mut I2C, address: u8) -> Result<Self, I2C::Error> {
+        Self::read_with(i2c, address, false)
+    }
+
+    /// Read every documented register, with the clear-on-read ones if `clear_on_read` is set.
+    ///
+    /// Reading them acknowledges the interrupts and status flags they hold, which are then gone
+    /// for the application.
+    pub fn read_with<I2C: I2c>(
+        i2c: &mut I2C,
+        address: u8,
+        clear_on_read: bool,
+    ) -> Result<Self, I2C::Error> {
+        let mut access = [0u8];
+        i2c.write_read(address, &[FUNC_CFG_ACCESS], &mut access)?;
+
+        let mut values = [0u8; REGISTERS.len()];
+        for page in Page::ALL {
+            select(i2c, address, page.access())?;
+
+            for (info, value) in REGISTERS.iter().zip(values.iter_mut()) {
+                if info.page != page || (info.clear_on_read && !clear_on_read) {
+                    continue;
+                }
+
+                let mut data = [0u8];
+                i2c.write_read(address, &[info.addr], &mut data)?;
+                *value = data[0];
+            }
+        }
+
+        select(i2c, address, access[0])?;
+
+        let mut dump = RegisterDump {
+            values,
+            clear_on_read,
+        };
+        dump.values[0] = access[0];
+        Ok(dump)
+    }
+
+    /// Value of a register, if it is part of the dump.
+    pub fn get(&self, page: Page, addr: u8) -> Option<u8> {
+        REGISTERS
+            .iter()
+            .position(|r| r.page == page && r.addr == addr)
+            .filter(|&i| self.has(&REGISTERS[i]))
+            .map(|i| self.values[i])
+    }
+
+    /// Registers with their values.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static RegisterInfo, u8)> + '_ {
+        REGISTERS
+            .iter()
+            .zip(self.values.iter().copied())
+            .filter(|(info, _)| self.has(info))
+    }
+
+    /// Registers that differ between `self` and `other`, among the ones both have read.
+    pub fn diff<'a>(&'a self, other: &'a RegisterDump) -> impl Iterator<Item = Change> + 'a {
+        REGISTERS
+            .iter()
+            .zip(self.values.iter().zip(other.values.iter()))
+            .filter(|(info, _)| self.has(info) && other.has(info))
+            .filter(|(_, (before, after))| before != after)
+            .map(|(register, (&before, &after))| Change {
+                register,
+                before,
+                after,
+            })
+    }
+
+    /// The register was read, all but the clear-on-read ones unless asked for.
+    fn has(&self, info: &RegisterInfo) -> bool {
+        !info.clear_on_read || self.clear_on_read
+    }
+
+    /// Write the writable bits of the dump back to the device.
+    ///
+    /// The embedded functions and sensor hub pages are written first, then the main page, and
+    /// `FUNC_CFG_ACCESS` last. Bits outside [`RegisterInfo::writable`] keep their current value.
+    pub fn restore<I2C: I2c>(&self, i2c: &mut I2C, address: u8) -> Result<(), I2C::Error> {
+        for page in [Page::Embedded, Page::SensorHub, Page::Main] {
+            select(i2c, address, page.access())?;
+
+            for (info, value) in self.iter() {
+                if info.page != page || info.writable == 0 || info.addr == FUNC_CFG_ACCESS {
+                    continue;
+                }
+
+                let value = if info.writable == 0xff {
+                    value
+                } else {
+                    let mut current = [0u8];
+                    i2c.write_read(address, &[info.addr], &mut current)?;
+                    (current[0] & !info.writable) | (value & info.writable)
+                };
+
+                i2c.write(address, &[info.addr, value])?;
+            }
+        }
+
+        select(i2c, address, self.values[0])
+    }
+}
+
+impl fmt::Display for RegisterDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut page = Page::Main;
+
+        for (info, value) in self.iter() {
+            if info.page != page {
+                page = info.page;
+                writeln!(f, "[{}]", page.name())?;
+            }
+
+            write!(f, "{:<24} 0x{:02x} = 0x{:02x}", info.name, info.addr, value)?;
+            for field in info.fields {
+                write!(f, " {}=", field.name)?;
+                write_field(f, field, field.get(value))?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_field(f: &mut fmt::Formatter<'_>, field: &Field, value: u8) -> fmt::Result {
+    match field.width {
+        1 => write!(f, "{}", value),
+        width => write!(f, "0b{:0width$b}", value, width = width as usize),
+    }
+}
+
+/// A register that differs between two dumps.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Change {
+    pub register: &'static RegisterInfo,
+    pub before: u8,
+    pub after: u8,
+}
+
+impl Change {
+    /// Fields that changed, with their values before and after.
+    pub fn fields(&self) -> impl Iterator<Item = (&'static Field, u8, u8)> + '_ {
+        self.register
+            .fields
+            .iter()
+            .map(|field| (field, field.get(self.before), field.get(self.after)))
+            .filter(|(_, before, after)| before != after)
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let register = self.register;
+        write!(
+            f,
+            "{} ({} 0x{:02x}):",
+            register.name,
+            register.page.name(),
+            register.addr
+        )?;
+
+        let decoded = register
+            .fields
+            .iter()
+            .fold(0, |mask, field| mask | field.mask());
+        if (self.before ^ self.after) & !decoded != 0 {
+            return write!(f, " 0x{:02x} -> 0x{:02x}", self.before, self.after);
+        }
+
+        for (field, before, after) in self.fields() {
+            write!(f, " {} ", field.name)?;
+            write_field(f, field, before)?;
+            write!(f, " -> ")?;
+            write_field(f, field, after)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctrl1xl::Odr_Xl;
+    use crate::sim::Simulator;
+    use crate::Ism330Dhcx;
+
+    #[test]
+    fn table() {
+        assert_eq!(REGISTERS[0].addr, FUNC_CFG_ACCESS);
+
+        for pair in REGISTERS.windows(2) {
+            let key = |r: &RegisterInfo| (r.page as u8, r.addr);
+            assert!(
+                key(&pair[0]) < key(&pair[1]),
+                "{} out of order",
+                pair[1].name
+            );
+        }
+
+        for register in REGISTERS {
+            assert!(!(0x78..=0x7e).contains(&register.addr) || register.page != Page::Main);
+
+            let mut used = 0u8;
+            for field in register.fields {
+                assert_eq!(used & field.mask(), 0, "{} overlaps", field.name);
+                used |= field.mask();
+            }
+        }
+    }
+
+    #[test]
+    fn read() {
+        let mut sim = Simulator::new();
+        sim.write(0x6b, &[FUNC_CFG_ACCESS, 1 << 7]).unwrap();
+        sim.write(0x6b, &[0x04, 0b0010_0000]).unwrap();
+        sim.write(0x6b, &[FUNC_CFG_ACCESS, 0]).unwrap();
+
+        let dump = RegisterDump::read(&mut sim, 0x6b).unwrap();
+
+        assert_eq!(dump.get(Page::Main, 0x0f), Some(0x6b));
+        assert_eq!(dump.get(Page::Main, 0x12), Some(0x04));
+        assert_eq!(dump.get(Page::Embedded, 0x04), Some(0b0010_0000));
+        assert_eq!(dump.get(Page::Main, 0x78), None);
+        assert_eq!(sim.register(FUNC_CFG_ACCESS), 0);
+
+        let report = format!("{}", dump);
+        assert!(report.contains("WHO_AM_I                 0x0f = 0x6b\n"));
+        assert!(report.contains("CTRL3_C                  0x12 = 0x04 BOOT=0 BDU=0 H_LACTIVE=0 PP_OD=0 SIM=0 IF_INC=1 SW_RESET=0\n"));
+        assert!(report.contains("[embedded functions]\n"));
+        assert!(report.contains(
+            "EMB_FUNC_EN_A            0x04 = 0x20 SIGN_MOTION_EN=1 TILT_EN=0 PEDO_EN=0\n"
+        ));
+    }
+
+    #[test]
+    fn clear_on_read() {
+        let mut sim = Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, Odr_Xl::Hz104)
+            .unwrap();
+        sim.advance_us(20_000);
+
+        let dump = RegisterDump::read(&mut sim, 0x6b).unwrap();
+        assert_eq!(dump.get(Page::Main, 0x1e), None);
+        assert_eq!(dump.get(Page::Embedded, 0x12), None);
+        assert!(dump.iter().all(|(info, _)| !info.clear_on_read));
+        assert!(!format!("{}", dump).contains("STATUS_REG"));
+
+        sim.advance_us(20_000);
+        let all = RegisterDump::read_with(&mut sim, 0x6b, true).unwrap();
+        assert_eq!(all.get(Page::Main, 0x1e).map(|v| v & 1), Some(1));
+        assert_eq!(all.get(Page::Embedded, 0x12), Some(0));
+        assert_eq!(dump.diff(&all).count(), 0);
+    }
+
+    #[test]
+    fn diff() {
+        let mut sim = Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+
+        let before = sensor.dump(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, Odr_Xl::Hz104)
+            .unwrap();
+        sim.write(0x6b, &[0x73, 0x12]).unwrap();
+        let after = sensor.dump(&mut sim).unwrap();
+
+        let changes: Vec<Change> = before
+            .diff(&after)
+            .filter(|c| c.register.writable != 0)
+            .collect();
+        assert_eq!(changes.len(), 2);
+
+        assert_eq!(changes[0].register.name, "CTRL1_XL");
+        let fields: Vec<_> = changes[0]
+            .fields()
+            .map(|(f, b, a)| (f.name, b, a))
+            .collect();
+        assert_eq!(fields, [("ODR_XL", 0, 4)]);
+        assert_eq!(
+            format!("{}", changes[0]),
+            "CTRL1_XL (main 0x10): ODR_XL 0b0000 -> 0b0100"
+        );
+
+        assert_eq!(
+            format!("{}", changes[1]),
+            "X_OFS_USR (main 0x73): 0x00 -> 0x12"
+        );
+    }
+
+    #[test]
+    fn restore() {
+        let mut sim = Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, Odr_Xl::Hz52)
+            .unwrap();
+        sim.write(0x6b, &[FUNC_CFG_ACCESS, 1 << 6]).unwrap();
+        sim.write(0x6b, &[0x15, 0x3d]).unwrap();
+        sim.write(0x6b, &[FUNC_CFG_ACCESS, 0]).unwrap();
+        let saved = sensor.dump(&mut sim).unwrap();
+
+        let mut other = Simulator::new();
+        let mut target = Ism330Dhcx::new(&mut other).unwrap();
+        target.restore(&mut other, &saved).unwrap();
+
+        let restored = target.dump(&mut other).unwrap();
+        assert_eq!(restored.diff(&saved).count(), 0);
+        assert_eq!(target.ctrl1xl.accelerometer_data_rate(), 52.);
+    }
+}
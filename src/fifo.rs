@@ -1,13 +1,17 @@
 use core::convert::{TryFrom, TryInto};
 use embedded_hal::i2c::I2c;
 
-use crate::{ctrl1xl, ctrl2g, AccelValue, GyroValue, Register};
+use crate::fifoctrl::{BdrGy, BdrXl};
+use crate::{config, ctrl1xl, ctrl2g, AccelValue, GyroValue, Ism330Dhcx, Register};
 
 #[repr(u8)]
 pub enum SensorTag {
     Empty,
     GyroscopeNC,
     AccelerometerNC,
+    Temperature,
+    Timestamp,
+    ConfigChange,
     Other(u8),
 }
 
@@ -19,31 +23,150 @@ impl TryFrom<u8> for SensorTag {
             0x00 => Ok(SensorTag::Empty),
             0x01 => Ok(SensorTag::GyroscopeNC),
             0x02 => Ok(SensorTag::AccelerometerNC),
+            0x03 => Ok(SensorTag::Temperature),
+            0x04 => Ok(SensorTag::Timestamp),
+            0x05 => Ok(SensorTag::ConfigChange),
             x if x <= 0x19 => Ok(SensorTag::Other(x)),
             _ => Err(()),
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, defmt::Format)]
+#[derive(Copy, Clone, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     Empty,
     Gyro(GyroValue),
     Accel(AccelValue),
+    /// Temperature in Celsius.
+    Temperature(f32),
+    /// Timestamp counter, in 25 µs steps, batched ahead of the samples of its time slot.
+    Timestamp(u32),
+    /// `CFG-Change` word, batched when an ODR, BDR or full scale changes with `ODR_CHG_EN` set.
+    ConfigChange(ConfigChange),
+    /// Words discarded by a FIFO overrun, see [`Ism330Dhcx::fifo_read`]. Estimated from the
+    /// timestamp counter and the batch data rates, `None` with the counter stopped.
+    Gap {
+        lost: Option<u32>,
+    },
     Other(u8, [u8; 6]),
 }
 
 const ADDR: u8 = 0x78;
 
+/// Settings reported by a `CFG-Change` word, in effect for the words batched after it.
+///
+/// The payload holds `CTRL1_XL`, `CTRL2_G` and `FIFO_CTRL3` in its first three bytes. The
+/// accelerometer data rate is the register code, see [`ctrl1xl::Ctrl1Xl::effective_odr`] for the
+/// 1.6 Hz one.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigChange {
+    pub accel_odr: ctrl1xl::Odr_Xl,
+    pub accel_scale: ctrl1xl::Fs_Xl,
+    pub gyro_odr: ctrl2g::Odr,
+    pub gyro_scale: ctrl2g::Fs,
+    pub accel_bdr: BdrXl,
+    pub gyro_bdr: BdrGy,
+}
+
+impl ConfigChange {
+    pub fn from_msr(out: &[u8; 6]) -> Self {
+        let ctrl1xl = ctrl1xl::Ctrl1Xl::new(out[0], 0);
+        let ctrl2g = ctrl2g::Ctrl2G::new(out[1], 0);
+
+        ConfigChange {
+            accel_odr: ctrl1xl.accelerometer_odr(),
+            accel_scale: ctrl1xl.chain_full_scale(),
+            gyro_odr: ctrl2g.gyroscope_odr(),
+            gyro_scale: ctrl2g.chain_full_scale(),
+            accel_bdr: config::accel_bdr(out[2] & 0b1111),
+            gyro_bdr: config::gyro_bdr(out[2] >> 4),
+        }
+    }
+}
+
+/// What [`Ism330Dhcx::fifo_read`] does after a FIFO overrun.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+pub enum Recovery {
+    /// Report the gap and keep reading the words left in the FIFO.
+    Continue,
+    /// Report the gap, counting the words left in the FIFO, and empty it with a `Bypass` toggle.
+    Reset,
+}
+
 pub struct FifoOut {
     pub address: u8,
+    /// Full scales of the words ahead in the FIFO, `None` when they are the current ones.
+    batched: Option<(ctrl2g::Fs, ctrl1xl::Fs_Xl)>,
+    /// Last `CFG-Change` word popped.
+    change: Option<ConfigChange>,
+    /// Timestamp counter and words left in the FIFO at the end of the last read.
+    pub(crate) last_read: Option<(u32, u16)>,
+    /// `TAG_CNT` of the last word popped.
+    tag_cnt: u8,
 }
 
 impl Register for FifoOut {}
 
 impl FifoOut {
     pub fn new(address: u8) -> Self {
-        FifoOut { address }
+        FifoOut {
+            address,
+            batched: None,
+            change: None,
+            last_read: None,
+            tag_cnt: 0,
+        }
+    }
+
+    /// `TAG_CNT` of the last word popped, the same for all the words of a time slot.
+    pub fn tag_cnt(&self) -> u8 {
+        self.tag_cnt
+    }
+
+    /// Settings of the words popped since the last `CFG-Change` word, `None` before the first one.
+    pub fn config_change(&self) -> Option<ConfigChange> {
+        self.change
+    }
+
+    /// Keep scaling the words ahead in the FIFO with `gyro_scale` and `accel_scale`, until a
+    /// `CFG-Change` word reports the full scales that follow it.
+    ///
+    /// Only the first call counts until then, the words ahead were batched with the full scales it
+    /// was given.
+    pub fn track_scales(&mut self, gyro_scale: ctrl2g::Fs, accel_scale: ctrl1xl::Fs_Xl) {
+        if self.batched.is_none() {
+            self.batched = Some((gyro_scale, accel_scale));
+        }
+    }
+
+    /// Pop a value from the FIFO, scaled with the full scales in effect when it was batched.
+    ///
+    /// `gyro_scale` and `accel_scale` are the current full scales, used unless
+    /// [`FifoOut::track_scales`] or a `CFG-Change` word popped earlier says otherwise. An empty
+    /// FIFO ends the tracking.
+    pub fn pop_tracked<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        gyro_scale: ctrl2g::Fs,
+        accel_scale: ctrl1xl::Fs_Xl,
+    ) -> Result<Value, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        let (gyro_scale, accel_scale) = self.batched.unwrap_or((gyro_scale, accel_scale));
+
+        let value = self.pop(i2c, gyro_scale, accel_scale)?;
+        match value {
+            Value::Empty => self.batched = None,
+            Value::ConfigChange(change) => {
+                self.batched = Some((change.gyro_scale, change.accel_scale));
+                self.change = Some(change);
+            }
+            _ => {}
+        }
+        Ok(value)
     }
 
     /// Pop a value from the FIFO.
@@ -60,6 +183,7 @@ impl FifoOut {
         i2c.write_read(self.address, &[ADDR], &mut out)?;
 
         let (tag, out) = out.split_at(1);
+        self.tag_cnt = (tag[0] >> 1) & 0b11;
         let tag = tag[0] >> 3;
         let out: &[u8; 6] = out.try_into().expect("must be 6!");
 
@@ -69,12 +193,121 @@ impl FifoOut {
             Ok(SensorTag::AccelerometerNC) => {
                 Ok(Value::Accel(AccelValue::from_msr(accel_scale, out)))
             }
+            Ok(SensorTag::Temperature) => Ok(Value::Temperature(crate::temperature_from_msr(
+                out[0..2].try_into().unwrap(),
+            ))),
+            Ok(SensorTag::Timestamp) => Ok(Value::Timestamp(u32::from_le_bytes(
+                out[0..4].try_into().unwrap(),
+            ))),
+            Ok(SensorTag::ConfigChange) => Ok(Value::ConfigChange(ConfigChange::from_msr(out))),
             Ok(SensorTag::Other(u)) => Ok(Value::Other(u, *out)),
             _ => unreachable!(),
         }
     }
 }
 
+/// Duration of a timestamp LSB in seconds.
+const TIMESTAMP_LSB_S: f32 = 25e-6;
+
+impl Ism330Dhcx {
+    /// Read the words in the FIFO into `out`, returning how many were written.
+    ///
+    /// The FIFO status and the timestamp counter are read first. After an overrun, a
+    /// [`Value::Gap`] comes first with the number of words lost since the last read, estimated
+    /// from the elapsed time and the batch data rates, then `recovery` decides what happens to the
+    /// words left. Words that do not fit in `out` are left in the FIFO.
+    ///
+    /// An overrun seen by an earlier read of `FIFO_STATUS2` counts too, see
+    /// [`crate::fifostatus::FifoStatus::take_overrun`].
+    pub fn fifo_read<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        out: &mut [Value],
+        recovery: Recovery,
+    ) -> Result<usize, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        if out.is_empty() {
+            return Ok(0);
+        }
+
+        let (mut level, timestamp) = self.fifo_level_at(i2c)?;
+
+        let mut n = 0;
+        if let Some(mut lost) = self.take_lost(timestamp, level) {
+            if recovery == Recovery::Reset {
+                self.fifoctrl.reset_fifo(i2c)?;
+                self.fifoout.batched = None;
+                lost = lost.map(|lost| lost + u32::from(level));
+                level = 0;
+            }
+
+            out[0] = Value::Gap { lost };
+            n = 1;
+        }
+
+        let count = usize::from(level).min(out.len() - n);
+        for slot in &mut out[n..n + count] {
+            *slot = self.fifo_pop(i2c)?;
+        }
+
+        self.fifoout.last_read = Some((timestamp, level - count as u16));
+        Ok(n + count)
+    }
+
+    /// Read the FIFO level and the timestamp counter.
+    pub(crate) fn fifo_level_at<I2C>(&mut self, i2c: &mut I2C) -> Result<(u16, u32), I2C::Error>
+    where
+        I2C: I2c,
+    {
+        let level = self.fifostatus.status(i2c)?.level;
+        let timestamp = self.get_timestamp(i2c)?;
+        Ok((level, timestamp))
+    }
+
+    /// Words lost to an overrun since the last read, with `level` words in the FIFO at
+    /// `timestamp`: `None` without an overrun, `Some(None)` without an estimate.
+    pub(crate) fn take_lost(&mut self, timestamp: u32, level: u16) -> Option<Option<u32>> {
+        self.fifostatus
+            .take_overrun()
+            .then(|| self.estimate_lost(timestamp, level))
+    }
+
+    /// Words batched since the last read that did not make it into the FIFO.
+    fn estimate_lost(&self, timestamp: u32, level: u16) -> Option<u32> {
+        let (last, left) = self.fifoout.last_read?;
+        let elapsed = timestamp.wrapping_sub(last);
+        if elapsed == 0 {
+            return None;
+        }
+
+        let [_, _, bdr, ctrl4] = self.fifoctrl.value();
+        let accel_hz = config::bdr_hz(bdr & 0b1111);
+        let gyro_hz = config::bdr_hz(bdr >> 4);
+        let temperature_hz = match (ctrl4 >> 4) & 0b11 {
+            0b01 => 1.6,
+            0b10 => 12.5,
+            0b11 => 52.,
+            _ => 0.,
+        };
+        // A timestamp word every 1, 8 or 32 time slots, at the fastest batch data rate.
+        let slot_hz = accel_hz.max(gyro_hz).max(temperature_hz);
+        let timestamp_hz = match ctrl4 >> 6 {
+            0b01 => slot_hz,
+            0b10 => slot_hz / 8.,
+            0b11 => slot_hz / 32.,
+            _ => 0.,
+        };
+
+        let rate_hz = accel_hz + gyro_hz + temperature_hz + timestamp_hz;
+        // rounded to the nearest word
+        let batched = (elapsed as f32 * TIMESTAMP_LSB_S * rate_hz + 0.5) as u32;
+
+        Some((u32::from(left) + batched).saturating_sub(u32::from(level)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,5 +328,313 @@ mod tests {
 
         assert!(matches!(v, Value::Gyro(_)));
         println!("{:?}", v);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_pop_timestamp() {
+        let mut i2c = Mock::new(&[Transaction::write_read(
+            0x6b,
+            vec![0x78],
+            vec![0x04 << 3, 0x04, 0x03, 0x02, 0x01, 0, 0],
+        )]);
+
+        let mut f = FifoOut::new(crate::DEFAULT_I2C_ADDRESS);
+        let v = f
+            .pop(&mut i2c, ctrl2g::Fs::Dps250, ctrl1xl::Fs_Xl::G2)
+            .unwrap();
+
+        assert_eq!(v, Value::Timestamp(0x0102_0304));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn scale_changes() {
+        let word =
+            |x: u8| Transaction::write_read(0x6b, vec![0x78], vec![0x02 << 3, x, 0, 0, 0, 0, 0]);
+        let change = |ctrl1_xl: u8| {
+            Transaction::write_read(
+                0x6b,
+                vec![0x78],
+                vec![0x05 << 3, ctrl1_xl, 0x10, 0x11, 0, 0, 0],
+            )
+        };
+        let empty = Transaction::write_read(0x6b, vec![0x78], vec![0; 7]);
+        let mut i2c = Mock::new(&[
+            word(1),
+            change(0b0100_1100),
+            word(2),
+            change(0b0101_1000),
+            word(3),
+            empty.clone(),
+            word(4),
+        ]);
+
+        let mut f = FifoOut::new(crate::DEFAULT_I2C_ADDRESS);
+        f.track_scales(ctrl2g::Fs::Dps250, ctrl1xl::Fs_Xl::G2);
+        f.track_scales(ctrl2g::Fs::Dps250, ctrl1xl::Fs_Xl::G16);
+
+        let mut scales = Vec::new();
+        loop {
+            match f
+                .pop_tracked(&mut i2c, ctrl2g::Fs::Dps250, ctrl1xl::Fs_Xl::G16)
+                .unwrap()
+            {
+                Value::Accel(accel) => scales.push(accel.as_ug()[0]),
+                Value::ConfigChange(_) => {}
+                Value::Empty => break,
+                v => panic!("{:?}", v),
+            }
+        }
+        assert_eq!(
+            scales,
+            [
+                ctrl1xl::Fs_Xl::G2.sensitivity_ug(),
+                2 * ctrl1xl::Fs_Xl::G8.sensitivity_ug(),
+                3 * ctrl1xl::Fs_Xl::G4.sensitivity_ug(),
+            ]
+        );
+        assert_eq!(
+            f.config_change(),
+            Some(ConfigChange {
+                accel_odr: ctrl1xl::Odr_Xl::Hz208,
+                accel_scale: ctrl1xl::Fs_Xl::G4,
+                gyro_odr: ctrl2g::Odr::Hz125,
+                gyro_scale: ctrl2g::Fs::Dps250,
+                accel_bdr: BdrXl::Hz12_5,
+                gyro_bdr: BdrGy::Hz12_5,
+            })
+        );
+
+        // Past an empty FIFO, the current full scales apply.
+        let v = f
+            .pop_tracked(&mut i2c, ctrl2g::Fs::Dps250, ctrl1xl::Fs_Xl::G16)
+            .unwrap();
+        assert_eq!(
+            v,
+            Value::Accel(AccelValue::from_msr(
+                ctrl1xl::Fs_Xl::G16,
+                &[4, 0, 0, 0, 0, 0]
+            ))
+        );
+        i2c.done();
+    }
+
+    mod overrun {
+        use super::super::*;
+        use crate::fifoctrl::{BdrGy, BdrXl, FifoMode};
+        use crate::sim::Simulator;
+        use crate::{ctrl1xl::Odr_Xl, ctrl2g::Odr};
+
+        fn continuous(sim: &mut Simulator, timestamp: bool) -> Ism330Dhcx {
+            let mut sensor = Ism330Dhcx::new(sim).unwrap();
+            sensor
+                .ctrl1xl
+                .set_accelerometer_data_rate(sim, Odr_Xl::Hz104)
+                .unwrap();
+            sensor
+                .ctrl2g
+                .set_gyroscope_data_rate(sim, Odr::Hz104)
+                .unwrap();
+            sensor
+                .fifoctrl
+                .set_accelerometer_batch_data_rate(sim, BdrXl::Hz104)
+                .unwrap();
+            sensor
+                .fifoctrl
+                .set_gyroscope_batch_data_rate(sim, BdrGy::Hz104)
+                .unwrap();
+            sensor.fifoctrl.mode(sim, FifoMode::Continuous).unwrap();
+            sensor.enable_timestamp(sim, timestamp).unwrap();
+            sensor
+        }
+
+        fn lost(value: Value) -> Option<u32> {
+            match value {
+                Value::Gap { lost } => lost,
+                v => panic!("{:?}", v),
+            }
+        }
+
+        #[test]
+        fn continue_after_gap() {
+            let mut sim = Simulator::new();
+            let mut sensor = continuous(&mut sim, true);
+            let mut out = [Value::Empty; 600];
+
+            sim.advance_us(100_000);
+            assert_eq!(
+                sensor
+                    .fifo_read(&mut sim, &mut out[..8], Recovery::Continue)
+                    .unwrap(),
+                8
+            );
+            assert!(out[..8]
+                .iter()
+                .all(|v| matches!(v, Value::Accel(_) | Value::Gyro(_))));
+
+            // 12 words left, 1040 batched, 512 kept
+            sim.advance_us(5_000_000);
+            let n = sensor
+                .fifo_read(&mut sim, &mut out, Recovery::Continue)
+                .unwrap();
+            assert_eq!(n, 513);
+            let lost = lost(out[0]).unwrap();
+            assert!((538..=542).contains(&lost), "{}", lost);
+            assert!(out[1..n]
+                .iter()
+                .all(|v| matches!(v, Value::Accel(_) | Value::Gyro(_))));
+
+            sim.advance_us(100_000);
+            let n = sensor
+                .fifo_read(&mut sim, &mut out, Recovery::Continue)
+                .unwrap();
+            assert!(matches!(out[0], Value::Accel(_) | Value::Gyro(_)));
+            assert_eq!(n, 20);
+        }
+
+        #[test]
+        fn reset_after_gap() {
+            let mut sim = Simulator::new();
+            let mut sensor = continuous(&mut sim, true);
+            let mut out = [Value::Empty; 8];
+
+            assert_eq!(
+                sensor
+                    .fifo_read(&mut sim, &mut out, Recovery::Reset)
+                    .unwrap(),
+                0
+            );
+            sim.advance_us(5_000_000);
+            assert_eq!(
+                sensor
+                    .fifo_read(&mut sim, &mut out, Recovery::Reset)
+                    .unwrap(),
+                1
+            );
+            let lost = lost(out[0]).unwrap();
+            assert!((1038..=1042).contains(&lost), "{}", lost);
+            assert_eq!(sim.fifo_level(), 0);
+            assert_eq!(
+                sim.register(crate::fifoctrl::ADDR + 3) & 0b111,
+                FifoMode::Continuous as u8
+            );
+        }
+
+        #[test]
+        fn unknown_without_timestamps() {
+            let mut sim = Simulator::new();
+            let mut sensor = continuous(&mut sim, false);
+            let mut out = [Value::Empty; 8];
+
+            assert_eq!(
+                sensor
+                    .fifo_read(&mut sim, &mut out, Recovery::Continue)
+                    .unwrap(),
+                0
+            );
+            sim.advance_us(5_000_000);
+            assert_eq!(
+                sensor
+                    .fifo_read(&mut sim, &mut out, Recovery::Continue)
+                    .unwrap(),
+                8
+            );
+            assert_eq!(lost(out[0]), None);
+        }
+
+        #[test]
+        fn overrun_kept_across_status_reads() {
+            let mut sim = Simulator::new();
+            let mut sensor = continuous(&mut sim, true);
+            let mut out = [Value::Empty; 8];
+
+            assert_eq!(
+                sensor
+                    .fifo_read(&mut sim, &mut out, Recovery::Continue)
+                    .unwrap(),
+                0
+            );
+            sim.advance_us(5_000_000);
+
+            // Clears FIFO_OVR_LATCHED, then FIFO_OVR_IA with the first word.
+            assert!(sensor.fifostatus.status(&mut sim).unwrap().overrun_latched);
+            assert!(matches!(
+                sensor.fifo_pop(&mut sim).unwrap(),
+                Value::Accel(_) | Value::Gyro(_)
+            ));
+            assert!(!sensor.fifostatus.overrun(&mut sim).unwrap());
+
+            sensor
+                .fifo_read(&mut sim, &mut out, Recovery::Continue)
+                .unwrap();
+            assert!(matches!(out[0], Value::Gap { .. }));
+            sensor
+                .fifo_read(&mut sim, &mut out, Recovery::Continue)
+                .unwrap();
+            assert!(!matches!(out[0], Value::Gap { .. }));
+        }
+
+        #[test]
+        fn lost_counts_temperature_and_timestamps() {
+            let mut sim = Simulator::new();
+            let mut sensor = continuous(&mut sim, true);
+            // ODR_T_BATCH at 52 Hz, a timestamp every 8 time slots
+            embedded_hal::i2c::I2c::write(
+                &mut sim,
+                crate::DEFAULT_I2C_ADDRESS,
+                &[crate::fifoctrl::ADDR + 3, 0b1011_0110],
+            )
+            .unwrap();
+            sensor.sync(&mut sim).unwrap();
+            let mut out = [Value::Empty; 8];
+
+            assert_eq!(
+                sensor
+                    .fifo_read(&mut sim, &mut out, Recovery::Reset)
+                    .unwrap(),
+                0
+            );
+            sim.advance_us(5_000_000);
+            sensor
+                .fifo_read(&mut sim, &mut out, Recovery::Reset)
+                .unwrap();
+
+            // 1040 samples, 260 temperatures and 65 timestamps
+            let lost = lost(out[0]).unwrap();
+            assert!((1360..=1370).contains(&lost), "{}", lost);
+        }
+
+        #[test]
+        fn overwritten_scale_changes() {
+            let mut sim = Simulator::new();
+            let mut sensor = continuous(&mut sim, true);
+            let mut out = [Value::Empty; 520];
+
+            assert_eq!(
+                sensor
+                    .fifo_read(&mut sim, &mut out, Recovery::Continue)
+                    .unwrap(),
+                0
+            );
+            sim.advance_us(50_000);
+            sensor
+                .set_accel_full_scale(&mut sim, ctrl1xl::Fs_Xl::G16)
+                .unwrap();
+            sim.advance_us(5_000_000);
+
+            // The words batched with the old full scale were all overwritten.
+            let n = sensor
+                .fifo_read(&mut sim, &mut out, Recovery::Continue)
+                .unwrap();
+            assert_eq!(n, 513);
+            for value in &out[1..n] {
+                if let Value::Accel(accel) = value {
+                    assert!((accel.as_g()[2] - 1.).abs() < 0.01, "{:?}", accel);
+                }
+            }
+        }
     }
 }
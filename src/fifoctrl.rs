@@ -52,6 +52,8 @@ impl Register for FifoCtrl {}
 ///
 /// Default value is `Bypass` (off).
 #[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FifoMode {
     Bypass = 0b000,
     FifoMode = 0b001,
@@ -63,6 +65,8 @@ pub enum FifoMode {
 
 /// Batch data rate of gyroscope.
 #[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BdrGy {
     Off = 0b0000,
     Hz6_5 = 0b1011,
@@ -80,6 +84,8 @@ pub enum BdrGy {
 
 /// Batch data rate of accelerometer.
 #[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BdrXl {
     Off = 0b0000,
     Hz6_5 = 0b1011,
@@ -95,11 +101,30 @@ pub enum BdrXl {
     Hz6667 = 0b1010,
 }
 
+/// Decimation of the timestamp batched in the FIFO, in time slots.
+///
+/// Timestamps are only batched with `TIMESTAMP_EN` set in `CTRL10_C`, see
+/// [`crate::Ism330Dhcx::enable_timestamp`].
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecTs {
+    Off = 0b00,
+    Every1 = 0b01,
+    Every8 = 0b10,
+    Every32 = 0b11,
+}
+
 impl FifoCtrl {
     pub fn new(value: [u8; 4], address: u8) -> Self {
         FifoCtrl { address, value }
     }
 
+    /// Cached value of the register.
+    pub fn value(&self) -> [u8; 4] {
+        self.value
+    }
+
     /// Enable compression of values in FIFO, increasing FIFO size from 3kB to maximum 9kB.
     pub fn compression<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
     where
@@ -110,6 +135,39 @@ impl FifoCtrl {
         self.write(i2c, self.address, ADDR + 1, self.value[1])
     }
 
+    /// Batch a `CFG-Change` word in the FIFO when an ODR, BDR or full scale changes.
+    pub fn odr_chg_en(&self) -> bool {
+        self.value[1] & (1 << 4) != 0
+    }
+
+    /// Enable batching a `CFG-Change` word in the FIFO when an ODR, BDR or full scale changes, see
+    /// [`crate::fifo::Value::ConfigChange`].
+    pub fn set_odr_chg_en<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value[1] &= !(1 << 4);
+        self.value[1] |= (value as u8) << 4;
+        self.write(i2c, self.address, ADDR + 1, self.value[1])
+    }
+
+    /// FIFO watermark threshold, in words.
+    pub fn watermark(&self) -> u16 {
+        u16::from(self.value[0]) | u16::from(self.value[1] & 1) << 8
+    }
+
+    /// Set the FIFO watermark threshold, in words, writing `FIFO_CTRL1` and `FIFO_CTRL2` in one
+    /// burst. Only the 9 low bits are used, up to [`crate::config::WATERMARK_MAX`].
+    pub fn set_watermark<I2C>(&mut self, i2c: &mut I2C, value: u16) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value[0] = value as u8;
+        self.value[1] &= !1;
+        self.value[1] |= (value >> 8) as u8 & 1;
+        i2c.write(self.address, &[ADDR, self.value[0], self.value[1]])
+    }
+
     /// Set the FIFO mode (or disable FIFO)
     pub fn mode<I2C>(&mut self, i2c: &mut I2C, mode: FifoMode) -> Result<(), I2C::Error>
     where
@@ -122,6 +180,30 @@ impl FifoCtrl {
         self.write(i2c, self.address, ADDR + 3, self.value[3])
     }
 
+    /// Empty the FIFO by switching to `Bypass` and back to the current mode.
+    pub fn reset_fifo<I2C>(&mut self, i2c: &mut I2C) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.write(i2c, self.address, ADDR + 3, self.value[3] & !0b111)?;
+        self.write(i2c, self.address, ADDR + 3, self.value[3])
+    }
+
+    /// Set how often the timestamp is batched in the FIFO.
+    pub fn set_timestamp_decimation<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        decimation: DecTs,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        const RESET: u8 = 0b11000000;
+        self.value[3] &= !RESET;
+        self.value[3] |= (decimation as u8) << 6;
+        self.write(i2c, self.address, ADDR + 3, self.value[3])
+    }
+
     /// Set the batch data rate for the accelerometer.
     pub fn set_accelerometer_batch_data_rate<I2C>(
         &mut self,
@@ -166,6 +248,8 @@ mod tests {
 
         f.compression(&mut i2c, true).unwrap();
         assert_eq!(f.value[1], 0b1000000);
+
+        i2c.done();
     }
 
     #[test]
@@ -196,6 +280,8 @@ mod tests {
 
         f.mode(&mut i2c, FifoMode::FifoMode).unwrap();
         assert_eq!(f.value[3], 0b0000001);
+
+        i2c.done();
     }
 
     #[test]
@@ -221,5 +307,21 @@ mod tests {
         f.set_accelerometer_batch_data_rate(&mut i2c, BdrXl::Hz208)
             .unwrap();
         assert_eq!(f.value[2], 0b00100101);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn set_watermark() {
+        let mut i2c = Mock::new(&[Transaction::write(0x6b, vec![0x07, 0x2c, 0b1000001])]);
+        let mut f = FifoCtrl {
+            address: 0x6b,
+            value: [0, 0b1000000, 0, 0],
+        };
+
+        f.set_watermark(&mut i2c, 300).unwrap();
+        assert_eq!(f.watermark(), 300);
+
+        i2c.done();
     }
 }
@@ -5,15 +5,89 @@ use crate::Register;
 /// The FIFO_STATUS registers.
 pub struct FifoStatus {
     pub address: u8,
+    /// An overrun was seen since the last [`FifoStatus::take_overrun`].
+    overrun_seen: bool,
 }
 
 pub const ADDR: u8 = 0x3a_u8;
 
+/// FIFO level and flags, from one read of FIFO_STATUS1 and FIFO_STATUS2.
+///
+/// The latched overrun and BDR counter flags are cleared by the read.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FifoStatusSnapshot {
+    /// Number of unread sensor data in FIFO, `DIFF_FIFO_[9:0]`.
+    pub level: u16,
+    /// FIFO watermark reached, `FIFO_WTM_IA`.
+    pub watermark_reached: bool,
+    /// FIFO overrun, `FIFO_OVR_IA`.
+    pub overrun: bool,
+    /// FIFO full, `FIFO_FULL_IA`.
+    pub full: bool,
+    /// Counter BDR reached, `COUNTER_BDR_IA`.
+    pub count_bdr_reached: bool,
+    /// Latched FIFO overrun, `FIFO_OVR_LATCHED`.
+    pub overrun_latched: bool,
+}
+
+impl FifoStatusSnapshot {
+    fn from_registers(v: [u8; 2]) -> Self {
+        FifoStatusSnapshot {
+            level: u16::from_le_bytes([v[0], v[1] & 0b11]),
+            watermark_reached: v[1] & (1 << 7) != 0,
+            overrun: v[1] & (1 << 6) != 0,
+            full: v[1] & (1 << 5) != 0,
+            count_bdr_reached: v[1] & (1 << 4) != 0,
+            overrun_latched: v[1] & (1 << 3) != 0,
+        }
+    }
+}
+
 impl Register for FifoStatus {}
 
 impl FifoStatus {
     pub fn new(address: u8) -> Self {
-        FifoStatus { address }
+        FifoStatus {
+            address,
+            overrun_seen: false,
+        }
+    }
+
+    /// Whether an overrun was seen by any read of `FIFO_STATUS2` since the last call.
+    ///
+    /// Reading `FIFO_STATUS2` clears `FIFO_OVR_LATCHED`, so the driver keeps it until taken.
+    pub fn take_overrun(&mut self) -> bool {
+        core::mem::take(&mut self.overrun_seen)
+    }
+
+    /// Read `FIFO_STATUS2`, keeping any overrun for [`FifoStatus::take_overrun`].
+    fn status2<I2C>(&mut self, i2c: &mut I2C) -> Result<u8, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        let v = self.read(i2c, self.address, ADDR + 1)?;
+        self.keep_overrun(v);
+
+        Ok(v)
+    }
+
+    /// Keep an overrun flagged in a `FIFO_STATUS2` value read by other means.
+    pub(crate) fn keep_overrun(&mut self, status2: u8) {
+        self.overrun_seen |= status2 & (1 << 6 | 1 << 3) != 0;
+    }
+
+    /// FIFO level and all the flags in one 2-byte read, for interrupt handlers.
+    pub fn status<I2C>(&mut self, i2c: &mut I2C) -> Result<FifoStatusSnapshot, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        let mut v = [0u8; 2];
+        i2c.write_read(self.address, &[ADDR], &mut v)?;
+
+        let status = FifoStatusSnapshot::from_registers(v);
+        self.overrun_seen |= status.overrun || status.overrun_latched;
+        Ok(status)
     }
 
     /// Is the FIFO full
@@ -21,7 +95,7 @@ impl FifoStatus {
     where
         I2C: embedded_hal::i2c::I2c,
     {
-        let v = self.read(i2c, self.address, ADDR + 1)?;
+        let v = self.status2(i2c)?;
 
         Ok(v & (1 << 5) != 0)
     }
@@ -31,7 +105,7 @@ impl FifoStatus {
     where
         I2C: embedded_hal::i2c::I2c,
     {
-        let v = self.read(i2c, self.address, ADDR + 1)?;
+        let v = self.status2(i2c)?;
 
         Ok(v & (1 << 6) != 0)
     }
@@ -41,7 +115,7 @@ impl FifoStatus {
     where
         I2C: embedded_hal::i2c::I2c,
     {
-        let v = self.read(i2c, self.address, ADDR + 1)?;
+        let v = self.status2(i2c)?;
 
         Ok(v & (1 << 7) != 0)
     }
@@ -51,7 +125,7 @@ impl FifoStatus {
     where
         I2C: embedded_hal::i2c::I2c,
     {
-        let v = self.read(i2c, self.address, ADDR + 1)?;
+        let v = self.status2(i2c)?;
 
         Ok(v & (1 << 3) != 0)
     }
@@ -61,7 +135,7 @@ impl FifoStatus {
     where
         I2C: embedded_hal::i2c::I2c,
     {
-        let v = self.read(i2c, self.address, ADDR + 1)?;
+        let v = self.status2(i2c)?;
 
         Ok(v & (1 << 4) != 0)
     }
@@ -71,11 +145,7 @@ impl FifoStatus {
     where
         I2C: I2c,
     {
-        let mut v = [0u8; 2];
-        i2c.write_read(self.address, &[ADDR], &mut v)?;
-        v[1] &= 0b11;
-
-        Ok(u16::from_le_bytes(v))
+        Ok(self.status(i2c)?.level)
     }
 }
 
@@ -90,6 +160,8 @@ mod tests {
 
         let mut f = FifoStatus::new(crate::DEFAULT_I2C_ADDRESS);
         assert!(f.full(&mut i2c).unwrap());
+
+        i2c.done();
     }
 
     #[test]
@@ -104,6 +176,8 @@ mod tests {
         let diff = f.diff_fifo(&mut i2c).unwrap();
 
         assert_eq!(diff, 0b000100000);
+
+        i2c.done();
     }
 
     #[test]
@@ -118,5 +192,33 @@ mod tests {
         let diff = f.diff_fifo(&mut i2c).unwrap();
 
         assert_eq!(diff, 0b100100000);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_status() {
+        let mut i2c = Mock::new(&[Transaction::write_read(
+            0x6b,
+            vec![0x3a],
+            vec![0x12, 0b1010_1011],
+        )]);
+
+        let mut f = FifoStatus::new(crate::DEFAULT_I2C_ADDRESS);
+        let status = f.status(&mut i2c).unwrap();
+
+        assert_eq!(
+            status,
+            FifoStatusSnapshot {
+                level: 0x312,
+                watermark_reached: true,
+                overrun: false,
+                full: true,
+                count_bdr_reached: false,
+                overrun_latched: true,
+            }
+        );
+
+        i2c.done();
     }
 }
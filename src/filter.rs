@@ -0,0 +1,526 @@
+//! Accelerometer and gyroscope filtering chains.
+//!
+//! The accelerometer output goes through LPF1 (ODR/2), then either stays on the low-pass path,
+//! optionally through LPF2, or takes the high-pass path through the slope or high-pass filter.
+//! Cutoffs are fractions of the accelerometer ODR, selected with `HPCF_XL` in `CTRL8_XL`.
+//!
+//! ```no_run
+//! # fn run<I2C: embedded_hal::i2c::I2c>(i2c: &mut I2C) -> Result<(), ism330dhcx::Error<I2C::Error>> {
+//! use ism330dhcx::ctrl8xl::Hpcf_Xl;
+//! use ism330dhcx::filter::AccelFilter;
+//! use ism330dhcx::Ism330Dhcx;
+//!
+//! let mut sensor = Ism330Dhcx::new(i2c)?;
+//! let filter = AccelFilter::lpf2(Hpcf_Xl::Odr45).fast_settling(true);
+//! sensor.set_accel_filter(i2c, filter)?;
+//! let bandwidth = sensor.accel_bandwidth_hz();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The gyroscope output goes through an optional high-pass filter (`HP_EN_G`, `HPM_G` in
+//! `CTRL7_G`), then an optional LPF1 (`LPF1_SEL_G` in `CTRL4_C`, `FTYPE` in `CTRL6_C`) and a last
+//! low-pass stage set by the ODR. [`GyroFilter::bandwidth_hz`] gives the overall cutoff from the
+//! datasheet bandwidth table, and [`GyroFilter::delay_us`] the low-frequency delay it causes.
+//!
+//! ```no_run
+//! # fn run<I2C: embedded_hal::i2c::I2c>(i2c: &mut I2C) -> Result<(), ism330dhcx::Error<I2C::Error>> {
+//! use ism330dhcx::ctrl6c::Ftype;
+//! use ism330dhcx::ctrl7g::Hpm_g;
+//! use ism330dhcx::filter::GyroFilter;
+//! use ism330dhcx::Ism330Dhcx;
+//!
+//! let mut sensor = Ism330Dhcx::new(i2c)?;
+//! let filter = GyroFilter::new().lpf1(Ftype::Ftype101).high_pass(Hpm_g::Hpmg65);
+//! sensor.set_gyro_filter(i2c, filter)?;
+//! let delay = sensor.gyro_delay_us();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::config::ConfigError;
+use crate::ctrl1xl::Ctrl1Xl;
+use crate::ctrl4c::Ctrl4C;
+use crate::ctrl6c::{Ctrl6C, Ftype};
+use crate::ctrl7g::{Ctrl7G, Hpm_g};
+use crate::ctrl8xl::{Ctrl8Xl, Hpcf_Xl};
+use crate::power::PowerMode;
+use crate::{Error, Ism330Dhcx};
+
+/// Where the accelerometer output is taken in the filtering chain.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AccelPath {
+    /// LPF1 only, ODR/2.
+    Lpf1,
+    /// LPF1 then LPF2, with the cutoff as a fraction of the ODR.
+    Lpf2(Hpcf_Xl),
+    /// High-pass filter, with the cutoff as a fraction of the ODR. `Hpcf_Xl::Odr4` is the slope
+    /// filter.
+    HighPass(Hpcf_Xl),
+}
+
+/// Accelerometer filter settings, see [`Ism330Dhcx::set_accel_filter`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccelFilter {
+    pub path: AccelPath,
+    /// High-pass filter reference mode, the output is the difference with the first sample after
+    /// enabling it. High-pass path only.
+    pub reference: bool,
+    /// Settle the filter on the second sample after a change instead of its time constant.
+    pub fast_settling: bool,
+}
+
+impl Default for AccelFilter {
+    fn default() -> Self {
+        AccelFilter::lpf1()
+    }
+}
+
+impl AccelFilter {
+    /// LPF1 only, the power-on default.
+    pub fn lpf1() -> Self {
+        AccelFilter {
+            path: AccelPath::Lpf1,
+            reference: false,
+            fast_settling: false,
+        }
+    }
+
+    /// Low-pass path through LPF2 with a cutoff of ODR/`cutoff.divisor()`.
+    pub fn lpf2(cutoff: Hpcf_Xl) -> Self {
+        AccelFilter {
+            path: AccelPath::Lpf2(cutoff),
+            ..Self::lpf1()
+        }
+    }
+
+    /// High-pass path with a cutoff of ODR/`cutoff.divisor()`.
+    pub fn high_pass(cutoff: Hpcf_Xl) -> Self {
+        AccelFilter {
+            path: AccelPath::HighPass(cutoff),
+            ..Self::lpf1()
+        }
+    }
+
+    /// High-pass path through the slope filter, the difference of consecutive samples.
+    pub fn slope() -> Self {
+        Self::high_pass(Hpcf_Xl::Odr4)
+    }
+
+    pub fn reference(self, reference: bool) -> Self {
+        AccelFilter { reference, ..self }
+    }
+
+    pub fn fast_settling(self, fast_settling: bool) -> Self {
+        AccelFilter {
+            fast_settling,
+            ..self
+        }
+    }
+
+    /// Check that the settings can work together.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.reference && !matches!(self.path, AccelPath::HighPass(_)) {
+            return Err(ConfigError::ReferenceWithoutHighPass);
+        }
+
+        Ok(())
+    }
+
+    /// `LPF2_XL_EN`, `HP_SLOPE_XL_EN` and `HPCF_XL` for the path.
+    pub(crate) fn path_bits(&self) -> (bool, bool, Hpcf_Xl) {
+        match self.path {
+            AccelPath::Lpf1 => (false, false, Hpcf_Xl::Odr4),
+            AccelPath::Lpf2(cutoff) => (true, false, cutoff),
+            AccelPath::HighPass(cutoff) => (false, true, cutoff),
+        }
+    }
+
+    /// The settings held by `CTRL1_XL` and `CTRL8_XL`.
+    pub(crate) fn from_registers(ctrl1xl: &Ctrl1Xl, ctrl8xl: &Ctrl8Xl) -> Self {
+        let cutoff = ctrl8xl.hpcf_xl();
+        let path = if ctrl8xl.hp_slope_xl_en() {
+            AccelPath::HighPass(cutoff)
+        } else if ctrl1xl.lpf2_xl_en() {
+            AccelPath::Lpf2(cutoff)
+        } else {
+            AccelPath::Lpf1
+        };
+
+        AccelFilter {
+            path,
+            reference: ctrl8xl.hp_ref_mode_xl(),
+            fast_settling: ctrl8xl.fastsettl_mode_xl(),
+        }
+    }
+
+    /// Cutoff frequency at `odr_hz`: the upper one on the low-pass path, the lower one on the
+    /// high-pass path.
+    pub fn bandwidth_hz(&self, odr_hz: f32) -> f32 {
+        match self.path {
+            AccelPath::Lpf1 => odr_hz / 2.,
+            AccelPath::Lpf2(cutoff) | AccelPath::HighPass(cutoff) => {
+                odr_hz / f32::from(cutoff.divisor())
+            }
+        }
+    }
+}
+
+impl Ism330Dhcx {
+    /// Configure the accelerometer filtering chain, with one write to `CTRL8_XL` then one to
+    /// `CTRL1_XL`.
+    ///
+    /// Nothing is written if the settings are invalid.
+    pub fn set_accel_filter<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        filter: AccelFilter,
+    ) -> Result<(), Error<I2C::Error>>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        filter.validate().map_err(Error::InvalidConfig)?;
+
+        let (lpf2, high_pass, cutoff) = filter.path_bits();
+
+        self.ctrl8xl.set_filtering(
+            i2c,
+            cutoff,
+            high_pass,
+            filter.reference,
+            filter.fast_settling,
+        )?;
+        self.ctrl1xl.set_lpf2_xl_en(i2c, lpf2)?;
+        Ok(())
+    }
+
+    /// The accelerometer filtering chain, from the cached configuration.
+    pub fn accel_filter(&self) -> AccelFilter {
+        AccelFilter::from_registers(&self.ctrl1xl, &self.ctrl8xl)
+    }
+
+    /// Cutoff frequency of the accelerometer filtering chain at the current data rate, see
+    /// [`AccelFilter::bandwidth_hz`].
+    pub fn accel_bandwidth_hz(&self) -> f32 {
+        self.accel_filter().bandwidth_hz(self.accel_data_rate_hz())
+    }
+}
+
+/// Gyroscope data rates, the columns of [`GYRO_BANDWIDTH`].
+const GYRO_ODRS: [f32; 10] = [12.5, 26., 52., 104., 208., 416., 833., 1667., 3333., 6667.];
+
+/// Gyroscope bandwidth in Hz with LPF1 enabled, by `FTYPE` then data rate.
+const GYRO_BANDWIDTH: [[f32; 10]; 8] = [
+    [4.3, 8.3, 16.7, 33., 67., 133., 222., 274., 292., 297.],
+    [4.3, 8.3, 16.7, 33., 67., 128., 186., 212., 220., 223.],
+    [4.3, 8.3, 16.7, 33., 67., 112., 140., 150., 153., 154.],
+    [4.3, 8.3, 16.7, 33., 67., 134., 260., 390., 451., 470.],
+    [4.3, 8.3, 16.7, 34., 62., 86., 96., 99., 100., 100.],
+    [4.3, 8.3, 16.8, 31., 43., 48., 49., 50., 50., 50.],
+    [4.3, 8.3, 15.7, 25., 28., 29., 30., 30., 30., 30.],
+    [4.3, 8.3, 13.1, 18., 19., 20., 20., 20., 20., 20.],
+];
+
+/// Gyroscope filter settings, see [`Ism330Dhcx::set_gyro_filter`].
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GyroFilter {
+    /// LPF1 bandwidth, `None` when disabled.
+    pub lpf1: Option<Ftype>,
+    /// High-pass filter cutoff, `None` when disabled.
+    pub high_pass: Option<Hpm_g>,
+}
+
+impl GyroFilter {
+    /// No LPF1 and no high-pass filter, the power-on default.
+    pub fn new() -> Self {
+        GyroFilter::default()
+    }
+
+    pub fn lpf1(self, ftype: Ftype) -> Self {
+        GyroFilter {
+            lpf1: Some(ftype),
+            ..self
+        }
+    }
+
+    pub fn high_pass(self, cutoff: Hpm_g) -> Self {
+        GyroFilter {
+            high_pass: Some(cutoff),
+            ..self
+        }
+    }
+
+    /// The settings held by `CTRL4_C`, `CTRL6_C` and `CTRL7_G`.
+    pub(crate) fn from_registers(ctrl4c: &Ctrl4C, ctrl6c: &Ctrl6C, ctrl7g: &Ctrl7G) -> Self {
+        GyroFilter {
+            lpf1: ctrl4c.lpf1_sel_g().then(|| ctrl6c.ftype()),
+            high_pass: ctrl7g.hp_en_g().then(|| ctrl7g.high_pass_cutoff()),
+        }
+    }
+
+    /// Low-pass cutoff at `odr_hz`, 0 when the gyroscope is off.
+    ///
+    /// Without LPF1, the last stage alone is taken as the widest LPF1 setting of the table,
+    /// `Ftype011`.
+    pub fn bandwidth_hz(&self, odr_hz: f32) -> f32 {
+        if odr_hz == 0. {
+            return 0.;
+        }
+
+        let column = GYRO_ODRS
+            .iter()
+            .position(|&odr| odr_hz < odr * 1.1)
+            .unwrap_or(GYRO_ODRS.len() - 1);
+        let row = self.lpf1.unwrap_or(Ftype::Ftype011) as usize;
+        GYRO_BANDWIDTH[row][column]
+    }
+
+    /// High-pass cutoff in Hz, 0 when disabled.
+    pub fn high_pass_hz(&self) -> f32 {
+        self.high_pass.map_or(0., |cutoff| cutoff.hz())
+    }
+
+    /// Delay of slow signals through the low-pass chain at `odr_hz`, in µs.
+    ///
+    /// Approximates the chain as a single pole at [`GyroFilter::bandwidth_hz`], whose group delay
+    /// is `1 / (2π fc)` well below the cutoff.
+    pub fn delay_us(&self, odr_hz: f32) -> u32 {
+        let fc = self.bandwidth_hz(odr_hz);
+        if fc == 0. {
+            return 0;
+        }
+        (1e6 / (2. * core::f32::consts::PI * fc)) as u32
+    }
+}
+
+impl Ism330Dhcx {
+    /// Configure the gyroscope LPF1 and high-pass filter together, writing each of `CTRL6_C`,
+    /// `CTRL4_C` and `CTRL7_G` once. `CTRL6_C` is skipped without LPF1.
+    ///
+    /// The high-pass filter only works in high-performance mode, nothing is written if it is
+    /// requested in another mode.
+    pub fn set_gyro_filter<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        filter: GyroFilter,
+    ) -> Result<(), Error<I2C::Error>>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        if filter.high_pass.is_some()
+            && matches!(
+                self.gyro_power_mode(),
+                Some(PowerMode::LowPower | PowerMode::Normal)
+            )
+        {
+            return Err(Error::InvalidConfig(
+                ConfigError::HighPassWithoutHighPerformance,
+            ));
+        }
+
+        if let Some(ftype) = filter.lpf1 {
+            self.ctrl6c.set_ftype(i2c, ftype)?;
+        }
+        self.ctrl4c.set_lpf1_sel_g(i2c, filter.lpf1.is_some())?;
+
+        self.ctrl7g.set_high_pass(i2c, filter.high_pass)?;
+        Ok(())
+    }
+
+    /// The gyroscope filters, from the cached configuration.
+    pub fn gyro_filter(&self) -> GyroFilter {
+        GyroFilter::from_registers(&self.ctrl4c, &self.ctrl6c, &self.ctrl7g)
+    }
+
+    /// Gyroscope low-pass cutoff at the current data rate, see [`GyroFilter::bandwidth_hz`].
+    pub fn gyro_bandwidth_hz(&self) -> f32 {
+        self.gyro_filter()
+            .bandwidth_hz(self.ctrl2g.gyroscope_data_rate())
+    }
+
+    /// Gyroscope low-pass delay at the current data rate, see [`GyroFilter::delay_us`].
+    pub fn gyro_delay_us(&self) -> u32 {
+        self.gyro_filter()
+            .delay_us(self.ctrl2g.gyroscope_data_rate())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctrl1xl::{self, Odr_Xl};
+    use crate::ctrl8xl;
+    use crate::sim::Simulator;
+    use approx::*;
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    #[test]
+    fn bandwidth() {
+        assert_abs_diff_eq!(AccelFilter::lpf1().bandwidth_hz(104.), 52.);
+        assert_abs_diff_eq!(
+            AccelFilter::lpf2(Hpcf_Xl::Odr45).bandwidth_hz(833.),
+            833. / 45.
+        );
+        assert_abs_diff_eq!(AccelFilter::slope().bandwidth_hz(416.), 104.);
+        assert_abs_diff_eq!(
+            AccelFilter::high_pass(Hpcf_Xl::Odr800).bandwidth_hz(1667.),
+            1667. / 800.
+        );
+    }
+
+    #[test]
+    fn reference_needs_high_pass() {
+        let mut sim = Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+
+        let filter = AccelFilter::lpf2(Hpcf_Xl::Odr10).reference(true);
+        assert!(matches!(
+            sensor.set_accel_filter(&mut sim, filter),
+            Err(Error::InvalidConfig(ConfigError::ReferenceWithoutHighPass))
+        ));
+        assert_eq!(sim.register(ctrl8xl::ADDR), 0);
+
+        let filter = AccelFilter::high_pass(Hpcf_Xl::Odr100).reference(true);
+        sensor.set_accel_filter(&mut sim, filter).unwrap();
+        assert_eq!(sensor.accel_filter(), filter);
+    }
+
+    #[test]
+    fn set_accel_filter() {
+        let mut sim = Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, Odr_Xl::Hz208)
+            .unwrap();
+        assert_eq!(sensor.accel_filter(), AccelFilter::default());
+        assert_abs_diff_eq!(sensor.accel_bandwidth_hz(), 104.);
+
+        let filter = AccelFilter::lpf2(Hpcf_Xl::Odr20).fast_settling(true);
+        sensor.set_accel_filter(&mut sim, filter).unwrap();
+        assert_eq!(
+            sim.register(ctrl8xl::ADDR),
+            0b010 << 5 | 1 << ctrl8xl::FASTSETTL_MODE_XL
+        );
+        assert_eq!(sim.register(ctrl1xl::ADDR), 0b0101_0010);
+        assert_eq!(sensor.accel_filter(), filter);
+        assert_abs_diff_eq!(sensor.accel_bandwidth_hz(), 10.4);
+
+        sensor
+            .set_accel_filter(&mut sim, AccelFilter::slope())
+            .unwrap();
+        assert_eq!(sim.register(ctrl8xl::ADDR), 1 << ctrl8xl::HP_SLOPE_XL_EN);
+        assert_eq!(sim.register(ctrl1xl::ADDR), 0b0101_0000);
+
+        // The cache survives a round trip through the device.
+        sensor.read_config(&mut sim).unwrap();
+        assert_eq!(sensor.accel_filter(), AccelFilter::slope());
+    }
+
+    #[test]
+    fn set_accel_filter_writes_once() {
+        let filter = AccelFilter::high_pass(Hpcf_Xl::Odr100).reference(true);
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x6b, vec![0x07], vec![0; crate::CONTROL_BLOCK]),
+            Transaction::write(
+                0x6b,
+                vec![
+                    ctrl8xl::ADDR,
+                    0b100 << 5 | 1 << ctrl8xl::HP_REF_MODE_XL | 1 << ctrl8xl::HP_SLOPE_XL_EN,
+                ],
+            ),
+            Transaction::write(0x6b, vec![ctrl1xl::ADDR, 0]),
+        ]);
+
+        let mut sensor = Ism330Dhcx::new(&mut i2c).unwrap();
+        sensor.set_accel_filter(&mut i2c, filter).unwrap();
+        assert_eq!(sensor.accel_filter(), filter);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn set_gyro_filter_writes_once() {
+        use crate::{ctrl4c, ctrl6c, ctrl7g};
+
+        let filter = GyroFilter::new()
+            .lpf1(Ftype::Ftype100)
+            .high_pass(Hpm_g::Hpmg104);
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x6b, vec![0x07], vec![0; crate::CONTROL_BLOCK]),
+            Transaction::write(0x6b, vec![ctrl6c::ADDR, 0b100]),
+            Transaction::write(0x6b, vec![ctrl4c::ADDR, 1 << ctrl4c::LPF1_SEL_G]),
+            Transaction::write(0x6b, vec![ctrl7g::ADDR, 1 << ctrl7g::HP_EN_G | 0b11 << 4]),
+            Transaction::write(0x6b, vec![ctrl4c::ADDR, 0]),
+            Transaction::write(0x6b, vec![ctrl7g::ADDR, 0b11 << 4]),
+        ]);
+
+        let mut sensor = Ism330Dhcx::new(&mut i2c).unwrap();
+        sensor.set_gyro_filter(&mut i2c, filter).unwrap();
+        assert_eq!(sensor.gyro_filter(), filter);
+        sensor.set_gyro_filter(&mut i2c, GyroFilter::new()).unwrap();
+        assert_eq!(sensor.gyro_filter(), GyroFilter::new());
+
+        i2c.done();
+    }
+
+    #[test]
+    fn gyro_bandwidth() {
+        let filter = GyroFilter::new();
+        assert_eq!(filter.bandwidth_hz(0.), 0.);
+        assert_eq!(filter.bandwidth_hz(52.2), 16.7);
+        assert_eq!(filter.bandwidth_hz(6667.), 470.);
+        assert_eq!(filter.bandwidth_hz(416.), 134.);
+
+        let filter = filter.lpf1(Ftype::Ftype111);
+        assert_eq!(filter.bandwidth_hz(12.5), 4.3);
+        assert_eq!(filter.bandwidth_hz(833.), 20.);
+        assert_eq!(filter.delay_us(833.), 7957);
+        assert_eq!(filter.high_pass_hz(), 0.);
+        assert_eq!(filter.high_pass(Hpm_g::Hpmg104).high_pass_hz(), 1.04);
+    }
+
+    #[test]
+    fn set_gyro_filter() {
+        use crate::ctrl2g::Odr;
+        use crate::{ctrl4c, ctrl6c, ctrl7g};
+
+        let mut sim = Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .set_gyro_power_mode(&mut sim, Odr::Hz52, PowerMode::LowPower)
+            .unwrap();
+
+        let filter = GyroFilter::new()
+            .lpf1(Ftype::Ftype110)
+            .high_pass(Hpm_g::Hpmg260);
+        assert!(matches!(
+            sensor.set_gyro_filter(&mut sim, filter),
+            Err(Error::InvalidConfig(
+                ConfigError::HighPassWithoutHighPerformance
+            ))
+        ));
+        assert_eq!(sim.register(ctrl6c::ADDR), 0);
+
+        sensor
+            .set_gyro_power_mode(&mut sim, Odr::Hz833, PowerMode::HighPerformance)
+            .unwrap();
+        sensor.set_gyro_filter(&mut sim, filter).unwrap();
+        assert_eq!(sim.register(ctrl6c::ADDR), 0b110);
+        assert_eq!(sim.register(ctrl4c::ADDR), 1 << ctrl4c::LPF1_SEL_G);
+        assert_eq!(sim.register(ctrl7g::ADDR), 1 << ctrl7g::HP_EN_G | 0b10 << 4);
+        assert_eq!(sensor.gyro_filter(), filter);
+        assert_eq!(sensor.gyro_bandwidth_hz(), 30.);
+        assert_eq!(sensor.gyro_delay_us(), 5305);
+        assert_eq!(sensor.ctrl7g.hpm_g(), 0.26);
+
+        sensor.set_gyro_filter(&mut sim, GyroFilter::new()).unwrap();
+        assert_eq!(sim.register(ctrl4c::ADDR), 0);
+        assert_eq!(sim.register(ctrl7g::ADDR), 0b10 << 4);
+        sensor.read_config(&mut sim).unwrap();
+        assert_eq!(sensor.gyro_filter(), GyroFilter::new());
+    }
+}
@@ -0,0 +1,463 @@
+//! Attitude estimation from gyroscope and accelerometer samples.
+//!
+//! [`Madgwick`] and [`Mahony`] integrate the angular rate into a [`Quaternion`] and use the
+//! direction of gravity measured by the accelerometer to correct the roll and pitch drift. Without
+//! a magnetometer the yaw is the integrated gyroscope rate only.
+//!
+//! The time step of an update comes from the configured data rate, see [`odr_dt`], or from the
+//! FIFO timestamps, see [`TimestampDt`]. All the math is `f32`.
+//!
+//! ```no_run
+//! # fn run<I2C: embedded_hal::i2c::I2c>(i2c: &mut I2C) -> Result<(), ism330dhcx::Error<I2C::Error>> {
+//! use ism330dhcx::fusion::{odr_dt, Ahrs, Madgwick};
+//! use ism330dhcx::Ism330Dhcx;
+//!
+//! let mut sensor = Ism330Dhcx::new(i2c)?;
+//! let mut ahrs = Madgwick::new(0.1);
+//! let dt = odr_dt(sensor.ctrl2g.gyroscope_data_rate());
+//!
+//! loop {
+//!     let gyro = sensor.get_gyroscope(i2c)?;
+//!     let accel = sensor.get_accelerometer(i2c)?;
+//!     ahrs.update(&gyro, &accel, dt);
+//!     let angles = ahrs.quaternion().euler();
+//! }
+//! # }
+//! ```
+
+use crate::{AccelValue, GyroValue};
+
+/// Duration of a timestamp LSB in seconds.
+pub const TIMESTAMP_LSB_S: f32 = 25e-6;
+
+/// Time step between samples at a data rate in Hz, 0 when the sensor is off.
+pub fn odr_dt(odr_hz: f32) -> f32 {
+    if odr_hz > 0. {
+        1. / odr_hz
+    } else {
+        0.
+    }
+}
+
+/// Time steps from consecutive timestamps, as found in the `TIMESTAMP` registers or the FIFO.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct TimestampDt {
+    last: Option<u32>,
+}
+
+impl TimestampDt {
+    pub fn new() -> Self {
+        TimestampDt { last: None }
+    }
+
+    /// Seconds elapsed since the previous timestamp, `None` for the first one.
+    ///
+    /// The 32-bit counter wraps after about 30 hours, which is handled.
+    pub fn update(&mut self, timestamp: u32) -> Option<f32> {
+        let dt = self
+            .last
+            .map(|last| timestamp.wrapping_sub(last) as f32 * TIMESTAMP_LSB_S);
+        self.last = Some(timestamp);
+        dt
+    }
+}
+
+/// Orientation of the sensor frame relative to the earth frame.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Quaternion {
+    pub w: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+/// Roll, pitch and yaw in radians, applied in yaw, pitch, roll order.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct EulerAngles {
+    pub roll: f32,
+    pub pitch: f32,
+    pub yaw: f32,
+}
+
+impl Quaternion {
+    /// No rotation.
+    pub const IDENTITY: Quaternion = Quaternion {
+        w: 1.,
+        x: 0.,
+        y: 0.,
+        z: 0.,
+    };
+
+    pub fn norm(&self) -> f32 {
+        libm::sqrtf(self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z)
+    }
+
+    /// The quaternion scaled to unit length, or the identity for a zero quaternion.
+    pub fn normalized(&self) -> Quaternion {
+        let norm = self.norm();
+        if norm == 0. {
+            return Quaternion::IDENTITY;
+        }
+
+        Quaternion {
+            w: self.w / norm,
+            x: self.x / norm,
+            y: self.y / norm,
+            z: self.z / norm,
+        }
+    }
+
+    /// Hamilton product `self * rhs`.
+    pub fn mul(&self, rhs: &Quaternion) -> Quaternion {
+        Quaternion {
+            w: self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            x: self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            y: self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            z: self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+        }
+    }
+
+    pub fn from_euler(angles: EulerAngles) -> Quaternion {
+        let (sr, cr) = (libm::sinf(angles.roll / 2.), libm::cosf(angles.roll / 2.));
+        let (sp, cp) = (libm::sinf(angles.pitch / 2.), libm::cosf(angles.pitch / 2.));
+        let (sy, cy) = (libm::sinf(angles.yaw / 2.), libm::cosf(angles.yaw / 2.));
+
+        Quaternion {
+            w: cr * cp * cy + sr * sp * sy,
+            x: sr * cp * cy - cr * sp * sy,
+            y: cr * sp * cy + sr * cp * sy,
+            z: cr * cp * sy - sr * sp * cy,
+        }
+    }
+
+    pub fn euler(&self) -> EulerAngles {
+        let Quaternion { w, x, y, z } = *self;
+
+        EulerAngles {
+            roll: libm::atan2f(2. * (w * x + y * z), 1. - 2. * (x * x + y * y)),
+            pitch: libm::asinf((2. * (w * y - z * x)).clamp(-1., 1.)),
+            yaw: libm::atan2f(2. * (w * z + x * y), 1. - 2. * (y * y + z * z)),
+        }
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Quaternion::IDENTITY
+    }
+}
+
+/// Unit vector along `v`, `None` for a zero vector.
+fn unit(v: [f32; 3]) -> Option<[f32; 3]> {
+    let norm = libm::sqrtf(v[0] * v[0] + v[1] * v[1] + v[2] * v[2]);
+    if norm == 0. {
+        None
+    } else {
+        Some(v.map(|c| c / norm))
+    }
+}
+
+/// An attitude filter fed with gyroscope and accelerometer samples.
+pub trait Ahrs {
+    /// Integrate one sample pair.
+    ///
+    /// `gyro` is in rad/s, `accel` in any unit since only its direction is used, `dt` in seconds.
+    fn update_imu(&mut self, gyro: [f32; 3], accel: [f32; 3], dt: f32);
+
+    /// Current orientation.
+    fn quaternion(&self) -> Quaternion;
+
+    /// Integrate one sample pair read from the sensor.
+    fn update(&mut self, gyro: &GyroValue, accel: &AccelValue, dt: f32) {
+        self.update_imu(gyro.as_rad_f32(), accel.as_g_f32(), dt);
+    }
+
+    /// Current orientation as Euler angles.
+    fn euler(&self) -> EulerAngles {
+        self.quaternion().euler()
+    }
+}
+
+/// Madgwick gradient descent filter.
+#[derive(Copy, Clone, Debug)]
+pub struct Madgwick {
+    /// Gain of the accelerometer correction, in rad/s.
+    pub beta: f32,
+    q: Quaternion,
+}
+
+impl Madgwick {
+    /// A filter starting at the identity. A `beta` around 0.1 suits most sensors.
+    pub fn new(beta: f32) -> Self {
+        Madgwick {
+            beta,
+            q: Quaternion::IDENTITY,
+        }
+    }
+
+    /// Start from a known orientation.
+    pub fn with_quaternion(beta: f32, q: Quaternion) -> Self {
+        Madgwick {
+            beta,
+            q: q.normalized(),
+        }
+    }
+}
+
+impl Ahrs for Madgwick {
+    fn update_imu(&mut self, gyro: [f32; 3], accel: [f32; 3], dt: f32) {
+        let Quaternion {
+            w: q0,
+            x: q1,
+            y: q2,
+            z: q3,
+        } = self.q;
+        let [gx, gy, gz] = gyro;
+
+        // Rate of change from the gyroscope.
+        let mut dq = [
+            0.5 * (-q1 * gx - q2 * gy - q3 * gz),
+            0.5 * (q0 * gx + q2 * gz - q3 * gy),
+            0.5 * (q0 * gy - q1 * gz + q3 * gx),
+            0.5 * (q0 * gz + q1 * gy - q2 * gx),
+        ];
+
+        if let Some([ax, ay, az]) = unit(accel) {
+            // Gradient of the error between measured and estimated gravity.
+            let s = [
+                4. * q0 * q2 * q2 + 2. * q2 * ax + 4. * q0 * q1 * q1 - 2. * q1 * ay,
+                4. * q1 * q3 * q3 - 2. * q3 * ax + 4. * q0 * q0 * q1 - 2. * q0 * ay - 4. * q1
+                    + 8. * q1 * q1 * q1
+                    + 8. * q1 * q2 * q2
+                    + 4. * q1 * az,
+                4. * q0 * q0 * q2 + 2. * q0 * ax + 4. * q2 * q3 * q3 - 2. * q3 * ay - 4. * q2
+                    + 8. * q2 * q1 * q1
+                    + 8. * q2 * q2 * q2
+                    + 4. * q2 * az,
+                4. * q1 * q1 * q3 - 2. * q1 * ax + 4. * q2 * q2 * q3 - 2. * q2 * ay,
+            ];
+
+            let norm = libm::sqrtf(s.iter().map(|v| v * v).sum());
+            if norm > 0. {
+                for (d, s) in dq.iter_mut().zip(s) {
+                    *d -= self.beta * s / norm;
+                }
+            }
+        }
+
+        self.q = Quaternion {
+            w: q0 + dq[0] * dt,
+            x: q1 + dq[1] * dt,
+            y: q2 + dq[2] * dt,
+            z: q3 + dq[3] * dt,
+        }
+        .normalized();
+    }
+
+    fn quaternion(&self) -> Quaternion {
+        self.q
+    }
+}
+
+/// Mahony complementary filter with proportional and integral feedback.
+#[derive(Copy, Clone, Debug)]
+pub struct Mahony {
+    /// Proportional gain of the accelerometer correction.
+    pub kp: f32,
+    /// Integral gain, estimating the gyroscope bias. 0 disables it.
+    pub ki: f32,
+    q: Quaternion,
+    integral: [f32; 3],
+}
+
+impl Mahony {
+    /// A filter starting at the identity. `kp` = 1 and `ki` = 0 are common starting points.
+    pub fn new(kp: f32, ki: f32) -> Self {
+        Mahony {
+            kp,
+            ki,
+            q: Quaternion::IDENTITY,
+            integral: [0.; 3],
+        }
+    }
+
+    /// Start from a known orientation.
+    pub fn with_quaternion(kp: f32, ki: f32, q: Quaternion) -> Self {
+        Mahony {
+            q: q.normalized(),
+            ..Mahony::new(kp, ki)
+        }
+    }
+
+    /// Gyroscope bias estimated by the integral term, in rad/s.
+    pub fn bias(&self) -> [f32; 3] {
+        self.integral.map(|v| -v)
+    }
+}
+
+impl Ahrs for Mahony {
+    fn update_imu(&mut self, gyro: [f32; 3], accel: [f32; 3], dt: f32) {
+        let Quaternion {
+            w: q0,
+            x: q1,
+            y: q2,
+            z: q3,
+        } = self.q;
+        let [mut gx, mut gy, mut gz] = gyro;
+
+        if let Some([ax, ay, az]) = unit(accel) {
+            // Estimated direction of gravity, halved.
+            let vx = q1 * q3 - q0 * q2;
+            let vy = q0 * q1 + q2 * q3;
+            let vz = q0 * q0 - 0.5 + q3 * q3;
+
+            // Error is the cross product between measured and estimated gravity.
+            let error = [ay * vz - az * vy, az * vx - ax * vz, ax * vy - ay * vx];
+
+            if self.ki > 0. {
+                for (i, e) in self.integral.iter_mut().zip(error) {
+                    *i += 2. * self.ki * e * dt;
+                }
+            } else {
+                self.integral = [0.; 3];
+            }
+
+            gx += self.integral[0] + 2. * self.kp * error[0];
+            gy += self.integral[1] + 2. * self.kp * error[1];
+            gz += self.integral[2] + 2. * self.kp * error[2];
+        }
+
+        let (gx, gy, gz) = (gx * 0.5 * dt, gy * 0.5 * dt, gz * 0.5 * dt);
+        self.q = Quaternion {
+            w: q0 - q1 * gx - q2 * gy - q3 * gz,
+            x: q1 + q0 * gx + q2 * gz - q3 * gy,
+            y: q2 + q0 * gy - q1 * gz + q3 * gx,
+            z: q3 + q0 * gz + q1 * gy - q2 * gx,
+        }
+        .normalized();
+    }
+
+    fn quaternion(&self) -> Quaternion {
+        self.q
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ctrl1xl::Fs_Xl, ctrl2g::Fs};
+    use approx::*;
+    use core::f32::consts::{FRAC_PI_2, FRAC_PI_6};
+
+    const DT: f32 = 1. / 104.;
+
+    fn each_filter(test: impl Fn(&mut dyn Ahrs)) {
+        test(&mut Madgwick::new(0.1));
+        test(&mut Mahony::new(1., 0.));
+    }
+
+    /// Gravity seen by a sensor rolled by `roll` and pitched by `pitch`.
+    fn gravity(roll: f32, pitch: f32) -> [f32; 3] {
+        [
+            -libm::sinf(pitch),
+            libm::sinf(roll) * libm::cosf(pitch),
+            libm::cosf(roll) * libm::cosf(pitch),
+        ]
+    }
+
+    #[test]
+    fn euler_round_trip() {
+        let angles = EulerAngles {
+            roll: 0.3,
+            pitch: -0.4,
+            yaw: 1.2,
+        };
+        let e = Quaternion::from_euler(angles).euler();
+
+        assert_abs_diff_eq!(e.roll, angles.roll, epsilon = 1e-5);
+        assert_abs_diff_eq!(e.pitch, angles.pitch, epsilon = 1e-5);
+        assert_abs_diff_eq!(e.yaw, angles.yaw, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn timestamps() {
+        let mut dt = TimestampDt::new();
+
+        assert_eq!(dt.update(100), None);
+        assert_abs_diff_eq!(dt.update(500).unwrap(), 0.01, epsilon = 1e-7);
+        dt.update(u32::MAX - 199);
+        assert_abs_diff_eq!(dt.update(200).unwrap(), 0.01, epsilon = 1e-7);
+
+        assert_eq!(odr_dt(0.), 0.);
+        assert_abs_diff_eq!(odr_dt(104.), DT);
+    }
+
+    // Yaw is not observable from gravity, so only roll and pitch are checked.
+    #[test]
+    fn converges_to_tilt() {
+        each_filter(|ahrs| {
+            for _ in 0..(20. / DT) as usize {
+                ahrs.update_imu([0.; 3], gravity(FRAC_PI_6, -0.2), DT);
+            }
+
+            let e = ahrs.euler();
+            assert_abs_diff_eq!(e.roll, FRAC_PI_6, epsilon = 0.01);
+            assert_abs_diff_eq!(e.pitch, -0.2, epsilon = 0.01);
+        });
+    }
+
+    #[test]
+    fn integrates_yaw() {
+        each_filter(|ahrs| {
+            for _ in 0..104 {
+                ahrs.update_imu([0., 0., FRAC_PI_2 / (104. * DT)], [0., 0., 1.], DT);
+            }
+
+            let e = ahrs.euler();
+            assert_abs_diff_eq!(e.yaw, FRAC_PI_2, epsilon = 0.01);
+            assert_abs_diff_eq!(e.roll, 0., epsilon = 0.01);
+        });
+    }
+
+    #[test]
+    fn tracks_rotation() {
+        // Rolling at 45 dps for 1 s, gravity following the rotation.
+        let rate = FRAC_PI_2 / 2.;
+
+        each_filter(|ahrs| {
+            for n in 1..=104 {
+                let roll = rate * n as f32 * DT;
+                ahrs.update_imu([rate, 0., 0.], gravity(roll, 0.), DT);
+            }
+
+            assert_abs_diff_eq!(ahrs.euler().roll, rate, epsilon = 0.01);
+        });
+    }
+
+    #[test]
+    fn mahony_estimates_bias() {
+        let mut ahrs = Mahony::new(1., 0.5);
+
+        for _ in 0..(60. / DT) as usize {
+            ahrs.update_imu([0.02, -0.01, 0.], gravity(0., 0.), DT);
+        }
+
+        assert_abs_diff_eq!(ahrs.bias()[0], 0.02, epsilon = 0.002);
+        assert_abs_diff_eq!(ahrs.bias()[1], -0.01, epsilon = 0.002);
+        assert_abs_diff_eq!(ahrs.euler().roll, 0., epsilon = 0.01);
+    }
+
+    #[test]
+    fn update_from_values() {
+        let gyro = GyroValue::new(Fs::Dps250, [0; 3]);
+        // 0.5 g on y and 0.866 g on z, 30° of roll.
+        let accel = AccelValue::new(Fs_Xl::G2, [0, 8197, 14197]);
+
+        let mut ahrs = Madgwick::new(0.5);
+        for _ in 0..(10. / DT) as usize {
+            ahrs.update(&gyro, &accel, DT);
+        }
+
+        assert_abs_diff_eq!(ahrs.euler().roll, FRAC_PI_6, epsilon = 0.01);
+    }
+}
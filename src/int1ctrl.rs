@@ -0,0 +1,205 @@
+use core::fmt;
+
+use crate::Register;
+
+/// The INT1_CTRL register.
+///
+/// INT1 pin control register, routes the data-ready, FIFO and counter signals to the INT1 pad.
+pub struct Int1Ctrl {
+    pub address: u8,
+    value: u8,
+}
+
+impl fmt::Display for Int1Ctrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl fmt::Binary for Int1Ctrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:b}", self.value)
+    }
+}
+
+impl fmt::LowerHex for Int1Ctrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.value, f)
+    }
+}
+
+/// Sub-address of the register.
+pub const ADDR: u8 = 0x0du8;
+
+/// Sends DEN_DRDY (DEN stamped on sensor data flag) to INT1 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT1_DEN_DRDY: u8 = 7;
+
+/// Enables COUNTER_BDR_IA interrupt on INT1.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT1_CNT_BDR: u8 = 6;
+
+/// Enables FIFO full flag interrupt on INT1 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT1_FIFO_FULL: u8 = 5;
+
+/// Enables FIFO overrun interrupt on INT1 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT1_FIFO_OVR: u8 = 4;
+
+/// Enables FIFO threshold interrupt on INT1 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT1_FIFO_TH: u8 = 3;
+
+/// Enables boot status on INT1 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT1_BOOT: u8 = 2;
+
+/// Enables gyroscope data-ready interrupt on INT1 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT1_DRDY_G: u8 = 1;
+
+/// Enables accelerometer data-ready interrupt on INT1 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT1_DRDY_XL: u8 = 0;
+
+impl Register for Int1Ctrl {}
+
+impl Int1Ctrl {
+    pub fn new(value: u8, address: u8) -> Self {
+        Int1Ctrl { address, value }
+    }
+
+    /// Cached value of the register.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn int1_den_drdy(&self) -> bool {
+        self.value & (1 << INT1_DEN_DRDY) != 0
+    }
+
+    pub fn set_int1_den_drdy<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT1_DEN_DRDY);
+        self.value |= (value as u8) << INT1_DEN_DRDY;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int1_cnt_bdr(&self) -> bool {
+        self.value & (1 << INT1_CNT_BDR) != 0
+    }
+
+    pub fn set_int1_cnt_bdr<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT1_CNT_BDR);
+        self.value |= (value as u8) << INT1_CNT_BDR;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int1_fifo_full(&self) -> bool {
+        self.value & (1 << INT1_FIFO_FULL) != 0
+    }
+
+    pub fn set_int1_fifo_full<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT1_FIFO_FULL);
+        self.value |= (value as u8) << INT1_FIFO_FULL;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int1_fifo_ovr(&self) -> bool {
+        self.value & (1 << INT1_FIFO_OVR) != 0
+    }
+
+    pub fn set_int1_fifo_ovr<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT1_FIFO_OVR);
+        self.value |= (value as u8) << INT1_FIFO_OVR;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int1_fifo_th(&self) -> bool {
+        self.value & (1 << INT1_FIFO_TH) != 0
+    }
+
+    pub fn set_int1_fifo_th<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT1_FIFO_TH);
+        self.value |= (value as u8) << INT1_FIFO_TH;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int1_boot(&self) -> bool {
+        self.value & (1 << INT1_BOOT) != 0
+    }
+
+    pub fn set_int1_boot<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT1_BOOT);
+        self.value |= (value as u8) << INT1_BOOT;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int1_drdy_g(&self) -> bool {
+        self.value & (1 << INT1_DRDY_G) != 0
+    }
+
+    pub fn set_int1_drdy_g<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT1_DRDY_G);
+        self.value |= (value as u8) << INT1_DRDY_G;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int1_drdy_xl(&self) -> bool {
+        self.value & (1 << INT1_DRDY_XL) != 0
+    }
+
+    pub fn set_int1_drdy_xl<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT1_DRDY_XL);
+        self.value |= (value as u8) << INT1_DRDY_XL;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+}
@@ -0,0 +1,185 @@
+use core::fmt;
+
+use crate::Register;
+
+/// The INT2_CTRL register.
+///
+/// INT2 pin control register, routes the data-ready, FIFO and counter signals to the INT2 pad.
+pub struct Int2Ctrl {
+    pub address: u8,
+    value: u8,
+}
+
+impl fmt::Display for Int2Ctrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl fmt::Binary for Int2Ctrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:b}", self.value)
+    }
+}
+
+impl fmt::LowerHex for Int2Ctrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.value, f)
+    }
+}
+
+/// Sub-address of the register.
+pub const ADDR: u8 = 0x0eu8;
+
+/// Enables COUNTER_BDR_IA interrupt on INT2.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT2_CNT_BDR: u8 = 6;
+
+/// Enables FIFO full flag interrupt on INT2 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT2_FIFO_FULL: u8 = 5;
+
+/// Enables FIFO overrun interrupt on INT2 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT2_FIFO_OVR: u8 = 4;
+
+/// Enables FIFO threshold interrupt on INT2 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT2_FIFO_TH: u8 = 3;
+
+/// Enables temperature sensor data-ready interrupt on INT2 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT2_DRDY_TEMP: u8 = 2;
+
+/// Enables gyroscope data-ready interrupt on INT2 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT2_DRDY_G: u8 = 1;
+
+/// Enables accelerometer data-ready interrupt on INT2 pin.
+///
+/// Default value: 0
+///
+/// (0: disabled; 1: enabled)
+pub const INT2_DRDY_XL: u8 = 0;
+
+impl Register for Int2Ctrl {}
+
+impl Int2Ctrl {
+    pub fn new(value: u8, address: u8) -> Self {
+        Int2Ctrl { address, value }
+    }
+
+    /// Cached value of the register.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn int2_cnt_bdr(&self) -> bool {
+        self.value & (1 << INT2_CNT_BDR) != 0
+    }
+
+    pub fn set_int2_cnt_bdr<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT2_CNT_BDR);
+        self.value |= (value as u8) << INT2_CNT_BDR;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int2_fifo_full(&self) -> bool {
+        self.value & (1 << INT2_FIFO_FULL) != 0
+    }
+
+    pub fn set_int2_fifo_full<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT2_FIFO_FULL);
+        self.value |= (value as u8) << INT2_FIFO_FULL;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int2_fifo_ovr(&self) -> bool {
+        self.value & (1 << INT2_FIFO_OVR) != 0
+    }
+
+    pub fn set_int2_fifo_ovr<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT2_FIFO_OVR);
+        self.value |= (value as u8) << INT2_FIFO_OVR;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int2_fifo_th(&self) -> bool {
+        self.value & (1 << INT2_FIFO_TH) != 0
+    }
+
+    pub fn set_int2_fifo_th<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT2_FIFO_TH);
+        self.value |= (value as u8) << INT2_FIFO_TH;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int2_drdy_temp(&self) -> bool {
+        self.value & (1 << INT2_DRDY_TEMP) != 0
+    }
+
+    pub fn set_int2_drdy_temp<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT2_DRDY_TEMP);
+        self.value |= (value as u8) << INT2_DRDY_TEMP;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int2_drdy_g(&self) -> bool {
+        self.value & (1 << INT2_DRDY_G) != 0
+    }
+
+    pub fn set_int2_drdy_g<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT2_DRDY_G);
+        self.value |= (value as u8) << INT2_DRDY_G;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+
+    pub fn int2_drdy_xl(&self) -> bool {
+        self.value & (1 << INT2_DRDY_XL) != 0
+    }
+
+    pub fn set_int2_drdy_xl<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.value &= !(1 << INT2_DRDY_XL);
+        self.value |= (value as u8) << INT2_DRDY_XL;
+        self.write(i2c, self.address, ADDR, self.value)
+    }
+}
@@ -3,31 +3,31 @@
 //! # Quick Start
 //! To declare a sensor is pretty simple:
 //!
-//! ```rust
+//! ```rust,ignore
 //! let sensor = Ism330Dhcx::new(&mut i2c).unwrap()
 //! ```
 //!
 //! If you want to use another address for the chip, you can do:
 //!
-//! ```rust
+//! ```rust,ignore
 //! let sensor = Ism330Dhcx::new_with_address(&mut i2c, 0x6au8).unwrap()
 //! ```
 //!
 //! Or alter it after the fact
 //!
-//! ```rust
+//! ```rust,ignore
 //! sensor.set_address(0x6au8);
 //! ```
 //!
 //! All registers have the bits addressed by their function, for example here se set the `BOOT` register in the `CTRL_3C` register to `1`
 //!
-//! ```rust
+//! ```rust,ignore
 //! sensor.ctrl3c.set_boot(i2c, true).unwrap();
 //! ```
 //!
 //! For bits that operate together, they have their custom type abstracted. For example, to set the accelerometer data rate you have to operate 4 bits. But here you just have to specify your desired data rate and the driver takes care of it.
 //!
-//! ```rust
+//! ```rust,ignore
 //! // Sets the following bits
 //! // ODR_XL3 to 0
 //! // ODR_XL2 to 0
@@ -39,6 +39,16 @@
 //!     .set_accelerometer_data_rate(i2c, ctrl1xl::Odr_Xl::Hz52)
 //!     .unwrap();
 //! ```
+//!
+//! # Features
+//!
+//! - `sim`: a register level [`sim::Simulator`] of the device, for testing applications without hardware.
+//! - `uom`: readings as typed `uom` quantities, see [`units`].
+//! - `std`: builds the `ism330dhcx-cli` tool, which dumps registers, configures the sensor, streams samples
+//!   and drains the FIFO over Linux `i2c-dev` or against the simulator (`--sim`).
+//! - `fusion`: Madgwick and Mahony attitude filters in [`fusion`], `f32` only, using `libm`.
+//! - `async`: [`stream::Stream::next`] waits for the interrupt pin with `embedded-hal-async`.
+//! - `serde`: `Serialize`/`Deserialize` for samples, FIFO values and [`config::Config`], without `std`.
 
 //!
 //! # Reference
@@ -48,30 +58,69 @@
 
 #![cfg_attr(not(test), no_std)]
 
+pub mod array;
+pub mod config;
+pub mod counterbdr;
+pub mod ctrl10c;
 pub mod ctrl1xl;
 pub mod ctrl2g;
 pub mod ctrl3c;
+pub mod ctrl4c;
+pub mod ctrl6c;
 pub mod ctrl7g;
+pub mod ctrl8xl;
 pub mod ctrl9xl;
+pub mod demux;
+pub mod den;
+pub mod dump;
 pub mod fifo;
 pub mod fifoctrl;
 pub mod fifostatus;
+pub mod filter;
+#[cfg(feature = "fusion")]
+pub mod fusion;
+pub mod int1ctrl;
+pub mod int2ctrl;
+pub mod power;
+#[cfg(any(test, feature = "sim"))]
+pub mod sim;
+pub mod statusreg;
+pub mod stream;
+pub mod typestate;
+#[cfg(feature = "uom")]
+pub mod units;
+pub mod verify;
 
+use counterbdr::CounterBdr;
+use ctrl10c::Ctrl10C;
 use ctrl1xl::Ctrl1Xl;
 use ctrl2g::Ctrl2G;
 use ctrl3c::Ctrl3C;
+use ctrl4c::Ctrl4C;
+use ctrl6c::Ctrl6C;
 use ctrl7g::Ctrl7G;
+use ctrl8xl::Ctrl8Xl;
 use ctrl9xl::Ctrl9Xl;
+use fifo::FifoOut;
 use fifoctrl::FifoCtrl;
 use fifostatus::FifoStatus;
+use int1ctrl::Int1Ctrl;
+use int2ctrl::Int2Ctrl;
+use statusreg::StatusReg;
 
 /// Datasheet write address for the device. (D6h)
 pub const DEFAULT_I2C_ADDRESS: u8 = 0x6bu8;
 
+/// Number of registers from `FIFO_CTRL1` (07h) to `CTRL10_C` (19h), read in one burst to fill the caches.
+pub(crate) const CONTROL_BLOCK: usize = 19;
+
 const SENSORS_DPS_TO_RADS: f64 = 0.017453292;
 const SENSORS_GRAVITY_STANDARD: f64 = 9.80665;
+const SENSORS_DPS_TO_RADS_F32: f32 = 0.017453292;
+const SENSORS_GRAVITY_STANDARD_F32: f32 = 9.80665;
 
-#[derive(Copy, Clone, Debug, defmt::Format)]
+#[derive(Copy, Clone, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GyroValue {
     range: ctrl2g::Fs,
     count: [i16; 3],
@@ -111,9 +160,33 @@ impl GyroValue {
     pub fn as_dps(&self) -> [f64; 3] {
         self.as_mdps().map(|v| v / 1000.)
     }
+
+    /// As radians [rad], in `f32`
+    pub fn as_rad_f32(&self) -> [f32; 3] {
+        self.as_mdps_f32()
+            .map(|v| v * SENSORS_DPS_TO_RADS_F32 / 1000.)
+    }
+
+    /// As milli degrees per second [mdps], in `f32`
+    pub fn as_mdps_f32(&self) -> [f32; 3] {
+        let sensitivity = self.range.sensitivity();
+        self.count.map(|r| r as f32 * sensitivity)
+    }
+
+    /// As degrees per second [dps], in `f32`
+    pub fn as_dps_f32(&self) -> [f32; 3] {
+        self.as_mdps_f32().map(|v| v / 1000.)
+    }
+
+    /// As milli degrees per second [mdps], in fixed point, truncated toward zero
+    pub fn as_mdps_i32(&self) -> [i32; 3] {
+        let sensitivity = self.range.sensitivity_udps() as i64;
+        self.count.map(|r| (r as i64 * sensitivity / 1000) as i32)
+    }
 }
 
-#[derive(Copy, Clone, Debug, defmt::Format)]
+#[derive(Copy, Clone, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AccelValue {
     range: ctrl1xl::Fs_Xl,
     count: [i16; 3],
@@ -153,6 +226,71 @@ impl AccelValue {
     pub fn as_g(&self) -> [f64; 3] {
         self.as_mg().map(|v| v / 1000.)
     }
+
+    /// As [m/s^2], in `f32`
+    pub fn as_m_ss_f32(&self) -> [f32; 3] {
+        self.as_mg_f32()
+            .map(|v| v * SENSORS_GRAVITY_STANDARD_F32 / 1000.)
+    }
+
+    /// As [milli-g], in `f32`
+    pub fn as_mg_f32(&self) -> [f32; 3] {
+        let sensitivity = self.range.sensitivity();
+        self.count.map(|r| r as f32 * sensitivity)
+    }
+
+    /// As [g], in `f32`
+    pub fn as_g_f32(&self) -> [f32; 3] {
+        self.as_mg_f32().map(|v| v / 1000.)
+    }
+
+    /// As [micro-g], in fixed point, exact
+    pub fn as_ug(&self) -> [i32; 3] {
+        let sensitivity = self.range.sensitivity_ug();
+        self.count.map(|r| r as i32 * sensitivity)
+    }
+}
+
+/// Errors of operations that do more than one bus transfer.
+///
+/// `P` is the error of an interrupt pin, for operations that use one.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Error<E, P = core::convert::Infallible> {
+    /// The underlying bus failed.
+    Bus(E),
+    /// Reading the interrupt pin failed.
+    Pin(P),
+    /// The configuration was rejected before anything was written.
+    InvalidConfig(config::ConfigError),
+    /// The device did not get ready in time.
+    Timeout,
+}
+
+impl<E, P> From<E> for Error<E, P> {
+    fn from(error: E) -> Self {
+        Error::Bus(error)
+    }
+}
+
+/// Interval between two polls of `STATUS_REG` while waiting for data, in microseconds.
+const POLL_INTERVAL_US: u32 = 100;
+
+/// Temperature, gyroscope and accelerometer from the same output cycle.
+#[derive(Copy, Clone, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImuSample {
+    /// Temperature in Celsius. Always read by [`Ism330Dhcx::read_all`], `None` from a
+    /// [`demux::Pairing`] before the first temperature word.
+    pub temp: Option<f32>,
+    /// Angular rate, at the gyroscope full scale of the sample.
+    pub gyro: GyroValue,
+    /// Linear acceleration, at the accelerometer full scale of the sample.
+    pub accel: AccelValue,
+}
+
+fn temperature_from_msr(measurements: &[u8; 2]) -> f32 {
+    let raw_temp = (measurements[1] as i16) << 8 | measurements[0] as i16;
+    (raw_temp as f32 / 256.0) + 25.0
 }
 
 trait Register {
@@ -179,15 +317,51 @@ trait Register {
     }
 }
 
+/// Call `done` every [`POLL_INTERVAL_US`] until it returns true, or fail after `timeout_us`.
+fn poll_until<I2C, D>(
+    i2c: &mut I2C,
+    delay: &mut D,
+    timeout_us: u32,
+    mut done: impl FnMut(&mut I2C) -> Result<bool, I2C::Error>,
+) -> Result<(), Error<I2C::Error>>
+where
+    I2C: embedded_hal::i2c::I2c,
+    D: embedded_hal::delay::DelayNs,
+{
+    let mut waited_us = 0;
+
+    loop {
+        if done(i2c)? {
+            return Ok(());
+        }
+
+        if waited_us >= timeout_us {
+            return Err(Error::Timeout);
+        }
+
+        delay.delay_us(POLL_INTERVAL_US);
+        waited_us += POLL_INTERVAL_US;
+    }
+}
+
 pub struct Ism330Dhcx {
     pub address: u8,
     pub ctrl1xl: Ctrl1Xl,
     pub ctrl2g: Ctrl2G,
     pub ctrl3c: Ctrl3C,
+    pub ctrl4c: Ctrl4C,
+    pub ctrl6c: Ctrl6C,
     pub ctrl7g: Ctrl7G,
+    pub ctrl8xl: Ctrl8Xl,
     pub ctrl9xl: Ctrl9Xl,
+    pub ctrl10c: Ctrl10C,
     pub fifoctrl: FifoCtrl,
+    pub counterbdr: CounterBdr,
     pub fifostatus: FifoStatus,
+    pub fifoout: FifoOut,
+    pub int1ctrl: Int1Ctrl,
+    pub int2ctrl: Int2Ctrl,
+    pub statusreg: StatusReg,
 }
 
 impl Ism330Dhcx {
@@ -202,39 +376,211 @@ impl Ism330Dhcx {
     where
         I2C: embedded_hal::i2c::I2c,
     {
-        let mut registers = [0u8; 13];
-        i2c.write_read(address, &[0x10], &mut registers)?;
-
-        let ctrl1xl = Ctrl1Xl::new(registers[0], address);
-        let ctrl2g = Ctrl2G::new(registers[1], address);
-        let ctrl3c = Ctrl3C::new(registers[2], address);
-        let ctrl7g = Ctrl7G::new(registers[6], address);
-        let ctrl9xl = Ctrl9Xl::new(registers[8], address);
-        let fifoctrl = FifoCtrl::new(registers[9..13].try_into().unwrap(), address);
-        let fifostatus = FifoStatus::new(address);
-
-        let ism330dhcx = Self {
+        let mut registers = [0u8; CONTROL_BLOCK];
+        i2c.write_read(address, &[config::CONTROL_START], &mut registers)?;
+
+        let mut ism330dhcx = Self {
             address,
-            ctrl1xl,
-            ctrl2g,
-            ctrl3c,
-            ctrl7g,
-            ctrl9xl,
-            fifoctrl,
-            fifostatus,
+            ctrl1xl: Ctrl1Xl::new(0, address),
+            ctrl2g: Ctrl2G::new(0, address),
+            ctrl3c: Ctrl3C::new(0, address),
+            ctrl4c: Ctrl4C::new(0, address),
+            ctrl6c: Ctrl6C::new(0, address),
+            ctrl7g: Ctrl7G::new(0, address),
+            ctrl8xl: Ctrl8Xl::new(0, address),
+            ctrl9xl: Ctrl9Xl::new(0, address),
+            ctrl10c: Ctrl10C::new(0, address),
+            fifoctrl: FifoCtrl::new([0; 4], address),
+            counterbdr: CounterBdr::new([0; 2], address),
+            fifostatus: FifoStatus::new(address),
+            fifoout: FifoOut::new(address),
+            int1ctrl: Int1Ctrl::new(0, address),
+            int2ctrl: Int2Ctrl::new(0, address),
+            statusreg: StatusReg::new(address),
         };
+        ism330dhcx.load(&registers);
 
         Ok(ism330dhcx)
     }
 
+    /// Refresh the register caches from the control block, `FIFO_CTRL1` to `CTRL10_C`.
+    fn load(&mut self, registers: &[u8; CONTROL_BLOCK]) {
+        let address = self.address;
+        let at = |addr: u8| registers[(addr - config::CONTROL_START) as usize];
+
+        self.ctrl1xl.refresh(at(ctrl1xl::ADDR));
+        self.ctrl2g.refresh(at(ctrl2g::ADDR));
+        self.ctrl3c = Ctrl3C::new(at(ctrl3c::ADDR), address);
+        self.ctrl4c = Ctrl4C::new(at(ctrl4c::ADDR), address);
+        self.ctrl6c = Ctrl6C::new(at(ctrl6c::ADDR), address);
+        self.ctrl7g = Ctrl7G::new(at(ctrl7g::ADDR), address);
+        self.ctrl8xl = Ctrl8Xl::new(at(ctrl8xl::ADDR), address);
+        self.ctrl9xl = Ctrl9Xl::new(at(ctrl9xl::ADDR), address);
+        self.ctrl10c = Ctrl10C::new(at(ctrl10c::ADDR), address);
+        self.fifoctrl = FifoCtrl::new(registers[0..4].try_into().unwrap(), address);
+        self.counterbdr =
+            CounterBdr::new([at(counterbdr::ADDR), at(counterbdr::ADDR + 1)], address);
+        self.int1ctrl = Int1Ctrl::new(at(int1ctrl::ADDR), address);
+        self.int2ctrl = Int2Ctrl::new(at(int2ctrl::ADDR), address);
+    }
+
+    /// Validate and apply a whole [`config::Config`].
+    ///
+    /// The control block is read once, then written back in two auto-increment bursts: the `CTRL`
+    /// registers first so the data rates are in place before the FIFO starts batching, then the FIFO
+    /// and interrupt registers. Nothing is written if the configuration is invalid.
+    ///
+    /// A full scale change is tracked for the samples already in the FIFO, as with
+    /// [`Ism330Dhcx::set_accel_full_scale`].
+    pub fn apply<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        config: &config::Config,
+    ) -> Result<(), Error<I2C::Error>>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        config.validate().map_err(Error::InvalidConfig)?;
+
+        let mut registers = [0u8; CONTROL_BLOCK];
+        i2c.write_read(self.address, &[config::CONTROL_START], &mut registers)?;
+
+        let before = config::Config::decode(&registers);
+        let scales = (before.gyro.scale, before.accel.scale);
+
+        config.encode(&mut registers);
+        let (ctrl, fifo) = config::Config::bursts(&registers);
+        i2c.write(self.address, &ctrl)?;
+        i2c.write(self.address, &fifo)?;
+
+        self.load(&registers);
+        // The CTRL burst goes first, the FIFO settings in effect are the old ones.
+        self.track_scale_change(scales, before.fifo.odr_change);
+        Ok(())
+    }
+
+    /// Read the whole configuration back from the device, refreshing the register caches.
+    pub fn read_config<I2C>(&mut self, i2c: &mut I2C) -> Result<config::Config, I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let registers = self.reload(i2c)?;
+        Ok(config::Config::decode(&registers))
+    }
+
+    /// Read the control block and refresh the register caches from it.
+    fn reload<I2C>(&mut self, i2c: &mut I2C) -> Result<[u8; CONTROL_BLOCK], I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let mut registers = [0u8; CONTROL_BLOCK];
+        i2c.write_read(self.address, &[config::CONTROL_START], &mut registers)?;
+
+        self.load(&registers);
+        Ok(registers)
+    }
+
+    /// Reload all the register caches from the device.
+    pub fn sync<I2C>(&mut self, i2c: &mut I2C) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.reload(i2c)?;
+        Ok(())
+    }
+
+    /// Compare the register caches with the device, see [`verify::Mismatches`].
+    ///
+    /// The caches are left untouched, use [`Ism330Dhcx::sync`] to take the device values.
+    pub fn verify<I2C>(&self, i2c: &mut I2C) -> Result<verify::Mismatches, I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let mut registers = [0u8; CONTROL_BLOCK];
+        i2c.write_read(self.address, &[config::CONTROL_START], &mut registers)?;
+
+        Ok(verify::Mismatches::new(self.cached(), registers))
+    }
+
+    /// Cached registers, as (address, value) pairs.
+    fn cached(&self) -> verify::Cached {
+        let fifo = self.fifoctrl.value();
+        let counter = self.counterbdr.value();
+        [
+            (fifoctrl::ADDR, fifo[0]),
+            (fifoctrl::ADDR + 1, fifo[1]),
+            (fifoctrl::ADDR + 2, fifo[2]),
+            (fifoctrl::ADDR + 3, fifo[3]),
+            (counterbdr::ADDR, counter[0]),
+            (counterbdr::ADDR + 1, counter[1]),
+            (int1ctrl::ADDR, self.int1ctrl.value()),
+            (int2ctrl::ADDR, self.int2ctrl.value()),
+            (ctrl1xl::ADDR, self.ctrl1xl.value()),
+            (ctrl2g::ADDR, self.ctrl2g.value()),
+            (ctrl3c::ADDR, self.ctrl3c.value()),
+            (ctrl4c::ADDR, self.ctrl4c.value()),
+            (ctrl6c::ADDR, self.ctrl6c.value()),
+            (ctrl7g::ADDR, self.ctrl7g.value()),
+            (ctrl8xl::ADDR, self.ctrl8xl.value()),
+            (ctrl9xl::ADDR, self.ctrl9xl.value()),
+            (ctrl10c::ADDR, self.ctrl10c.value()),
+        ]
+    }
+
+    /// Snapshot every documented register, see [`dump::RegisterDump`].
+    ///
+    /// The clear-on-read interrupt sources and status registers are skipped, so pending interrupts
+    /// and data-ready flags are left for the application. An overrun flagged in `FIFO_STATUS2` is kept for
+    /// [`fifostatus::FifoStatus::take_overrun`].
+    pub fn dump<I2C>(&mut self, i2c: &mut I2C) -> Result<dump::RegisterDump, I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let dump = dump::RegisterDump::read(i2c, self.address)?;
+        if let Some(status2) = dump.get(dump::Page::Main, fifostatus::ADDR + 1) {
+            self.fifostatus.keep_overrun(status2);
+        }
+        Ok(dump)
+    }
+
+    /// Write a dump back to the device and refresh the register caches.
+    ///
+    /// A full scale change is tracked for the samples already in the FIFO, as with
+    /// [`Ism330Dhcx::set_accel_full_scale`].
+    pub fn restore<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        dump: &dump::RegisterDump,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let scales = self.full_scales();
+        dump.restore(i2c, self.address)?;
+        self.reload(i2c)?;
+        // The FIFO registers are restored first, the FIFO settings in effect are the new ones.
+        self.track_scale_change(scales, self.fifoctrl.odr_chg_en());
+        Ok(())
+    }
+
     pub fn set_address(&mut self, address: u8) {
+        self.address = address;
         self.ctrl1xl.address = address;
         self.ctrl2g.address = address;
         self.ctrl3c.address = address;
+        self.ctrl4c.address = address;
+        self.ctrl6c.address = address;
         self.ctrl7g.address = address;
+        self.ctrl8xl.address = address;
         self.ctrl9xl.address = address;
+        self.ctrl10c.address = address;
         self.fifoctrl.address = address;
+        self.counterbdr.address = address;
         self.fifostatus.address = address;
+        self.fifoout.address = address;
+        self.int1ctrl.address = address;
+        self.int2ctrl.address = address;
+        self.statusreg.address = address;
     }
 
     /// Get temperature in Celsius.
@@ -245,10 +591,7 @@ impl Ism330Dhcx {
         let mut measurements = [0u8; 2];
         i2c.write_read(self.address, &[0x20], &mut measurements)?;
 
-        let raw_temp = (measurements[1] as i16) << 8 | measurements[0] as i16;
-        let temp: f32 = (raw_temp as f32 / 256.0) + 25.0;
-
-        Ok(temp)
+        Ok(temperature_from_msr(&measurements))
     }
 
     pub fn get_gyroscope<I2C>(&mut self, i2c: &mut I2C) -> Result<GyroValue, I2C::Error>
@@ -275,6 +618,230 @@ impl Ism330Dhcx {
         Ok(AccelValue::from_msr(scale, &measurements))
     }
 
+    /// Start or stop the timestamp counter, `TIMESTAMP_EN` in `CTRL10_C`.
+    pub fn enable_timestamp<I2C>(&mut self, i2c: &mut I2C, enable: bool) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.ctrl10c.set_timestamp_en(i2c, enable)
+    }
+
+    /// Restart the timestamp counter from zero.
+    pub fn reset_timestamp<I2C>(&mut self, i2c: &mut I2C) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        i2c.write(
+            self.address,
+            &[ctrl10c::TIMESTAMP2, ctrl10c::TIMESTAMP_RESET],
+        )
+    }
+
+    /// Timestamp counter, in 25 µs steps.
+    pub fn get_timestamp<I2C>(&mut self, i2c: &mut I2C) -> Result<u32, I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let mut timestamp = [0u8; 4];
+        i2c.write_read(self.address, &[ctrl10c::TIMESTAMP0], &mut timestamp)?;
+
+        Ok(u32::from_le_bytes(timestamp))
+    }
+
+    /// Which outputs have new data, from `STATUS_REG`.
+    pub fn data_ready<I2C>(&mut self, i2c: &mut I2C) -> Result<statusreg::DataReady, I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.statusreg.data_ready(i2c)
+    }
+
+    /// Raise `COUNTER_BDR_IA` every `samples` batch events of `sensor`, routed to `pin` if any.
+    ///
+    /// `samples` goes from 1 to [`counterbdr::THRESHOLD_MAX`]. The counter restarts from zero.
+    pub fn set_bdr_counter<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        sensor: counterbdr::TrigCounterBdr,
+        samples: u16,
+        pin: Option<stream::IntPin>,
+    ) -> Result<(), Error<I2C::Error>>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        if !(1..=counterbdr::THRESHOLD_MAX).contains(&samples) {
+            return Err(Error::InvalidConfig(
+                config::ConfigError::BdrCounterOutOfRange,
+            ));
+        }
+
+        self.counterbdr.set_trig_counter_bdr(i2c, sensor)?;
+        self.counterbdr.set_threshold(i2c, samples)?;
+        self.counterbdr.reset_counter(i2c)?;
+        self.int1ctrl
+            .set_int1_cnt_bdr(i2c, pin == Some(stream::IntPin::Int1))?;
+        self.int2ctrl
+            .set_int2_cnt_bdr(i2c, pin == Some(stream::IntPin::Int2))?;
+        Ok(())
+    }
+
+    /// Stop the BDR counter and its interrupt.
+    pub fn disable_bdr_counter<I2C>(&mut self, i2c: &mut I2C) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        self.int1ctrl.set_int1_cnt_bdr(i2c, false)?;
+        self.int2ctrl.set_int2_cnt_bdr(i2c, false)?;
+        self.counterbdr.set_threshold(i2c, 0)
+    }
+
+    /// Wait until a new accelerometer sample is available, polling every 100 µs.
+    ///
+    /// Returns [`Error::Timeout`] if none arrives within `timeout_us` microseconds.
+    pub fn wait_for_accel<I2C, D>(
+        &mut self,
+        i2c: &mut I2C,
+        delay: &mut D,
+        timeout_us: u32,
+    ) -> Result<(), Error<I2C::Error>>
+    where
+        I2C: embedded_hal::i2c::I2c,
+        D: embedded_hal::delay::DelayNs,
+    {
+        self.wait_for(i2c, delay, timeout_us, |ready| ready.accel)
+    }
+
+    /// Wait until a new gyroscope sample is available, polling every 100 µs.
+    ///
+    /// Returns [`Error::Timeout`] if none arrives within `timeout_us` microseconds.
+    pub fn wait_for_gyro<I2C, D>(
+        &mut self,
+        i2c: &mut I2C,
+        delay: &mut D,
+        timeout_us: u32,
+    ) -> Result<(), Error<I2C::Error>>
+    where
+        I2C: embedded_hal::i2c::I2c,
+        D: embedded_hal::delay::DelayNs,
+    {
+        self.wait_for(i2c, delay, timeout_us, |ready| ready.gyro)
+    }
+
+    fn wait_for<I2C, D>(
+        &mut self,
+        i2c: &mut I2C,
+        delay: &mut D,
+        timeout_us: u32,
+        ready: fn(&statusreg::DataReady) -> bool,
+    ) -> Result<(), Error<I2C::Error>>
+    where
+        I2C: embedded_hal::i2c::I2c,
+        D: embedded_hal::delay::DelayNs,
+    {
+        let statusreg = &mut self.statusreg;
+        poll_until(i2c, delay, timeout_us, |i2c| {
+            Ok(ready(&statusreg.data_ready(i2c)?))
+        })
+    }
+
+    /// Software reset: restore every register to its default value, wait for `SW_RESET` to clear
+    /// and refresh the register caches.
+    ///
+    /// Returns [`Error::Timeout`] if the reset does not complete within `timeout_us` microseconds.
+    pub fn reset<I2C, D>(
+        &mut self,
+        i2c: &mut I2C,
+        delay: &mut D,
+        timeout_us: u32,
+    ) -> Result<(), Error<I2C::Error>>
+    where
+        I2C: embedded_hal::i2c::I2c,
+        D: embedded_hal::delay::DelayNs,
+    {
+        self.ctrl3c.sw_reset(i2c)?;
+        self.wait_cleared(i2c, delay, timeout_us, ctrl3c::SW_RESET)
+    }
+
+    /// Reboot: reload the trimming parameters from flash, wait for `BOOT` to clear and refresh the
+    /// register caches.
+    ///
+    /// The accelerometer must be on. The reboot takes about 10 ms; returns [`Error::Timeout`] if it
+    /// does not complete within `timeout_us` microseconds.
+    pub fn reboot<I2C, D>(
+        &mut self,
+        i2c: &mut I2C,
+        delay: &mut D,
+        timeout_us: u32,
+    ) -> Result<(), Error<I2C::Error>>
+    where
+        I2C: embedded_hal::i2c::I2c,
+        D: embedded_hal::delay::DelayNs,
+    {
+        self.ctrl3c.set_boot(i2c, true)?;
+        self.wait_cleared(i2c, delay, timeout_us, ctrl3c::BOOT)
+    }
+
+    /// Poll `CTRL3_C` until the self-clearing `bit` reads 0, then reload the control block.
+    ///
+    /// The FIFO is emptied by both, so the full scale changes, gap tracking and overrun seen for
+    /// the words it held are dropped too.
+    fn wait_cleared<I2C, D>(
+        &mut self,
+        i2c: &mut I2C,
+        delay: &mut D,
+        timeout_us: u32,
+        bit: u8,
+    ) -> Result<(), Error<I2C::Error>>
+    where
+        I2C: embedded_hal::i2c::I2c,
+        D: embedded_hal::delay::DelayNs,
+    {
+        let address = self.address;
+        poll_until(i2c, delay, timeout_us, |i2c| {
+            let mut ctrl3_c = [0u8];
+            i2c.write_read(address, &[ctrl3c::ADDR], &mut ctrl3_c)?;
+            Ok(ctrl3_c[0] & (1 << bit) == 0)
+        })?;
+
+        self.fifoout = FifoOut::new(address);
+        self.fifostatus = FifoStatus::new(address);
+        self.reload(i2c)?;
+        Ok(())
+    }
+
+    /// Read temperature, gyroscope and accelerometer in one 14-byte burst from `OUT_TEMP_L`.
+    ///
+    /// Block data update is enabled first if it is off, so all the outputs come from the same
+    /// output cycle.
+    pub fn read_all<I2C>(&mut self, i2c: &mut I2C) -> Result<ImuSample, I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        if !self.ctrl3c.bdu() {
+            self.ctrl3c.set_bdu(i2c, true)?;
+        }
+
+        let mut measurements = [0u8; 14];
+        i2c.write_read(self.address, &[0x20], &mut measurements)?;
+
+        Ok(self.sample_from_msr(&measurements))
+    }
+
+    /// Decode a burst from `OUT_TEMP_L` to `OUTZ_H_A` with the cached full scales.
+    pub(crate) fn sample_from_msr(&self, measurements: &[u8; 14]) -> ImuSample {
+        ImuSample {
+            temp: Some(temperature_from_msr(measurements[0..2].try_into().unwrap())),
+            gyro: GyroValue::from_msr(
+                self.ctrl2g.chain_full_scale(),
+                measurements[2..8].try_into().unwrap(),
+            ),
+            accel: AccelValue::from_msr(
+                self.ctrl1xl.chain_full_scale(),
+                measurements[8..14].try_into().unwrap(),
+            ),
+        }
+    }
+
     pub fn fifo_pop<I2C>(&mut self, i2c: &mut I2C) -> Result<fifo::Value, I2C::Error>
     where
         I2C: embedded_hal::i2c::I2c,
@@ -282,7 +849,61 @@ impl Ism330Dhcx {
         let gyro_scale = self.ctrl2g.chain_full_scale();
         let accel_scale = self.ctrl1xl.chain_full_scale();
 
-        fifo::FifoOut::new(self.address).pop(i2c, gyro_scale, accel_scale)
+        self.fifoout.pop_tracked(i2c, gyro_scale, accel_scale)
+    }
+
+    /// Set the accelerometer full scale.
+    ///
+    /// With `ODR_CHG_EN` set, samples already in the FIFO keep being scaled with the old full
+    /// scale by [`Ism330Dhcx::fifo_pop`], up to the `CFG-Change` word batched with the change.
+    pub fn set_accel_full_scale<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        scale: ctrl1xl::Fs_Xl,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let scales = self.full_scales();
+        self.ctrl1xl.set_chain_full_scale(i2c, scale)?;
+        self.track_scale_change(scales, self.fifoctrl.odr_chg_en());
+        Ok(())
+    }
+
+    /// Set the gyroscope full scale.
+    ///
+    /// With `ODR_CHG_EN` set, samples already in the FIFO keep being scaled with the old full
+    /// scale by [`Ism330Dhcx::fifo_pop`], up to the `CFG-Change` word batched with the change.
+    pub fn set_gyro_full_scale<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        scale: ctrl2g::Fs,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let scales = self.full_scales();
+        self.ctrl2g.set_chain_full_scale(i2c, scale)?;
+        self.track_scale_change(scales, self.fifoctrl.odr_chg_en());
+        Ok(())
+    }
+
+    fn full_scales(&self) -> (ctrl2g::Fs, ctrl1xl::Fs_Xl) {
+        (
+            self.ctrl2g.chain_full_scale(),
+            self.ctrl1xl.chain_full_scale(),
+        )
+    }
+
+    /// Keep scaling the words in the FIFO with `scales` if the cached full scales differ, see
+    /// [`fifo::FifoOut::track_scales`].
+    ///
+    /// `odr_change` is the state of `ODR_CHG_EN` during the change. Without it no `CFG-Change` word
+    /// marks where the new full scales start, and the words are scaled with the current ones.
+    fn track_scale_change(&mut self, scales: (ctrl2g::Fs, ctrl1xl::Fs_Xl), odr_change: bool) {
+        if self.full_scales() != scales && odr_change {
+            self.fifoout.track_scales(scales.0, scales.1);
+        }
     }
 }
 
@@ -336,4 +957,453 @@ mod tests {
         let a = GyroValue::from_msr(Fs::Dps250, &[0xb7, 0xa6, 0x0, 0x0, 0x0, 0x0]).as_rad();
         assert_abs_diff_eq!(a[0], -200. * SENSORS_DPS_TO_RADS, epsilon = 0.01);
     }
+
+    #[test]
+    fn read_all_in_one_burst() {
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+        let mut registers = [0u8; CONTROL_BLOCK];
+        registers[ctrl3c::ADDR as usize - 0x07] = 0b0000_0100;
+        let outputs = vec![
+            0x00, 0x02, // 27 °C
+            0xa4, 0x2c, 0, 0, 0, 0, // 100 dps on x
+            0, 0, 0, 0, 0x09, 0x40, // 1 g on z
+        ];
+
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x6b, vec![0x07], registers.to_vec()),
+            Transaction::write(0x6b, vec![ctrl3c::ADDR, 0b0100_0100]),
+            Transaction::write_read(0x6b, vec![0x20], outputs.clone()),
+            Transaction::write_read(0x6b, vec![0x20], outputs),
+        ]);
+
+        let mut sensor = Ism330Dhcx::new(&mut i2c).unwrap();
+        let sample = sensor.read_all(&mut i2c).unwrap();
+        assert_eq!(sensor.read_all(&mut i2c).unwrap(), sample);
+
+        assert_eq!(sample.temp, Some(27.));
+        assert_abs_diff_eq!(sample.gyro.as_dps()[0], 100., epsilon = 0.01);
+        assert_abs_diff_eq!(sample.accel.as_g()[2], 1., epsilon = 0.001);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn read_all_matches_separate_reads() {
+        let mut sim = sim::Simulator::new();
+        sim.set_gyroscope_signal(|_| [10., -20., 30.]);
+        sim.set_temperature_signal(|_| 31.5);
+
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, ctrl1xl::Odr_Xl::Hz104)
+            .unwrap();
+        sensor
+            .ctrl2g
+            .set_gyroscope_data_rate(&mut sim, ctrl2g::Odr::Hz104)
+            .unwrap();
+        sim.advance_us(50_000);
+
+        let sample = sensor.read_all(&mut sim).unwrap();
+        assert!(sensor.ctrl3c.bdu());
+        assert_eq!(sample.temp, Some(sensor.get_temperature(&mut sim).unwrap()));
+        assert_eq!(sample.gyro, sensor.get_gyroscope(&mut sim).unwrap());
+        assert_eq!(sample.accel, sensor.get_accelerometer(&mut sim).unwrap());
+        assert_abs_diff_eq!(sample.gyro.as_dps()[2], 30., epsilon = 0.01);
+    }
+
+    #[test]
+    fn wait_for_data() {
+        use embedded_hal_mock::eh1::delay::{CheckedDelay, Transaction as Delay};
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+        let status = |value: u8| Transaction::write_read(0x6b, vec![statusreg::ADDR], vec![value]);
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x6b, vec![0x07], vec![0; CONTROL_BLOCK]),
+            status(0b000),
+            status(0b010),
+            status(0b011),
+            status(0b000),
+            status(0b000),
+            status(0b001),
+            status(0b001),
+        ]);
+        let mut delay = CheckedDelay::new(&[
+            Delay::delay_us(100),
+            Delay::delay_us(100),
+            Delay::delay_us(100),
+        ]);
+
+        let mut sensor = Ism330Dhcx::new(&mut i2c).unwrap();
+        sensor.wait_for_accel(&mut i2c, &mut delay, 1_000).unwrap();
+        assert!(matches!(
+            sensor.wait_for_gyro(&mut i2c, &mut delay, 100),
+            Err(Error::Timeout)
+        ));
+        assert!(sensor.data_ready(&mut i2c).unwrap().accel);
+        assert!(!sensor.data_ready(&mut i2c).unwrap().gyro);
+
+        i2c.done();
+        delay.done();
+    }
+
+    #[test]
+    fn reset_and_reboot() {
+        use embedded_hal_mock::eh1::delay::{CheckedDelay, Transaction as Delay};
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+        let ctrl3_c = |value: u8| Transaction::write_read(0x6b, vec![ctrl3c::ADDR], vec![value]);
+        let mut block = [0u8; CONTROL_BLOCK];
+        block[(ctrl1xl::ADDR - 0x07) as usize] = 0x40;
+        block[(ctrl3c::ADDR - 0x07) as usize] = 0x44;
+        let mut defaults = [0u8; CONTROL_BLOCK];
+        defaults[(ctrl3c::ADDR - 0x07) as usize] = 0x04;
+
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x6b, vec![0x07], block.to_vec()),
+            Transaction::write(0x6b, vec![ctrl3c::ADDR, 0x45]),
+            ctrl3_c(0x45),
+            ctrl3_c(0x04),
+            Transaction::write_read(0x6b, vec![0x07], defaults.to_vec()),
+            Transaction::write(0x6b, vec![ctrl3c::ADDR, 0x84]),
+            ctrl3_c(0x84),
+            ctrl3_c(0x84),
+        ]);
+        let mut delay = CheckedDelay::new(&[Delay::delay_us(100), Delay::delay_us(100)]);
+
+        let mut sensor = Ism330Dhcx::new(&mut i2c).unwrap();
+        assert_eq!(sensor.ctrl1xl.accelerometer_data_rate(), 104.);
+        sensor.reset(&mut i2c, &mut delay, 1_000).unwrap();
+        assert_eq!(sensor.ctrl1xl.accelerometer_data_rate(), 0.);
+        assert!(!sensor.ctrl3c.bdu());
+
+        assert!(matches!(
+            sensor.reboot(&mut i2c, &mut delay, 100),
+            Err(Error::Timeout)
+        ));
+
+        i2c.done();
+        delay.done();
+    }
+
+    const COUNTS: [i16; 7] = [i16::MIN, -12345, -1, 0, 1, 4321, i16::MAX];
+
+    #[test]
+    fn accel_f32_and_fixed_point() {
+        use ctrl1xl::Fs_Xl;
+
+        for range in [Fs_Xl::G2, Fs_Xl::G4, Fs_Xl::G8, Fs_Xl::G16] {
+            let lsb = range.sensitivity() as f64;
+
+            for count in COUNTS {
+                let value = AccelValue::new(range, [count, -count.saturating_add(1), count / 3]);
+                let mg = value.as_mg();
+
+                for (i, mg) in mg.into_iter().enumerate() {
+                    assert_abs_diff_eq!(value.as_mg_f32()[i] as f64, mg, epsilon = lsb);
+                    assert_abs_diff_eq!(value.as_ug()[i] as f64 / 1000., mg, epsilon = lsb);
+                    assert_abs_diff_eq!(
+                        value.as_g_f32()[i] as f64,
+                        value.as_g()[i],
+                        epsilon = lsb / 1000.
+                    );
+                    assert_abs_diff_eq!(
+                        value.as_m_ss_f32()[i] as f64,
+                        value.as_m_ss()[i],
+                        epsilon = lsb * SENSORS_GRAVITY_STANDARD / 1000.
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gyro_f32_and_fixed_point() {
+        use ctrl2g::Fs;
+
+        for range in [
+            Fs::Dps125,
+            Fs::Dps250,
+            Fs::Dps500,
+            Fs::Dps1000,
+            Fs::Dps2000,
+            Fs::Dps4000,
+        ] {
+            let lsb = range.sensitivity() as f64;
+
+            for count in COUNTS {
+                let value = GyroValue::new(range, [count, -count.saturating_add(1), count / 3]);
+                let mdps = value.as_mdps();
+
+                for (i, mdps) in mdps.into_iter().enumerate() {
+                    assert_abs_diff_eq!(value.as_mdps_f32()[i] as f64, mdps, epsilon = lsb);
+                    assert_abs_diff_eq!(value.as_mdps_i32()[i] as f64, mdps, epsilon = lsb);
+                    assert_abs_diff_eq!(
+                        value.as_dps_f32()[i] as f64,
+                        value.as_dps()[i],
+                        epsilon = lsb / 1000.
+                    );
+                    assert_abs_diff_eq!(
+                        value.as_rad_f32()[i] as f64,
+                        value.as_rad()[i],
+                        epsilon = lsb * SENSORS_DPS_TO_RADS / 1000.
+                    );
+                }
+            }
+        }
+
+        assert_eq!(
+            GyroValue::new(Fs::Dps4000, [i16::MAX, i16::MIN, -1]).as_mdps_i32(),
+            [4_587_380, -4_587_520, -140]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    fn round_trip<T>(value: T)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + core::fmt::Debug,
+    {
+        let mut buf = [0u8; 64];
+        let bytes = postcard::to_slice(&value, &mut buf).unwrap();
+        assert_eq!(postcard::from_bytes::<T>(bytes).unwrap(), value);
+
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(serde_json::from_str::<T>(&json).unwrap(), value);
+    }
+
+    #[test]
+    fn bdr_counter() {
+        let mut sim = sim::Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, ctrl1xl::Odr_Xl::Hz104)
+            .unwrap();
+        sensor
+            .fifoctrl
+            .set_accelerometer_batch_data_rate(&mut sim, fifoctrl::BdrXl::Hz104)
+            .unwrap();
+        sensor
+            .fifoctrl
+            .mode(&mut sim, fifoctrl::FifoMode::Continuous)
+            .unwrap();
+
+        assert!(matches!(
+            sensor.set_bdr_counter(&mut sim, counterbdr::TrigCounterBdr::Accelerometer, 0, None),
+            Err(Error::InvalidConfig(
+                config::ConfigError::BdrCounterOutOfRange
+            ))
+        ));
+        sensor
+            .set_bdr_counter(
+                &mut sim,
+                counterbdr::TrigCounterBdr::Accelerometer,
+                5,
+                Some(stream::IntPin::Int2),
+            )
+            .unwrap();
+        assert!(sensor.int2ctrl.int2_cnt_bdr());
+        assert!(!sensor.int1ctrl.int1_cnt_bdr());
+
+        sim.advance_us(30_000);
+        assert!(!sensor.fifostatus.count_bdr_reached(&mut sim).unwrap());
+        sim.advance_us(30_000);
+        assert!(sensor.fifostatus.count_bdr_reached(&mut sim).unwrap());
+        assert!(!sensor.fifostatus.count_bdr_reached(&mut sim).unwrap());
+
+        // the gyroscope is off, nothing to count
+        sensor
+            .counterbdr
+            .set_trig_counter_bdr(&mut sim, counterbdr::TrigCounterBdr::Gyroscope)
+            .unwrap();
+        sensor.counterbdr.reset_counter(&mut sim).unwrap();
+        sim.advance_us(100_000);
+        assert!(!sensor.fifostatus.count_bdr_reached(&mut sim).unwrap());
+
+        sensor.disable_bdr_counter(&mut sim).unwrap();
+        assert!(sensor.verify(&mut sim).unwrap().is_empty());
+        assert_eq!(sensor.counterbdr.threshold(), 0);
+    }
+
+    #[test]
+    fn fifo_tracks_scale_changes() {
+        let mut sim = sim::Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, ctrl1xl::Odr_Xl::Hz104)
+            .unwrap();
+        sensor
+            .fifoctrl
+            .set_accelerometer_batch_data_rate(&mut sim, fifoctrl::BdrXl::Hz104)
+            .unwrap();
+        sensor.fifoctrl.set_odr_chg_en(&mut sim, true).unwrap();
+        sensor
+            .fifoctrl
+            .mode(&mut sim, fifoctrl::FifoMode::Continuous)
+            .unwrap();
+
+        sim.advance_us(50_000);
+        sensor
+            .set_accel_full_scale(&mut sim, ctrl1xl::Fs_Xl::G16)
+            .unwrap();
+        sim.advance_us(50_000);
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, ctrl1xl::Odr_Xl::Hz208)
+            .unwrap();
+        sim.advance_us(50_000);
+
+        let mut accel = 0;
+        let mut changes = 0;
+        loop {
+            match sensor.fifo_pop(&mut sim).unwrap() {
+                fifo::Value::Accel(a) => {
+                    assert_abs_diff_eq!(a.as_g()[2], 1., epsilon = 0.01);
+                    accel += 1;
+                }
+                fifo::Value::ConfigChange(_) => changes += 1,
+                fifo::Value::Empty => break,
+                v => panic!("{:?}", v),
+            }
+        }
+        // One for the full scale, one for the data rate.
+        assert_eq!((accel, changes), (15, 2));
+    }
+
+    #[test]
+    fn reset_drops_fifo_tracking() {
+        use embedded_hal_mock::eh1::delay::NoopDelay;
+
+        let mut sim = sim::Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        let batching = |sensor: &mut Ism330Dhcx, sim: &mut sim::Simulator| {
+            sensor
+                .ctrl1xl
+                .set_accelerometer_data_rate(sim, ctrl1xl::Odr_Xl::Hz104)
+                .unwrap();
+            sensor
+                .fifoctrl
+                .set_accelerometer_batch_data_rate(sim, fifoctrl::BdrXl::Hz104)
+                .unwrap();
+            sensor
+                .fifoctrl
+                .mode(sim, fifoctrl::FifoMode::Continuous)
+                .unwrap();
+        };
+
+        batching(&mut sensor, &mut sim);
+        sim.advance_us(50_000);
+        sensor
+            .set_accel_full_scale(&mut sim, ctrl1xl::Fs_Xl::G16)
+            .unwrap();
+        assert!(matches!(
+            sensor.fifo_pop(&mut sim).unwrap(),
+            fifo::Value::Accel(_)
+        ));
+        sensor.fifostatus.keep_overrun(1 << 6);
+
+        sensor
+            .reset(&mut sim, &mut NoopDelay::new(), 1_000)
+            .unwrap();
+        assert!(!sensor.fifostatus.take_overrun());
+        assert_eq!(sensor.fifoout.tag_cnt(), 0);
+
+        // The words batched after the reset are not scaled with the full scale from before it.
+        batching(&mut sensor, &mut sim);
+        sensor
+            .ctrl1xl
+            .set_chain_full_scale(&mut sim, ctrl1xl::Fs_Xl::G16)
+            .unwrap();
+        sim.advance_us(50_000);
+        let mut accel = 0;
+        loop {
+            match sensor.fifo_pop(&mut sim).unwrap() {
+                fifo::Value::Accel(a) => {
+                    assert_abs_diff_eq!(a.as_g()[2], 1., epsilon = 0.01);
+                    accel += 1;
+                }
+                fifo::Value::Empty => break,
+                v => panic!("{:?}", v),
+            }
+        }
+        assert_eq!(accel, 5);
+    }
+
+    #[test]
+    fn fifo_tracks_scale_changes_from_apply_and_restore() {
+        let mut sim = sim::Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        let mut config = config::Config::default();
+        config.accel.odr = ctrl1xl::Odr_Xl::Hz104;
+        config.fifo.accel_bdr = fifoctrl::BdrXl::Hz104;
+        config.fifo.mode = fifoctrl::FifoMode::Continuous;
+        config.fifo.odr_change = true;
+        sensor.apply(&mut sim, &config).unwrap();
+        let saved = sensor.dump(&mut sim).unwrap();
+
+        sim.advance_us(50_000);
+        config.accel.scale = ctrl1xl::Fs_Xl::G16;
+        sensor.apply(&mut sim, &config).unwrap();
+        sim.advance_us(50_000);
+        sensor.restore(&mut sim, &saved).unwrap();
+        assert_eq!(sensor.ctrl1xl.chain_full_scale(), ctrl1xl::Fs_Xl::G2);
+        sim.advance_us(50_000);
+
+        let mut accel = 0;
+        let mut changes = 0;
+        loop {
+            match sensor.fifo_pop(&mut sim).unwrap() {
+                fifo::Value::Accel(a) => {
+                    assert_abs_diff_eq!(a.as_g()[2], 1., epsilon = 0.01);
+                    accel += 1;
+                }
+                fifo::Value::ConfigChange(_) => changes += 1,
+                fifo::Value::Empty => break,
+                v => panic!("{:?}", v),
+            }
+        }
+        assert_eq!((accel, changes), (15, 2));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_samples() {
+        let gyro = GyroValue::new(ctrl2g::Fs::Dps125, [1, -2, i16::MAX]);
+        let accel = AccelValue::new(ctrl1xl::Fs_Xl::G16, [i16::MIN, 0, 3]);
+
+        round_trip(gyro);
+        round_trip(accel);
+        round_trip(fifo::Value::Empty);
+        round_trip(fifo::Value::Gyro(gyro));
+        round_trip(fifo::Value::Accel(accel));
+        round_trip(fifo::Value::Timestamp(0x0102_0304));
+        round_trip(fifo::Value::ConfigChange(fifo::ConfigChange::from_msr(&[
+            0x4c, 0x10, 0x11, 0, 0, 0,
+        ])));
+        round_trip(fifo::Value::Gap { lost: Some(42) });
+        round_trip(fifo::Value::Other(0x03, [1, 2, 3, 4, 5, 6]));
+
+        let json = serde_json::to_string(&accel).unwrap();
+        assert_eq!(json, r#"{"range":"G16","count":[-32768,0,3]}"#);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_config() {
+        let mut config = config::Config::default();
+        config.accel.odr = ctrl1xl::Odr_Xl::Hz833;
+        config.gyro.scale = ctrl2g::Fs::Dps4000;
+        config.gyro.filter = filter::GyroFilter::new().high_pass(ctrl7g::Hpm_g::Hpmg65);
+        config.fifo.mode = fifoctrl::FifoMode::ContinuousToFifo;
+        config.fifo.accel_bdr = fifoctrl::BdrXl::Hz6_5;
+        config.fifo.gyro_bdr = fifoctrl::BdrGy::Hz417;
+        config.fifo.watermark = 511;
+        config.int2.fifo_full = true;
+
+        round_trip(config);
+        round_trip(config::Config::default());
+        round_trip(config::ConfigError::GyroBdrAboveOdr);
+    }
 }
@@ -0,0 +1,511 @@
+//! Accelerometer and gyroscope operating modes.
+//!
+//! Both sensors run in high-performance mode by default. Setting `XL_HM_MODE` in `CTRL6_C` (or
+//! `G_HM_MODE` in `CTRL7_G` for the gyroscope) lets the data rate pick the mode instead:
+//!
+//! | Data rate        | Mode             |
+//! |------------------|------------------|
+//! | 1.6 Hz (accel)   | low-power        |
+//! | 12.5 Hz to 52 Hz | low-power        |
+//! | 104 Hz, 208 Hz   | normal           |
+//! | 416 Hz and above | high-performance |
+//!
+//! [`Ism330Dhcx::set_accel_power_mode`] and [`Ism330Dhcx::set_gyro_power_mode`] take the mode and
+//! the data rate together and reject combinations the device cannot run.
+//!
+//! Unlike some parts of the same family, the ISM330DHCX has no accelerometer ultra-low-power mode:
+//! 1.6 Hz in low-power mode is the least it can draw while measuring.
+//!
+//! The currents are typical values from the datasheet consumption tables, meant for power budgets
+//! rather than as guarantees.
+//!
+//! Between bursts of measurements, [`Ism330Dhcx::set_power_state`] parks the sensors in a
+//! [`PowerState`] and brings them back at the data rates they had:
+//!
+//! ```no_run
+//! # use embedded_hal::{delay::DelayNs, i2c::I2c};
+//! # fn run<I2C: I2c>(i2c: &mut I2C, delay: &mut impl DelayNs) -> Result<(), ism330dhcx::Error<I2C::Error>> {
+//! use ism330dhcx::power::PowerState;
+//! use ism330dhcx::Ism330Dhcx;
+//!
+//! let mut sensor = Ism330Dhcx::new(i2c)?;
+//! sensor.set_power_state(i2c, PowerState::GyroSleep)?;
+//! // ...
+//! let settling = sensor.set_power_state(i2c, PowerState::Active)?;
+//! delay.delay_us(settling.time_us);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::config::{accel_odr_hz, gyro_odr_hz, ConfigError};
+use crate::ctrl1xl::Odr_Xl;
+use crate::ctrl2g::Odr;
+use crate::{Error, Ism330Dhcx};
+
+/// Gyroscope turn-on time from power-down, before its first sample.
+pub const GYRO_TURN_ON_US: u32 = 70_000;
+
+/// Accelerometer samples to discard after it turns on, with the LPF1 path.
+pub const ACCEL_DISCARD: u8 = 2;
+
+/// Gyroscope samples to discard after it turns on or wakes up from sleep.
+pub const GYRO_DISCARD: u8 = 2;
+
+/// Operating mode of a sensor.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PowerMode {
+    LowPower,
+    Normal,
+    HighPerformance,
+}
+
+impl PowerMode {
+    /// The mode a sensor runs in at `rate_hz`, `None` when powered down.
+    ///
+    /// `hm_mode` is the state of `XL_HM_MODE` or `G_HM_MODE`, set to leave high-performance mode.
+    pub fn at(rate_hz: f32, hm_mode: bool) -> Option<Self> {
+        if rate_hz == 0. {
+            None
+        } else if !hm_mode || rate_hz > 208. {
+            Some(PowerMode::HighPerformance)
+        } else if rate_hz > 52.5 {
+            Some(PowerMode::Normal)
+        } else {
+            Some(PowerMode::LowPower)
+        }
+    }
+}
+
+/// Power state of the whole device.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PowerState {
+    /// Both sensors off.
+    PowerDown,
+    /// Accelerometer running, gyroscope in sleep mode for a fast wake-up.
+    GyroSleep,
+    /// Both sensors running at their configured data rates.
+    Active,
+}
+
+/// What to wait for after a [`PowerState`] transition before trusting the outputs.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug, defmt::Format)]
+pub struct Settling {
+    /// Time until the last sample to discard has been produced, in µs.
+    pub time_us: u32,
+    /// Accelerometer samples to discard.
+    pub accel_samples: u8,
+    /// Gyroscope samples to discard.
+    pub gyro_samples: u8,
+}
+
+/// Time taken by `samples` samples at `rate_hz`, in µs.
+fn samples_us(samples: u8, rate_hz: f32) -> u32 {
+    if rate_hz == 0. {
+        return 0;
+    }
+    (f32::from(samples) * 1e6 / rate_hz) as u32
+}
+
+/// Typical accelerometer supply current in µA, or why `odr` cannot run in `mode`.
+///
+/// Powered down draws nothing, whatever the mode.
+pub fn accel_current_ua(odr: Odr_Xl, mode: PowerMode) -> Result<u16, ConfigError> {
+    let ua = match (mode, odr) {
+        (_, Odr_Xl::Off) => 0,
+        (PowerMode::LowPower, Odr_Xl::Hz1_6) => 4,
+        (PowerMode::LowPower, Odr_Xl::Hz125) => 12,
+        (PowerMode::LowPower, Odr_Xl::Hz26) => 19,
+        (PowerMode::LowPower, Odr_Xl::Hz52) => 35,
+        (PowerMode::Normal, Odr_Xl::Hz104) => 65,
+        (PowerMode::Normal, Odr_Xl::Hz208) => 120,
+        (PowerMode::HighPerformance, Odr_Xl::Hz1_6) => return Err(ConfigError::AccelModeOdr),
+        (PowerMode::HighPerformance, _) => 600,
+        _ => return Err(ConfigError::AccelModeOdr),
+    };
+
+    Ok(ua)
+}
+
+/// Typical gyroscope supply current in µA, or why `odr` cannot run in `mode`.
+///
+/// Powered down draws nothing, whatever the mode.
+pub fn gyro_current_ua(odr: Odr, mode: PowerMode) -> Result<u16, ConfigError> {
+    let ua = match (mode, odr) {
+        (_, Odr::Off) => 0,
+        (PowerMode::LowPower, Odr::Hz125) => 290,
+        (PowerMode::LowPower, Odr::Hz26) => 350,
+        (PowerMode::LowPower, Odr::Hz52) => 420,
+        (PowerMode::Normal, Odr::Hz104) => 550,
+        (PowerMode::Normal, Odr::Hz208) => 750,
+        (PowerMode::HighPerformance, _) => 900,
+        _ => return Err(ConfigError::GyroModeOdr),
+    };
+
+    Ok(ua)
+}
+
+impl Ism330Dhcx {
+    /// Set the accelerometer data rate together with the operating mode it should run in.
+    ///
+    /// Nothing is written if the data rate is not available in `mode`.
+    pub fn set_accel_power_mode<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        odr: Odr_Xl,
+        mode: PowerMode,
+    ) -> Result<(), Error<I2C::Error>>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        accel_current_ua(odr, mode).map_err(Error::InvalidConfig)?;
+
+        self.ctrl6c
+            .set_xl_hm_mode(i2c, mode != PowerMode::HighPerformance)?;
+        self.ctrl1xl.set_accelerometer_data_rate(i2c, odr)?;
+        Ok(())
+    }
+
+    /// Set the gyroscope data rate together with the operating mode it should run in.
+    ///
+    /// Nothing is written if the data rate is not available in `mode`.
+    pub fn set_gyro_power_mode<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        odr: Odr,
+        mode: PowerMode,
+    ) -> Result<(), Error<I2C::Error>>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        gyro_current_ua(odr, mode).map_err(Error::InvalidConfig)?;
+
+        self.ctrl7g
+            .set_g_hm_mode(i2c, mode != PowerMode::HighPerformance)?;
+        self.ctrl2g.set_gyroscope_data_rate(i2c, odr)?;
+        Ok(())
+    }
+
+    /// Accelerometer data rate in effect, from the cached configuration, see
+    /// [`Ctrl1Xl::effective_odr`](crate::ctrl1xl::Ctrl1Xl::effective_odr).
+    pub fn accel_odr(&self) -> Odr_Xl {
+        self.ctrl1xl.effective_odr(self.ctrl6c.xl_hm_mode())
+    }
+
+    /// Accelerometer data rate in effect in Hz, see [`Ism330Dhcx::accel_odr`].
+    pub fn accel_data_rate_hz(&self) -> f32 {
+        accel_odr_hz(self.accel_odr())
+    }
+
+    /// Current accelerometer operating mode, `None` when powered down.
+    pub fn accel_power_mode(&self) -> Option<PowerMode> {
+        PowerMode::at(self.accel_data_rate_hz(), self.ctrl6c.xl_hm_mode())
+    }
+
+    /// Current gyroscope operating mode, `None` when powered down.
+    pub fn gyro_power_mode(&self) -> Option<PowerMode> {
+        let odr = self.ctrl2g.gyroscope_odr();
+        PowerMode::at(gyro_odr_hz(odr), self.ctrl7g.g_hm_mode())
+    }
+
+    /// Typical supply current of both sensors in µA, from the cached configuration.
+    pub fn current_ua(&self) -> u16 {
+        let accel = self.accel_power_mode().map_or(0, |mode| {
+            accel_current_ua(self.accel_odr(), mode).unwrap_or(0)
+        });
+        let gyro = self.gyro_power_mode().map_or(0, |mode| {
+            gyro_current_ua(self.ctrl2g.gyroscope_odr(), mode).unwrap_or(0)
+        });
+
+        accel + gyro
+    }
+
+    /// Current power state, from the cached configuration.
+    pub fn power_state(&self) -> PowerState {
+        let accel_on = self.ctrl1xl.accelerometer_odr() != Odr_Xl::Off;
+        let gyro_on = self.ctrl2g.gyroscope_odr() != Odr::Off;
+
+        if !accel_on && !gyro_on {
+            PowerState::PowerDown
+        } else if gyro_on && self.ctrl4c.sleep_g() {
+            PowerState::GyroSleep
+        } else {
+            PowerState::Active
+        }
+    }
+
+    /// Move to `state`, returning how long the sensors that started take to settle.
+    ///
+    /// Powering down remembers the data rates in [`crate::ctrl1xl::Ctrl1Xl`] and
+    /// [`crate::ctrl2g::Ctrl2G`], and the other states restore them. The gyroscope is stopped
+    /// before the accelerometer and started after it.
+    pub fn set_power_state<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        state: PowerState,
+    ) -> Result<Settling, I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let accel_was_on = self.ctrl1xl.accelerometer_odr() != Odr_Xl::Off;
+        let gyro_was_on = self.ctrl2g.gyroscope_odr() != Odr::Off;
+        let gyro_was_running = gyro_was_on && !self.ctrl4c.sleep_g();
+
+        let sleep = state == PowerState::GyroSleep;
+        match state {
+            PowerState::PowerDown => {
+                self.ctrl2g.park(i2c)?;
+                if self.ctrl4c.sleep_g() {
+                    self.ctrl4c.set_sleep_g(i2c, false)?;
+                }
+                self.ctrl1xl.park(i2c)?;
+            }
+            PowerState::GyroSleep | PowerState::Active => {
+                self.ctrl1xl.resume(i2c)?;
+                if self.ctrl4c.sleep_g() != sleep {
+                    self.ctrl4c.set_sleep_g(i2c, sleep)?;
+                }
+                self.ctrl2g.resume(i2c)?;
+            }
+        }
+
+        let mut settling = Settling::default();
+
+        let accel_rate = self.accel_data_rate_hz();
+        if !accel_was_on && accel_rate > 0. {
+            settling.accel_samples = ACCEL_DISCARD;
+            settling.time_us = samples_us(ACCEL_DISCARD, accel_rate);
+        }
+
+        let gyro_rate = self.ctrl2g.gyroscope_data_rate();
+        if !gyro_was_running && gyro_rate > 0. && !sleep {
+            let turn_on = if gyro_was_on { 0 } else { GYRO_TURN_ON_US };
+            settling.gyro_samples = GYRO_DISCARD;
+            settling.time_us = settling
+                .time_us
+                .max(turn_on + samples_us(GYRO_DISCARD, gyro_rate));
+        }
+
+        Ok(settling)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::Simulator;
+    use crate::{ctrl1xl, ctrl2g, ctrl4c, ctrl6c, ctrl7g};
+
+    #[test]
+    fn mode_from_rate() {
+        assert_eq!(PowerMode::at(0., true), None);
+        assert_eq!(PowerMode::at(1.6, true), Some(PowerMode::LowPower));
+        assert_eq!(PowerMode::at(52., true), Some(PowerMode::LowPower));
+        assert_eq!(PowerMode::at(104., true), Some(PowerMode::Normal));
+        assert_eq!(PowerMode::at(208., true), Some(PowerMode::Normal));
+        assert_eq!(PowerMode::at(416., true), Some(PowerMode::HighPerformance));
+        assert_eq!(PowerMode::at(26., false), Some(PowerMode::HighPerformance));
+    }
+
+    #[test]
+    fn combinations() {
+        assert_eq!(accel_current_ua(Odr_Xl::Hz1_6, PowerMode::LowPower), Ok(4));
+        assert_eq!(
+            accel_current_ua(Odr_Xl::Hz1_6, PowerMode::HighPerformance),
+            Err(ConfigError::AccelModeOdr)
+        );
+        assert_eq!(
+            accel_current_ua(Odr_Xl::Hz104, PowerMode::LowPower),
+            Err(ConfigError::AccelModeOdr)
+        );
+        assert_eq!(
+            accel_current_ua(Odr_Xl::Hz833, PowerMode::Normal),
+            Err(ConfigError::AccelModeOdr)
+        );
+        assert_eq!(accel_current_ua(Odr_Xl::Off, PowerMode::Normal), Ok(0));
+
+        assert_eq!(
+            gyro_current_ua(Odr::Hz208, PowerMode::LowPower),
+            Err(ConfigError::GyroModeOdr)
+        );
+        assert_eq!(gyro_current_ua(Odr::Hz52, PowerMode::LowPower), Ok(420));
+        assert_eq!(
+            gyro_current_ua(Odr::Hz52, PowerMode::HighPerformance),
+            Ok(900)
+        );
+    }
+
+    #[test]
+    fn set_power_modes() {
+        let mut sim = Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        assert_eq!(sensor.accel_power_mode(), None);
+        assert_eq!(sensor.current_ua(), 0);
+
+        sensor
+            .set_accel_power_mode(&mut sim, Odr_Xl::Hz1_6, PowerMode::LowPower)
+            .unwrap();
+        sensor
+            .set_gyro_power_mode(&mut sim, Odr::Hz104, PowerMode::Normal)
+            .unwrap();
+        assert_eq!(sim.register(ctrl6c::ADDR), 1 << ctrl6c::XL_HM_MODE);
+        assert_eq!(sim.register(ctrl7g::ADDR), 1 << ctrl7g::G_HM_MODE);
+        assert_eq!(sim.register(ctrl1xl::ADDR), 0b1011 << 4);
+        assert_eq!(sensor.accel_power_mode(), Some(PowerMode::LowPower));
+        assert_eq!(sensor.gyro_power_mode(), Some(PowerMode::Normal));
+        assert_eq!(sensor.current_ua(), 4 + 550);
+
+        // Samples come at 1.6 Hz.
+        sim.advance_us(1_000_000);
+        assert!(sensor.data_ready(&mut sim).unwrap().accel);
+
+        assert!(matches!(
+            sensor.set_gyro_power_mode(&mut sim, Odr::Hz833, PowerMode::LowPower),
+            Err(Error::InvalidConfig(ConfigError::GyroModeOdr))
+        ));
+        assert_eq!(sensor.gyro_power_mode(), Some(PowerMode::Normal));
+
+        sensor
+            .set_accel_power_mode(&mut sim, Odr_Xl::Hz833, PowerMode::HighPerformance)
+            .unwrap();
+        assert_eq!(sim.register(ctrl6c::ADDR), 0);
+        assert_eq!(sensor.current_ua(), 600 + 550);
+    }
+
+    #[test]
+    fn power_states() {
+        let mut sim = Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, Odr_Xl::Hz104)
+            .unwrap();
+        sensor
+            .ctrl2g
+            .set_gyroscope_data_rate(&mut sim, Odr::Hz208)
+            .unwrap();
+        assert_eq!(sensor.power_state(), PowerState::Active);
+
+        let settling = sensor
+            .set_power_state(&mut sim, PowerState::PowerDown)
+            .unwrap();
+        assert_eq!(settling, Settling::default());
+        assert_eq!(sensor.power_state(), PowerState::PowerDown);
+        assert_eq!(sim.register(ctrl1xl::ADDR) >> 4, 0);
+        assert_eq!(sim.register(ctrl2g::ADDR) >> 4, 0);
+        assert_eq!(sensor.ctrl1xl.parked_odr(), Odr_Xl::Hz104);
+        assert_eq!(sensor.ctrl2g.parked_odr(), Odr::Hz208);
+        assert_eq!(sensor.current_ua(), 0);
+
+        // Only the accelerometer produces samples in gyroscope sleep.
+        let settling = sensor
+            .set_power_state(&mut sim, PowerState::GyroSleep)
+            .unwrap();
+        assert_eq!(sensor.power_state(), PowerState::GyroSleep);
+        assert_eq!(sim.register(ctrl4c::ADDR), 1 << ctrl4c::SLEEP_G);
+        assert_eq!(sensor.ctrl2g.gyroscope_odr(), Odr::Hz208);
+        assert_eq!(
+            settling,
+            Settling {
+                time_us: 19_230,
+                accel_samples: ACCEL_DISCARD,
+                gyro_samples: 0,
+            }
+        );
+
+        // Waking up from sleep skips the turn-on time.
+        let settling = sensor
+            .set_power_state(&mut sim, PowerState::Active)
+            .unwrap();
+        assert_eq!(sim.register(ctrl4c::ADDR), 0);
+        assert_eq!(settling.gyro_samples, GYRO_DISCARD);
+        assert_eq!(settling.time_us, 9_615);
+
+        sensor
+            .set_power_state(&mut sim, PowerState::PowerDown)
+            .unwrap();
+        let settling = sensor
+            .set_power_state(&mut sim, PowerState::Active)
+            .unwrap();
+        assert_eq!(settling.time_us, GYRO_TURN_ON_US + 9_615);
+        assert_eq!(sensor.ctrl1xl.parked_odr(), Odr_Xl::Off);
+        assert_eq!(sensor.ctrl1xl.accelerometer_odr(), Odr_Xl::Hz104);
+        assert_eq!(sim.register(ctrl2g::ADDR) >> 4, 5);
+    }
+
+    #[test]
+    fn parked_across_sync() {
+        let mut sim = Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, Odr_Xl::Hz104)
+            .unwrap();
+        sensor
+            .ctrl2g
+            .set_gyroscope_data_rate(&mut sim, Odr::Hz208)
+            .unwrap();
+
+        sensor
+            .set_power_state(&mut sim, PowerState::PowerDown)
+            .unwrap();
+        sensor.sync(&mut sim).unwrap();
+        assert_eq!(sensor.ctrl1xl.parked_odr(), Odr_Xl::Hz104);
+        assert_eq!(sensor.ctrl2g.parked_odr(), Odr::Hz208);
+
+        sensor
+            .set_power_state(&mut sim, PowerState::Active)
+            .unwrap();
+        assert_eq!(sim.register(ctrl1xl::ADDR) >> 4, 4);
+        assert_eq!(sim.register(ctrl2g::ADDR) >> 4, 5);
+
+        // A data rate set behind the driver's back replaces the parked one.
+        sensor
+            .set_power_state(&mut sim, PowerState::PowerDown)
+            .unwrap();
+        embedded_hal::i2c::I2c::write(
+            &mut sim,
+            crate::DEFAULT_I2C_ADDRESS,
+            &[ctrl1xl::ADDR, 0b0011_0000],
+        )
+        .unwrap();
+        sensor.sync(&mut sim).unwrap();
+        assert_eq!(sensor.ctrl1xl.parked_odr(), Odr_Xl::Off);
+        assert_eq!(sensor.ctrl2g.parked_odr(), Odr::Hz208);
+    }
+
+    #[test]
+    fn low_power_code_in_high_performance() {
+        assert!(Odr_Xl::Off < Odr_Xl::Hz1_6 && Odr_Xl::Hz1_6 < Odr_Xl::Hz125);
+        assert!(Odr_Xl::Hz125 < Odr_Xl::Hz6667);
+
+        let mut sim = Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, Odr_Xl::Hz1_6)
+            .unwrap();
+
+        // XL_HM_MODE is clear, so the 1.6 Hz code runs at 12.5 Hz.
+        assert_eq!(sensor.accel_odr(), Odr_Xl::Hz125);
+        assert_eq!(sensor.accel_data_rate_hz(), 12.5);
+        assert_eq!(sensor.accel_power_mode(), Some(PowerMode::HighPerformance));
+        assert_eq!(sensor.current_ua(), 600);
+        assert_eq!(sensor.accel_bandwidth_hz(), 12.5 / 2.);
+        assert_eq!(
+            sensor.read_config(&mut sim).unwrap().accel.odr,
+            Odr_Xl::Hz125
+        );
+
+        sensor
+            .set_accel_power_mode(&mut sim, Odr_Xl::Hz1_6, PowerMode::LowPower)
+            .unwrap();
+        assert_eq!(sensor.accel_odr(), Odr_Xl::Hz1_6);
+        assert_eq!(sensor.accel_data_rate_hz(), 1.6);
+        assert_eq!(
+            sensor.read_config(&mut sim).unwrap().accel.odr,
+            Odr_Xl::Hz1_6
+        );
+    }
+}
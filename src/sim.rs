@@ -0,0 +1,1085 @@
+//! A behavioural model of the ISM330DHCX for testing without hardware.
+//!
+//! [`Simulator`] implements both [`embedded_hal::i2c::I2c`] and [`embedded_hal::spi::SpiDevice`], so it
+//! can be handed to the driver in place of a real bus. Unlike a transaction list mock it does not care
+//! about the order or grouping of bus accesses, it only models what the chip would do with them:
+//!
+//! - a register file for the main, embedded functions and sensor hub pages, selected through
+//!   `FUNC_CFG_ACCESS`
+//! - register address auto-increment (`IF_INC`) and block data update (`BDU`)
+//! - `WHO_AM_I`, software reset and reboot
+//! - accelerometer, gyroscope and temperature outputs driven by configurable signals and the
+//!   configured ODRs and full scales, with data-ready flags in `STATUS_REG`
+//! - the timestamp counter
+//! - a FIFO that fills at the configured batch data rates in bypass, FIFO and continuous mode
+//! - the batch data rate counter
+//!
+//! Time only moves when [`Simulator::advance_us`] is called.
+//!
+//! ```
+//! use ism330dhcx::sim::Simulator;
+//! use ism330dhcx::{ctrl2g, Ism330Dhcx};
+//!
+//! let mut sim = Simulator::new();
+//! sim.set_gyroscope_signal(|_| [0., 0., 90.]);
+//!
+//! let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+//! sensor.ctrl2g.set_gyroscope_data_rate(&mut sim, ctrl2g::Odr::Hz104).unwrap();
+//!
+//! sim.advance_us(20_000);
+//! let gyro = sensor.get_gyroscope(&mut sim).unwrap();
+//! ```
+
+use core::convert::Infallible;
+
+use embedded_hal::i2c::{self, ErrorKind, NoAcknowledgeSource};
+use embedded_hal::spi;
+
+use crate::fifoctrl::FIFO_SIZE;
+
+/// Value of the `WHO_AM_I` register.
+pub const WHO_AM_I: u8 = 0x6b;
+
+/// A three axis signal, evaluated at the simulated time in seconds.
+///
+/// Accelerometer signals are in g, gyroscope signals in dps.
+pub type Signal = fn(f32) -> [f32; 3];
+
+/// The temperature signal in °C, evaluated at the simulated time in seconds.
+pub type TemperatureSignal = fn(f32) -> f32;
+
+const FUNC_CFG_ACCESS: u8 = 0x01;
+const PIN_CTRL: u8 = 0x02;
+const FIFO_CTRL1: u8 = 0x07;
+const FIFO_CTRL2: u8 = 0x08;
+const FIFO_CTRL3: u8 = 0x09;
+const FIFO_CTRL4: u8 = 0x0a;
+const WHO_AM_I_ADDR: u8 = 0x0f;
+const CTRL1_XL: u8 = 0x10;
+const CTRL2_G: u8 = 0x11;
+const CTRL3_C: u8 = 0x12;
+const CTRL6_C: u8 = 0x15;
+const CTRL9_XL: u8 = 0x18;
+const CTRL10_C: u8 = 0x19;
+const STATUS_REG: u8 = 0x1e;
+const OUT_TEMP_L: u8 = 0x20;
+const OUTX_L_G: u8 = 0x22;
+const OUTX_L_A: u8 = 0x28;
+const OUTZ_H_A: u8 = 0x2d;
+const COUNTER_BDR_REG1: u8 = 0x0b;
+const FIFO_STATUS1: u8 = 0x3a;
+const FIFO_STATUS2: u8 = 0x3b;
+const TIMESTAMP0: u8 = 0x40;
+const TIMESTAMP2: u8 = 0x42;
+const TIMESTAMP3: u8 = 0x43;
+const FIFO_DATA_OUT_TAG: u8 = 0x78;
+const FIFO_DATA_OUT_Z_H: u8 = 0x7e;
+
+const TAG_GYROSCOPE: u8 = 0x01;
+const TAG_ACCELEROMETER: u8 = 0x02;
+const TAG_TEMPERATURE: u8 = 0x03;
+const TAG_TIMESTAMP: u8 = 0x04;
+const TAG_CFG_CHANGE: u8 = 0x05;
+
+/// Duration of one timestamp LSB.
+const TIMESTAMP_LSB_NS: u64 = 25_000;
+
+/// Output data rate of the temperature sensor.
+const TEMPERATURE_ODR: f32 = 52.;
+
+const PAGE_MAIN: usize = 0;
+const PAGE_EMBEDDED: usize = 1;
+const PAGE_SENSOR_HUB: usize = 2;
+
+/// Clocks driving the model, all of them are scheduled independently.
+const CLK_ODR_XL: usize = 0;
+const CLK_ODR_G: usize = 1;
+const CLK_ODR_T: usize = 2;
+const CLK_BDR_XL: usize = 3;
+const CLK_BDR_G: usize = 4;
+const CLK_BDR_T: usize = 5;
+const CLOCKS: usize = 6;
+
+/// Output registers protected by `BDU`, as LSB/MSB pairs from `OUT_TEMP_L` to `OUTZ_H_A`.
+const BDU_PAIRS: usize = 7;
+
+/// Simulated ISM330DHCX.
+pub struct Simulator {
+    address: u8,
+    pages: [[u8; 128]; 3],
+    pointer: u8,
+    now_ns: u64,
+    timestamp_origin_ns: u64,
+    clocks: [Option<u64>; CLOCKS],
+    slot_ns: Option<u64>,
+    tag_cnt: u8,
+    ts_decimation: u8,
+    accel: Signal,
+    gyro: Signal,
+    temperature: TemperatureSignal,
+    latest_xl: [u8; 6],
+    latest_g: [u8; 6],
+    latest_t: [u8; 2],
+    bdu_locked: [bool; BDU_PAIRS],
+    bdu_pending: [Option<[u8; 2]>; BDU_PAIRS],
+    fifo: [[u8; 7]; FIFO_SIZE as usize],
+    fifo_head: usize,
+    fifo_len: usize,
+    fifo_overrun: bool,
+    fifo_overrun_latched: bool,
+    bdr_counter: u16,
+    counter_bdr_ia: bool,
+    spi_command: Option<bool>,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simulator {
+    /// A device answering on [`crate::DEFAULT_I2C_ADDRESS`], at rest and level.
+    pub fn new() -> Self {
+        Self::with_address(crate::DEFAULT_I2C_ADDRESS)
+    }
+
+    /// A device answering on `address`.
+    pub fn with_address(address: u8) -> Self {
+        let mut sim = Simulator {
+            address,
+            pages: [[0; 128]; 3],
+            pointer: 0,
+            now_ns: 0,
+            timestamp_origin_ns: 0,
+            clocks: [None; CLOCKS],
+            slot_ns: None,
+            tag_cnt: 0,
+            ts_decimation: 0,
+            accel: |_| [0., 0., 1.],
+            gyro: |_| [0., 0., 0.],
+            temperature: |_| 25.,
+            latest_xl: [0; 6],
+            latest_g: [0; 6],
+            latest_t: [0; 2],
+            bdu_locked: [false; BDU_PAIRS],
+            bdu_pending: [None; BDU_PAIRS],
+            fifo: [[0; 7]; FIFO_SIZE as usize],
+            fifo_head: 0,
+            fifo_len: 0,
+            fifo_overrun: false,
+            fifo_overrun_latched: false,
+            bdr_counter: 0,
+            counter_bdr_ia: false,
+            spi_command: None,
+        };
+        sim.reset();
+        sim
+    }
+
+    /// Set the accelerometer signal, in g.
+    pub fn set_accelerometer_signal(&mut self, signal: Signal) {
+        self.accel = signal;
+    }
+
+    /// Set the gyroscope signal, in dps.
+    pub fn set_gyroscope_signal(&mut self, signal: Signal) {
+        self.gyro = signal;
+    }
+
+    /// Set the temperature signal, in °C.
+    pub fn set_temperature_signal(&mut self, signal: TemperatureSignal) {
+        self.temperature = signal;
+    }
+
+    /// Simulated time since creation in microseconds.
+    pub fn elapsed_us(&self) -> u64 {
+        self.now_ns / 1000
+    }
+
+    /// Peek at a register of the main page without any read side effects.
+    pub fn register(&self, reg: u8) -> u8 {
+        self.pages[PAGE_MAIN][(reg & 0x7f) as usize]
+    }
+
+    /// Number of words currently stored in the FIFO.
+    pub fn fifo_level(&self) -> usize {
+        self.fifo_len
+    }
+
+    /// Let `us` microseconds pass, producing samples and batching them as configured.
+    pub fn advance_us(&mut self, us: u64) {
+        let target = self.now_ns + us * 1000;
+
+        loop {
+            let mut next = None;
+            for clock in 0..CLOCKS {
+                if let Some(due) = self.schedule(clock) {
+                    next = Some(next.map_or(due, |n: u64| n.min(due)));
+                }
+            }
+
+            let Some(now) = next.filter(|n| *n <= target) else {
+                break;
+            };
+            self.now_ns = now;
+
+            for clock in 0..CLOCKS {
+                if self.clocks[clock] == Some(now) {
+                    self.tick(clock);
+                    let period = period_ns(self.clock_rate(clock));
+                    self.clocks[clock] = Some(now + period);
+                }
+            }
+        }
+
+        self.now_ns = target;
+    }
+
+    fn schedule(&mut self, clock: usize) -> Option<u64> {
+        let rate = self.clock_rate(clock);
+        if rate <= 0. {
+            self.clocks[clock] = None;
+            return None;
+        }
+
+        let now = self.now_ns;
+        Some(*self.clocks[clock].get_or_insert(now + period_ns(rate)))
+    }
+
+    fn clock_rate(&self, clock: usize) -> f32 {
+        let odr_xl = self.accelerometer_odr();
+        let odr_g = self.gyroscope_odr();
+        let fifo_ctrl3 = self.register(FIFO_CTRL3);
+
+        match clock {
+            CLK_ODR_XL => odr_xl,
+            CLK_ODR_G => odr_g,
+            CLK_ODR_T if odr_xl > 0. || odr_g > 0. => TEMPERATURE_ODR,
+            CLK_BDR_XL if odr_xl > 0. && self.batching() => bdr(fifo_ctrl3 & 0xf),
+            CLK_BDR_G if odr_g > 0. && self.batching() => bdr(fifo_ctrl3 >> 4),
+            CLK_BDR_T if (odr_xl > 0. || odr_g > 0.) && self.batching() => {
+                match (self.register(FIFO_CTRL4) >> 4) & 0b11 {
+                    0b01 => 1.6,
+                    0b10 => 12.5,
+                    0b11 => 52.,
+                    _ => 0.,
+                }
+            }
+            _ => 0.,
+        }
+    }
+
+    fn accelerometer_odr(&self) -> f32 {
+        let low_power = self.register(CTRL6_C) & (1 << 4) != 0;
+        match self.register(CTRL1_XL) >> 4 {
+            0b1011 if low_power => 1.6,
+            0b1011 => 12.5,
+            odr => odr_hz(odr),
+        }
+    }
+
+    fn gyroscope_odr(&self) -> f32 {
+        odr_hz(self.register(CTRL2_G) >> 4)
+    }
+
+    /// The FIFO is collecting data in the current mode.
+    fn batching(&self) -> bool {
+        matches!(self.register(FIFO_CTRL4) & 0b111, 0b001 | 0b011 | 0b110)
+    }
+
+    fn tick(&mut self, clock: usize) {
+        let t = self.now_ns as f32 / 1e9;
+
+        match clock {
+            CLK_ODR_XL => {
+                let sensitivity = match (self.register(CTRL1_XL) >> 2) & 0b11 {
+                    0b00 => 0.061,
+                    0b01 => 0.488,
+                    0b10 => 0.122,
+                    _ => 0.244,
+                };
+                self.latest_xl = encode_axes((self.accel)(t), sensitivity);
+                self.store_output(OUTX_L_A, self.latest_xl);
+                self.pages[PAGE_MAIN][STATUS_REG as usize] |= 1 << 0;
+            }
+            CLK_ODR_G => {
+                let ctrl2_g = self.register(CTRL2_G);
+                let sensitivity = if ctrl2_g & 1 != 0 {
+                    140.
+                } else if ctrl2_g & 2 != 0 {
+                    4.375
+                } else {
+                    match (ctrl2_g >> 2) & 0b11 {
+                        0b00 => 8.75,
+                        0b01 => 17.5,
+                        0b10 => 35.,
+                        _ => 70.,
+                    }
+                };
+                self.latest_g = encode_axes((self.gyro)(t), sensitivity);
+                self.store_output(OUTX_L_G, self.latest_g);
+                self.pages[PAGE_MAIN][STATUS_REG as usize] |= 1 << 1;
+            }
+            CLK_ODR_T => {
+                let raw = saturate(((self.temperature)(t) - 25.) * 256.);
+                self.latest_t = raw.to_le_bytes();
+                self.store_output(OUT_TEMP_L, self.latest_t);
+                self.pages[PAGE_MAIN][STATUS_REG as usize] |= 1 << 2;
+            }
+            CLK_BDR_XL => self.batch(TAG_ACCELEROMETER, self.latest_xl),
+            CLK_BDR_G => self.batch(TAG_GYROSCOPE, self.latest_g),
+            CLK_BDR_T => {
+                let t = self.latest_t;
+                self.batch(TAG_TEMPERATURE, [t[0], t[1], 0, 0, 0, 0])
+            }
+            _ => {}
+        }
+    }
+
+    /// Update output registers, holding back pairs locked by `BDU`.
+    fn store_output<const N: usize>(&mut self, reg: u8, data: [u8; N]) {
+        for (i, pair) in data.chunks(2).enumerate() {
+            let reg = reg + 2 * i as u8;
+            let index = ((reg - OUT_TEMP_L) / 2) as usize;
+
+            if self.bdu() && self.bdu_locked[index] {
+                self.bdu_pending[index] = Some([pair[0], pair[1]]);
+            } else {
+                self.pages[PAGE_MAIN][reg as usize] = pair[0];
+                self.pages[PAGE_MAIN][reg as usize + 1] = pair[1];
+            }
+        }
+    }
+
+    fn bdu(&self) -> bool {
+        self.register(CTRL3_C) & (1 << 6) != 0
+    }
+
+    fn timestamp(&self) -> u32 {
+        ((self.now_ns - self.timestamp_origin_ns) / TIMESTAMP_LSB_NS) as u32
+    }
+
+    fn timestamp_enabled(&self) -> bool {
+        self.register(CTRL10_C) & (1 << 5) != 0
+    }
+
+    /// Store a sample in the FIFO, opening a new time slot if needed.
+    fn batch(&mut self, tag: u8, data: [u8; 6]) {
+        if self.slot_ns != Some(self.now_ns) {
+            if self.slot_ns.is_some() {
+                self.tag_cnt = (self.tag_cnt + 1) & 0b11;
+            }
+            self.slot_ns = Some(self.now_ns);
+
+            let decimation = match self.register(FIFO_CTRL4) >> 6 {
+                0b01 => 1,
+                0b10 => 8,
+                0b11 => 32,
+                _ => 0,
+            };
+            if decimation > 0 && self.timestamp_enabled() {
+                if self.ts_decimation == 0 {
+                    let ts = self.timestamp().to_le_bytes();
+                    self.push(TAG_TIMESTAMP, [ts[0], ts[1], ts[2], ts[3], 0, 0]);
+                }
+                self.ts_decimation = (self.ts_decimation + 1) % decimation;
+            }
+        }
+
+        self.push(tag, data);
+        self.count_batch_event(tag);
+    }
+
+    /// Count a batch event for the BDR counter, raising `COUNTER_BDR_IA` at the threshold.
+    fn count_batch_event(&mut self, tag: u8) {
+        let reg1 = self.register(COUNTER_BDR_REG1);
+        let trigger = match reg1 & (1 << 5) != 0 {
+            true => TAG_GYROSCOPE,
+            false => TAG_ACCELEROMETER,
+        };
+        let threshold =
+            u16::from(reg1 & 0b111) << 8 | u16::from(self.register(COUNTER_BDR_REG1 + 1));
+        if tag != trigger || threshold == 0 {
+            return;
+        }
+
+        self.bdr_counter += 1;
+        if self.bdr_counter >= threshold {
+            self.bdr_counter = 0;
+            self.counter_bdr_ia = true;
+        }
+    }
+
+    fn fifo_capacity(&self) -> usize {
+        let watermark = self.watermark() as usize;
+        if self.register(FIFO_CTRL2) & (1 << 7) != 0 && watermark > 0 {
+            watermark.min(FIFO_SIZE as usize)
+        } else {
+            FIFO_SIZE as usize
+        }
+    }
+
+    fn watermark(&self) -> u16 {
+        u16::from(self.register(FIFO_CTRL1)) | u16::from(self.register(FIFO_CTRL2) & 1) << 8
+    }
+
+    fn push(&mut self, tag: u8, data: [u8; 6]) {
+        let mut word = [0u8; 7];
+        word[0] = tag << 3 | self.tag_cnt << 1;
+        word[1..].copy_from_slice(&data);
+
+        if self.fifo_len >= self.fifo_capacity() {
+            // Only continuous mode keeps going by discarding the oldest word.
+            if self.register(FIFO_CTRL4) & 0b111 == 0b001 {
+                return;
+            }
+            self.fifo_head = (self.fifo_head + 1) % self.fifo.len();
+            self.fifo_len -= 1;
+            self.fifo_overrun = true;
+            self.fifo_overrun_latched = true;
+        }
+
+        let tail = (self.fifo_head + self.fifo_len) % self.fifo.len();
+        self.fifo[tail] = word;
+        self.fifo_len += 1;
+    }
+
+    fn pop(&mut self) -> [u8; 7] {
+        if self.fifo_len == 0 {
+            return [0; 7];
+        }
+
+        let word = self.fifo[self.fifo_head];
+        self.fifo_head = (self.fifo_head + 1) % self.fifo.len();
+        self.fifo_len -= 1;
+        self.fifo_overrun = false;
+        word
+    }
+
+    fn clear_fifo(&mut self) {
+        self.fifo_head = 0;
+        self.fifo_len = 0;
+        self.fifo_overrun = false;
+        self.fifo_overrun_latched = false;
+        self.slot_ns = None;
+        self.tag_cnt = 0;
+        self.ts_decimation = 0;
+    }
+
+    /// Restore every register to its power-on value.
+    fn reset(&mut self) {
+        self.pages = [[0; 128]; 3];
+        self.pages[PAGE_MAIN][PIN_CTRL as usize] = 0x3f;
+        self.pages[PAGE_MAIN][WHO_AM_I_ADDR as usize] = WHO_AM_I;
+        self.pages[PAGE_MAIN][CTRL3_C as usize] = 1 << 2;
+        self.pages[PAGE_MAIN][CTRL9_XL as usize] = 0b1110_0000;
+
+        self.clocks = [None; CLOCKS];
+        self.bdu_locked = [false; BDU_PAIRS];
+        self.bdu_pending = [None; BDU_PAIRS];
+        self.latest_xl = [0; 6];
+        self.latest_g = [0; 6];
+        self.latest_t = [0; 2];
+        self.timestamp_origin_ns = self.now_ns;
+        self.bdr_counter = 0;
+        self.counter_bdr_ia = false;
+        self.clear_fifo();
+    }
+
+    fn page(&self, reg: u8) -> usize {
+        let access = self.register(FUNC_CFG_ACCESS);
+        if reg == FUNC_CFG_ACCESS {
+            PAGE_MAIN
+        } else if access & (1 << 7) != 0 {
+            PAGE_EMBEDDED
+        } else if access & (1 << 6) != 0 {
+            PAGE_SENSOR_HUB
+        } else {
+            PAGE_MAIN
+        }
+    }
+
+    /// Read a register, applying the side effects of the read.
+    fn read_register(&mut self, reg: u8) -> u8 {
+        let page = self.page(reg);
+        if page != PAGE_MAIN {
+            return self.pages[page][reg as usize];
+        }
+
+        match reg {
+            STATUS_REG..=0x1f => self.register(reg),
+            OUT_TEMP_L..=OUTZ_H_A => {
+                let status = &mut self.pages[PAGE_MAIN][STATUS_REG as usize];
+                match reg {
+                    OUT_TEMP_L..=0x21 => *status &= !(1 << 2),
+                    OUTX_L_G..=0x27 => *status &= !(1 << 1),
+                    _ => *status &= !(1 << 0),
+                }
+
+                let value = self.register(reg);
+                if self.bdu() {
+                    let index = ((reg - OUT_TEMP_L) / 2) as usize;
+                    if reg & 1 == 0 {
+                        self.bdu_locked[index] = true;
+                    } else {
+                        self.bdu_locked[index] = false;
+                        if let Some(pair) = self.bdu_pending[index].take() {
+                            let lsb = (reg - 1) as usize;
+                            self.pages[PAGE_MAIN][lsb..=lsb + 1].copy_from_slice(&pair);
+                        }
+                    }
+                }
+                value
+            }
+            FIFO_STATUS1 => self.fifo_len as u8,
+            FIFO_STATUS2 => {
+                let mut value = (self.fifo_len >> 8) as u8 & 0b11;
+                if self.watermark() > 0 && self.fifo_len >= self.watermark() as usize {
+                    value |= 1 << 7;
+                }
+                if self.fifo_overrun {
+                    value |= 1 << 6;
+                }
+                if self.fifo_len + 1 >= self.fifo_capacity() {
+                    value |= 1 << 5;
+                }
+                if self.counter_bdr_ia {
+                    value |= 1 << 4;
+                }
+                if self.fifo_overrun_latched {
+                    value |= 1 << 3;
+                }
+                self.counter_bdr_ia = false;
+                self.fifo_overrun_latched = false;
+                value
+            }
+            TIMESTAMP0..=TIMESTAMP3 => {
+                if !self.timestamp_enabled() {
+                    return 0;
+                }
+                self.timestamp().to_le_bytes()[(reg - TIMESTAMP0) as usize]
+            }
+            FIFO_DATA_OUT_TAG => {
+                let word = self.pop();
+                self.pages[PAGE_MAIN][reg as usize..=FIFO_DATA_OUT_Z_H as usize]
+                    .copy_from_slice(&word);
+                word[0]
+            }
+            _ => self.register(reg),
+        }
+    }
+
+    /// Write a register, ignoring read-only ones and applying the side effects of the write.
+    fn write_register(&mut self, reg: u8, value: u8) {
+        let page = self.page(reg);
+        if page != PAGE_MAIN {
+            self.pages[page][reg as usize] = value;
+            return;
+        }
+
+        let settings = self.batch_settings();
+        match reg {
+            WHO_AM_I_ADDR
+            | 0x1a..=0x1f
+            | OUT_TEMP_L..=OUTZ_H_A
+            | 0x35..=0x3b
+            | TIMESTAMP0..=TIMESTAMP3
+            | FIFO_DATA_OUT_TAG..=FIFO_DATA_OUT_Z_H => {
+                if reg == TIMESTAMP2 && value == 0xaa {
+                    self.timestamp_origin_ns = self.now_ns;
+                }
+            }
+            COUNTER_BDR_REG1 => {
+                if value & (1 << 6) != 0 {
+                    self.bdr_counter = 0;
+                }
+                self.pages[PAGE_MAIN][reg as usize] = value & !(1 << 6);
+            }
+            CTRL3_C if value & 1 != 0 => self.reset(),
+            CTRL3_C => {
+                // BOOT only reloads the trimming parameters, which are not modelled.
+                self.pages[PAGE_MAIN][reg as usize] = value & !(1 << 7);
+            }
+            FIFO_CTRL4 => {
+                self.pages[PAGE_MAIN][reg as usize] = value;
+                if !self.batching() {
+                    self.clear_fifo();
+                }
+            }
+            _ => self.pages[PAGE_MAIN][reg as usize] = value,
+        }
+
+        // A rate may have changed, start over from the current time.
+        if matches!(reg, CTRL1_XL | CTRL2_G | CTRL6_C | FIFO_CTRL3 | FIFO_CTRL4) {
+            self.clocks = [None; CLOCKS];
+        }
+
+        let changed = self.batch_settings();
+        if changed != settings && self.register(FIFO_CTRL2) & (1 << 4) != 0 && self.batching() {
+            let [ctrl1_xl, ctrl2_g, fifo_ctrl3] = changed;
+            self.push(TAG_CFG_CHANGE, [ctrl1_xl, ctrl2_g, fifo_ctrl3, 0, 0, 0]);
+        }
+    }
+
+    /// ODRs, full scales and batch data rates, as reported by a CFG-Change word.
+    fn batch_settings(&self) -> [u8; 3] {
+        [
+            self.register(CTRL1_XL) & 0b1111_1100,
+            self.register(CTRL2_G),
+            self.register(FIFO_CTRL3),
+        ]
+    }
+
+    /// Move the register pointer after an access.
+    fn increment(&mut self) {
+        if self.pointer == FIFO_DATA_OUT_Z_H {
+            self.pointer = FIFO_DATA_OUT_TAG;
+        } else if self.register(CTRL3_C) & (1 << 2) != 0 {
+            self.pointer = (self.pointer + 1) & 0x7f;
+        }
+    }
+
+    fn read_next(&mut self) -> u8 {
+        let value = self.read_register(self.pointer);
+        self.increment();
+        value
+    }
+
+    fn write_next(&mut self, value: u8) {
+        self.write_register(self.pointer, value);
+        self.increment();
+    }
+
+    /// Shift one byte through the SPI interface.
+    fn spi_shift(&mut self, out: u8) -> u8 {
+        match self.spi_command {
+            None => {
+                self.pointer = out & 0x7f;
+                self.spi_command = Some(out & 0x80 != 0);
+                0
+            }
+            Some(true) => self.read_next(),
+            Some(false) => {
+                self.write_next(out);
+                0
+            }
+        }
+    }
+}
+
+fn odr_hz(odr: u8) -> f32 {
+    match odr {
+        0b0001 => 12.5,
+        0b0010 => 26.,
+        0b0011 => 52.,
+        0b0100 => 104.,
+        0b0101 => 208.,
+        0b0110 => 416.,
+        0b0111 => 833.,
+        0b1000 => 1666.,
+        0b1001 => 3333.,
+        0b1010 => 6666.,
+        _ => 0.,
+    }
+}
+
+fn bdr(bdr: u8) -> f32 {
+    match bdr {
+        0b1011 => 6.5,
+        bdr => odr_hz(bdr),
+    }
+}
+
+fn period_ns(rate: f32) -> u64 {
+    (1e9 / rate) as u64
+}
+
+fn saturate(value: f32) -> i16 {
+    let rounded = if value >= 0. {
+        value + 0.5
+    } else {
+        value - 0.5
+    };
+    rounded.clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+/// Encode a signal as little endian counts, `sensitivity` in milli-units per LSB.
+fn encode_axes(value: [f32; 3], sensitivity: f32) -> [u8; 6] {
+    let mut out = [0u8; 6];
+    for (axis, v) in value.iter().enumerate() {
+        let count = saturate(v * 1000. / sensitivity);
+        out[2 * axis..2 * axis + 2].copy_from_slice(&count.to_le_bytes());
+    }
+    out
+}
+
+impl i2c::ErrorType for Simulator {
+    type Error = ErrorKind;
+}
+
+impl i2c::I2c for Simulator {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if address != self.address {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+
+        // The first byte written after a (repeated) start is the register address.
+        let mut sub_address = true;
+        for operation in operations {
+            match operation {
+                i2c::Operation::Write(bytes) => {
+                    for &byte in bytes.iter() {
+                        if sub_address {
+                            self.pointer = byte & 0x7f;
+                            sub_address = false;
+                        } else {
+                            self.write_next(byte);
+                        }
+                    }
+                }
+                i2c::Operation::Read(buffer) => {
+                    for byte in buffer.iter_mut() {
+                        *byte = self.read_next();
+                    }
+                    sub_address = true;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl spi::ErrorType for Simulator {
+    type Error = Infallible;
+}
+
+impl spi::SpiDevice for Simulator {
+    fn transaction(&mut self, operations: &mut [spi::Operation<'_, u8>]) -> Result<(), Infallible> {
+        self.spi_command = None;
+
+        for operation in operations {
+            match operation {
+                spi::Operation::Read(buffer) => {
+                    for byte in buffer.iter_mut() {
+                        *byte = self.spi_shift(0);
+                    }
+                }
+                spi::Operation::Write(bytes) => {
+                    for &byte in bytes.iter() {
+                        self.spi_shift(byte);
+                    }
+                }
+                spi::Operation::Transfer(read, write) => {
+                    for i in 0..read.len().max(write.len()) {
+                        let byte = self.spi_shift(write.get(i).copied().unwrap_or(0));
+                        if let Some(r) = read.get_mut(i) {
+                            *r = byte;
+                        }
+                    }
+                }
+                spi::Operation::TransferInPlace(buffer) => {
+                    for byte in buffer.iter_mut() {
+                        *byte = self.spi_shift(*byte);
+                    }
+                }
+                spi::Operation::DelayNs(_) => {}
+            }
+        }
+
+        self.spi_command = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ctrl1xl, ctrl2g, fifo, fifoctrl, Ism330Dhcx};
+    use approx::*;
+    use embedded_hal::i2c::I2c;
+
+    #[test]
+    fn who_am_i() {
+        let mut sim = Simulator::new();
+
+        let mut v = [0u8];
+        sim.write_read(0x6b, &[WHO_AM_I_ADDR], &mut v).unwrap();
+        assert_eq!(v[0], WHO_AM_I);
+
+        let mut v = [0x80 | WHO_AM_I_ADDR, 0];
+        spi::SpiDevice::transfer_in_place(&mut sim, &mut v).unwrap();
+        assert_eq!(v[1], WHO_AM_I);
+    }
+
+    #[test]
+    fn wrong_address() {
+        let mut sim = Simulator::new();
+
+        let mut v = [0u8];
+        assert_eq!(
+            sim.write_read(0x6a, &[WHO_AM_I_ADDR], &mut v),
+            Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address))
+        );
+    }
+
+    #[test]
+    fn auto_increment() {
+        let mut sim = Simulator::new();
+
+        sim.write(0x6b, &[CTRL1_XL, 0x11, 0x22]).unwrap();
+        assert_eq!(sim.register(CTRL1_XL), 0x11);
+        assert_eq!(sim.register(CTRL2_G), 0x22);
+
+        sim.write(0x6b, &[CTRL3_C, 0]).unwrap();
+        let mut v = [0u8; 2];
+        sim.write_read(0x6b, &[CTRL1_XL], &mut v).unwrap();
+        assert_eq!(v, [0x11, 0x11]);
+    }
+
+    #[test]
+    fn read_only_registers() {
+        let mut sim = Simulator::new();
+
+        sim.write(0x6b, &[WHO_AM_I_ADDR, 0]).unwrap();
+        assert_eq!(sim.register(WHO_AM_I_ADDR), WHO_AM_I);
+    }
+
+    #[test]
+    fn spi_write() {
+        let mut sim = Simulator::new();
+
+        spi::SpiDevice::write(&mut sim, &[CTRL1_XL, 0x40, 0x50]).unwrap();
+        assert_eq!(sim.register(CTRL1_XL), 0x40);
+        assert_eq!(sim.register(CTRL2_G), 0x50);
+    }
+
+    #[test]
+    fn pages() {
+        let mut sim = Simulator::new();
+
+        sim.write(0x6b, &[FUNC_CFG_ACCESS, 1 << 7]).unwrap();
+        sim.write(0x6b, &[0x04, 0xab]).unwrap();
+        sim.write(0x6b, &[FUNC_CFG_ACCESS, 0]).unwrap();
+
+        assert_eq!(sim.register(0x04), 0);
+        assert_eq!(sim.pages[PAGE_EMBEDDED][0x04], 0xab);
+    }
+
+    #[test]
+    fn driver_reads_defaults() {
+        let mut sim = Simulator::new();
+
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        assert!(sensor.ctrl3c.if_inc());
+        assert!(!sensor.ctrl3c.bdu());
+        assert!(sensor.ctrl9xl.den_x());
+        assert_eq!(sensor.ctrl1xl.accelerometer_data_rate(), 0.);
+    }
+
+    #[test]
+    fn outputs_follow_signals() {
+        let mut sim = Simulator::new();
+        sim.set_gyroscope_signal(|_| [10., -20., 250.]);
+        sim.set_temperature_signal(|_| 30.);
+
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_chain_full_scale(&mut sim, ctrl1xl::Fs_Xl::G4)
+            .unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, ctrl1xl::Odr_Xl::Hz104)
+            .unwrap();
+        sensor
+            .ctrl2g
+            .set_chain_full_scale(&mut sim, ctrl2g::Fs::Dps500)
+            .unwrap();
+        sensor
+            .ctrl2g
+            .set_gyroscope_data_rate(&mut sim, ctrl2g::Odr::Hz104)
+            .unwrap();
+
+        // Nothing sampled yet.
+        assert_eq!(sensor.get_accelerometer(&mut sim).unwrap().count(), [0; 3]);
+
+        sim.advance_us(50_000);
+
+        let a = sensor.get_accelerometer(&mut sim).unwrap().as_g();
+        assert_abs_diff_eq!(a[2], 1., epsilon = 0.001);
+
+        let g = sensor.get_gyroscope(&mut sim).unwrap().as_dps();
+        assert_abs_diff_eq!(g[0], 10., epsilon = 0.02);
+        assert_abs_diff_eq!(g[1], -20., epsilon = 0.02);
+        assert_abs_diff_eq!(g[2], 250., epsilon = 0.02);
+
+        let t = sensor.get_temperature(&mut sim).unwrap();
+        assert_abs_diff_eq!(t, 30., epsilon = 0.01);
+    }
+
+    #[test]
+    fn status_reg() {
+        let mut sim = Simulator::new();
+
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, ctrl1xl::Odr_Xl::Hz52)
+            .unwrap();
+        assert_eq!(sim.register(STATUS_REG), 0);
+
+        sim.advance_us(20_000);
+        assert_eq!(sim.register(STATUS_REG) & 0b101, 0b101);
+
+        sensor.get_accelerometer(&mut sim).unwrap();
+        assert_eq!(sim.register(STATUS_REG) & 0b001, 0);
+    }
+
+    #[test]
+    fn block_data_update() {
+        let mut sim = Simulator::new();
+        sim.set_accelerometer_signal(|t| [t, 0., 0.]);
+
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, ctrl1xl::Odr_Xl::Hz104)
+            .unwrap();
+        sensor.ctrl3c.set_bdu(&mut sim, true).unwrap();
+        sim.advance_us(1_000_000);
+
+        // Only read the LSB, the pair is frozen until the MSB is read.
+        let mut lsb = [0u8];
+        sim.write_read(0x6b, &[OUTX_L_A], &mut lsb).unwrap();
+        let held = sim.register(OUTX_L_A + 1);
+        sim.advance_us(1_000_000);
+        assert_eq!(sim.register(OUTX_L_A), lsb[0]);
+        assert_eq!(sim.register(OUTX_L_A + 1), held);
+
+        let mut msb = [0u8];
+        sim.write_read(0x6b, &[OUTX_L_A + 1], &mut msb).unwrap();
+        assert_eq!(msb[0], held);
+        assert_ne!(sim.register(OUTX_L_A + 1), held);
+    }
+
+    #[test]
+    fn software_reset() {
+        let mut sim = Simulator::new();
+
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, ctrl1xl::Odr_Xl::Hz104)
+            .unwrap();
+        sensor.ctrl3c.sw_reset(&mut sim).unwrap();
+
+        assert_eq!(sim.register(CTRL1_XL), 0);
+        assert_eq!(sim.register(CTRL3_C), 1 << 2);
+    }
+
+    #[test]
+    fn timestamp() {
+        let mut sim = Simulator::new();
+
+        sim.write(0x6b, &[CTRL10_C, 1 << 5]).unwrap();
+        sim.advance_us(1_000);
+
+        let mut ts = [0u8; 4];
+        sim.write_read(0x6b, &[TIMESTAMP0], &mut ts).unwrap();
+        assert_eq!(u32::from_le_bytes(ts), 40);
+
+        sim.write(0x6b, &[TIMESTAMP2, 0xaa]).unwrap();
+        sim.write_read(0x6b, &[TIMESTAMP0], &mut ts).unwrap();
+        assert_eq!(u32::from_le_bytes(ts), 0);
+    }
+
+    fn batching(sim: &mut Simulator, mode: fifoctrl::FifoMode) -> Ism330Dhcx {
+        let mut sensor = Ism330Dhcx::new(sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(sim, ctrl1xl::Odr_Xl::Hz104)
+            .unwrap();
+        sensor
+            .ctrl2g
+            .set_gyroscope_data_rate(sim, ctrl2g::Odr::Hz104)
+            .unwrap();
+        sensor
+            .fifoctrl
+            .set_accelerometer_batch_data_rate(sim, fifoctrl::BdrXl::Hz52)
+            .unwrap();
+        sensor
+            .fifoctrl
+            .set_gyroscope_batch_data_rate(sim, fifoctrl::BdrGy::Hz104)
+            .unwrap();
+        sensor.fifoctrl.mode(sim, mode).unwrap();
+        sensor
+    }
+
+    #[test]
+    fn fifo_fills_at_bdr() {
+        let mut sim = Simulator::new();
+        let mut sensor = batching(&mut sim, fifoctrl::FifoMode::Continuous);
+
+        sim.advance_us(100_000);
+        assert_eq!(sensor.fifostatus.diff_fifo(&mut sim).unwrap(), 10 + 5);
+
+        let mut gyro = 0;
+        let mut accel = 0;
+        loop {
+            match sensor.fifo_pop(&mut sim).unwrap() {
+                fifo::Value::Gyro(_) => gyro += 1,
+                fifo::Value::Accel(a) => {
+                    assert_abs_diff_eq!(a.as_g()[2], 1., epsilon = 0.001);
+                    accel += 1
+                }
+                fifo::Value::Empty => break,
+                fifo::Value::Temperature(_)
+                | fifo::Value::Timestamp(_)
+                | fifo::Value::ConfigChange(_)
+                | fifo::Value::Gap { .. }
+                | fifo::Value::Other(..) => unreachable!(),
+            }
+        }
+        assert_eq!((gyro, accel), (10, 5));
+        assert_eq!(sensor.fifostatus.diff_fifo(&mut sim).unwrap(), 0);
+    }
+
+    #[test]
+    fn fifo_mode_stops_when_full() {
+        let mut sim = Simulator::new();
+        let mut sensor = batching(&mut sim, fifoctrl::FifoMode::FifoMode);
+
+        sim.advance_us(10_000_000);
+        assert_eq!(sim.fifo_level(), FIFO_SIZE as usize);
+        assert!(sensor.fifostatus.full(&mut sim).unwrap());
+        assert!(!sensor.fifostatus.overrun(&mut sim).unwrap());
+    }
+
+    #[test]
+    fn continuous_mode_overruns() {
+        let mut sim = Simulator::new();
+        let mut sensor = batching(&mut sim, fifoctrl::FifoMode::Continuous);
+
+        sim.advance_us(10_000_000);
+        assert_eq!(sim.fifo_level(), FIFO_SIZE as usize);
+        assert!(sensor.fifostatus.overrun_latched(&mut sim).unwrap());
+
+        // The latched flag is cleared by reading FIFO_STATUS2, the live one by reading the FIFO.
+        assert!(!sensor.fifostatus.overrun_latched(&mut sim).unwrap());
+        assert!(sensor.fifostatus.overrun(&mut sim).unwrap());
+        sensor.fifo_pop(&mut sim).unwrap();
+        assert!(!sensor.fifostatus.overrun(&mut sim).unwrap());
+
+        sensor
+            .fifoctrl
+            .mode(&mut sim, fifoctrl::FifoMode::Bypass)
+            .unwrap();
+        assert_eq!(sensor.fifostatus.diff_fifo(&mut sim).unwrap(), 0);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::Register;
+
+/// The STATUS_REG register.
+///
+/// Data-ready flags of the accelerometer, gyroscope and temperature outputs. A flag is cleared
+/// when the matching output registers are read.
+pub struct StatusReg {
+    pub address: u8,
+}
+
+/// Sub-address of the register.
+pub const ADDR: u8 = 0x1e_u8;
+
+/// Temperature new data available.
+///
+/// (0: no set of data is available at temperature sensor output; 1: a new set of data is available at temperature sensor output)
+pub const TDA: u8 = 2;
+
+/// Gyroscope new data available.
+///
+/// (0: no set of data available at gyroscope output; 1: a new set of data is available at gyroscope output)
+pub const GDA: u8 = 1;
+
+/// Accelerometer new data available.
+///
+/// (0: no set of data available at accelerometer output; 1: a new set of data is available at accelerometer output)
+pub const XLDA: u8 = 0;
+
+/// Outputs holding a sample that has not been read yet.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DataReady {
+    pub accel: bool,
+    pub gyro: bool,
+    pub temp: bool,
+}
+
+impl From<u8> for DataReady {
+    fn from(value: u8) -> Self {
+        DataReady {
+            accel: value & (1 << XLDA) != 0,
+            gyro: value & (1 << GDA) != 0,
+            temp: value & (1 << TDA) != 0,
+        }
+    }
+}
+
+impl Register for StatusReg {}
+
+impl StatusReg {
+    pub fn new(address: u8) -> Self {
+        StatusReg { address }
+    }
+
+    /// Which outputs have new data.
+    pub fn data_ready<I2C>(&mut self, i2c: &mut I2C) -> Result<DataReady, I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let v = self.read(i2c, self.address, ADDR)?;
+
+        Ok(DataReady::from(v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    #[test]
+    fn data_ready() {
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x6b, vec![ADDR], vec![0b101]),
+            Transaction::write_read(0x6b, vec![ADDR], vec![0b010]),
+        ]);
+        let mut status = StatusReg::new(0x6b);
+
+        assert_eq!(
+            status.data_ready(&mut i2c).unwrap(),
+            DataReady {
+                accel: true,
+                gyro: false,
+                temp: true
+            }
+        );
+        assert_eq!(
+            status.data_ready(&mut i2c).unwrap(),
+            DataReady {
+                accel: false,
+                gyro: true,
+                temp: false
+            }
+        );
+
+        i2c.done();
+    }
+}
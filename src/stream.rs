@@ -0,0 +1,327 @@
+//! Interrupt driven FIFO acquisition.
+//!
+//! A [`Stream`] routes the FIFO threshold interrupt to an INT pad and drains the FIFO each time the
+//! pin connected to it is asserted, instead of polling the sensor on a timer. The data rates,
+//! batch data rates and FIFO mode are configured beforehand as usual.
+//!
+//! ```no_run
+//! # use core::convert::Infallible;
+//! # use embedded_hal::{digital::InputPin, i2c::I2c};
+//! # fn run<I2C: I2c, P: InputPin<Error = Infallible>>(i2c: &mut I2C, int1: P) -> Result<(), ism330dhcx::Error<I2C::Error>> {
+//! use ism330dhcx::fifo;
+//! use ism330dhcx::fifoctrl::{BdrXl, FifoMode};
+//! use ism330dhcx::stream::{IntPin, Stream};
+//! use ism330dhcx::Ism330Dhcx;
+//!
+//! let mut sensor = Ism330Dhcx::new(i2c)?;
+//! sensor.fifoctrl.set_accelerometer_batch_data_rate(i2c, BdrXl::Hz52)?;
+//! sensor.fifoctrl.mode(i2c, FifoMode::Continuous)?;
+//!
+//! let mut stream = Stream::new(i2c, sensor, int1, IntPin::Int1, 32)?;
+//! let mut values = [fifo::Value::Empty; 32];
+//!
+//! loop {
+//!     let n = stream.wait(i2c, &mut values)?;
+//!     for value in &values[..n] {
+//!         println!("{:?}", value);
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! With the `async` feature, `Stream::next` waits for the pin without blocking.
+
+use embedded_hal::digital::InputPin;
+use embedded_hal::i2c::I2c;
+
+use crate::config::{self, ConfigError};
+use crate::{fifo, Error, Ism330Dhcx};
+
+/// Interrupt pad of the sensor.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+pub enum IntPin {
+    Int1,
+    Int2,
+}
+
+/// FIFO reader woken up by the FIFO threshold interrupt.
+pub struct Stream<P> {
+    sensor: Ism330Dhcx,
+    pin: P,
+    int: IntPin,
+    active_low: bool,
+}
+
+impl<P> Stream<P> {
+    /// Set the FIFO watermark and route the FIFO threshold interrupt to `int`, where `pin` is
+    /// connected.
+    ///
+    /// The pin polarity follows `H_LACTIVE` in `CTRL3_C`. Nothing is written if `watermark` is
+    /// larger than [`config::WATERMARK_MAX`].
+    pub fn new<I2C>(
+        i2c: &mut I2C,
+        mut sensor: Ism330Dhcx,
+        pin: P,
+        int: IntPin,
+        watermark: u16,
+    ) -> Result<Self, Error<I2C::Error>>
+    where
+        I2C: I2c,
+    {
+        if watermark > config::WATERMARK_MAX {
+            return Err(Error::InvalidConfig(ConfigError::WatermarkOutOfRange));
+        }
+
+        sensor.fifoctrl.set_watermark(i2c, watermark)?;
+        match int {
+            IntPin::Int1 => sensor.int1ctrl.set_int1_fifo_th(i2c, true)?,
+            IntPin::Int2 => sensor.int2ctrl.set_int2_fifo_th(i2c, true)?,
+        }
+        let active_low = sensor.ctrl3c.h_lactive();
+
+        Ok(Stream {
+            sensor,
+            pin,
+            int,
+            active_low,
+        })
+    }
+
+    /// The sensor, for configuration changes while streaming.
+    pub fn sensor(&mut self) -> &mut Ism330Dhcx {
+        &mut self.sensor
+    }
+
+    /// Stop routing the FIFO threshold interrupt and give back the sensor and the pin.
+    pub fn release<I2C>(mut self, i2c: &mut I2C) -> Result<(Ism330Dhcx, P), I2C::Error>
+    where
+        I2C: I2c,
+    {
+        match self.int {
+            IntPin::Int1 => self.sensor.int1ctrl.set_int1_fifo_th(i2c, false)?,
+            IntPin::Int2 => self.sensor.int2ctrl.set_int2_fifo_th(i2c, false)?,
+        }
+
+        Ok((self.sensor, self.pin))
+    }
+
+    /// Pop the words in the FIFO into `out`, returning how many were read.
+    ///
+    /// Words that do not fit in `out` are left in the FIFO. An overrun is reported with a
+    /// [`fifo::Value::Gap`], see [`Ism330Dhcx::fifo_read`].
+    pub fn drain<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        out: &mut [fifo::Value],
+    ) -> Result<usize, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        self.sensor.fifo_read(i2c, out, fifo::Recovery::Continue)
+    }
+}
+
+impl<P: InputPin> Stream<P> {
+    /// Is the FIFO threshold interrupt asserted.
+    pub fn is_pending(&mut self) -> Result<bool, P::Error> {
+        if self.active_low {
+            self.pin.is_low()
+        } else {
+            self.pin.is_high()
+        }
+    }
+
+    /// Drain the FIFO if the interrupt is asserted, otherwise return 0 without touching the bus.
+    pub fn poll<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        out: &mut [fifo::Value],
+    ) -> Result<usize, Error<I2C::Error, P::Error>>
+    where
+        I2C: I2c,
+    {
+        if !self.is_pending().map_err(Error::Pin)? {
+            return Ok(0);
+        }
+
+        Ok(self.drain(i2c, out)?)
+    }
+
+    /// Busy-wait for the interrupt, then drain the FIFO.
+    pub fn wait<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        out: &mut [fifo::Value],
+    ) -> Result<usize, Error<I2C::Error, P::Error>>
+    where
+        I2C: I2c,
+    {
+        while !self.is_pending().map_err(Error::Pin)? {}
+
+        Ok(self.drain(i2c, out)?)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P: embedded_hal_async::digital::Wait> Stream<P> {
+    /// Wait for the interrupt without blocking, then drain the FIFO.
+    pub async fn next<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        out: &mut [fifo::Value],
+    ) -> Result<usize, Error<I2C::Error, P::Error>>
+    where
+        I2C: I2c,
+    {
+        if self.active_low {
+            self.pin.wait_for_low().await.map_err(Error::Pin)?;
+        } else {
+            self.pin.wait_for_high().await.map_err(Error::Pin)?;
+        }
+
+        Ok(self.drain(i2c, out)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fifoctrl::{BdrXl, FifoMode};
+    use crate::{ctrl1xl, int1ctrl, int2ctrl, sim::Simulator};
+    use embedded_hal_mock::eh1::digital::{Mock as PinMock, State, Transaction as PinTransaction};
+    use embedded_hal_mock::eh1::MockError;
+    use std::io::ErrorKind;
+
+    fn streaming(sim: &mut Simulator) -> Ism330Dhcx {
+        let mut sensor = Ism330Dhcx::new(sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(sim, ctrl1xl::Odr_Xl::Hz104)
+            .unwrap();
+        sensor
+            .fifoctrl
+            .set_accelerometer_batch_data_rate(sim, BdrXl::Hz104)
+            .unwrap();
+        sensor.fifoctrl.mode(sim, FifoMode::Continuous).unwrap();
+        sensor
+    }
+
+    #[test]
+    fn routes_threshold() {
+        let mut sim = Simulator::new();
+        let sensor = streaming(&mut sim);
+        let pin = PinMock::new(&[]);
+
+        let stream = Stream::new(&mut sim, sensor, pin, IntPin::Int2, 300).unwrap();
+        assert_eq!(sim.register(int2ctrl::ADDR), 1 << int2ctrl::INT2_FIFO_TH);
+        assert_eq!(sim.register(0x07), 44);
+        assert_eq!(sim.register(0x08), 1);
+
+        let (sensor, mut pin) = stream.release(&mut sim).unwrap();
+        assert_eq!(sim.register(int2ctrl::ADDR), 0);
+        assert_eq!(sensor.fifoctrl.watermark(), 300);
+        pin.done();
+    }
+
+    #[test]
+    fn rejects_large_watermark() {
+        let mut sim = Simulator::new();
+        let sensor = streaming(&mut sim);
+        let mut pin = PinMock::new(&[]);
+
+        assert!(matches!(
+            Stream::new(&mut sim, sensor, pin.clone(), IntPin::Int1, 512),
+            Err(Error::InvalidConfig(ConfigError::WatermarkOutOfRange))
+        ));
+        assert_eq!(sim.register(0x07), 0);
+        assert_eq!(sim.register(int1ctrl::ADDR), 0);
+        pin.done();
+    }
+
+    #[test]
+    fn drains_when_asserted() {
+        let mut sim = Simulator::new();
+        let sensor = streaming(&mut sim);
+        let pin = PinMock::new(&[
+            PinTransaction::get(State::Low),
+            PinTransaction::get(State::Low),
+            PinTransaction::get(State::High),
+            PinTransaction::get(State::High),
+        ]);
+
+        let mut stream = Stream::new(&mut sim, sensor, pin, IntPin::Int1, 4).unwrap();
+        assert_eq!(sim.register(int1ctrl::ADDR), 1 << int1ctrl::INT1_FIFO_TH);
+        sim.advance_us(100_000);
+
+        let mut values = [fifo::Value::Empty; 8];
+        assert_eq!(stream.poll(&mut sim, &mut values).unwrap(), 0);
+        assert_eq!(sim.fifo_level(), 10);
+
+        assert_eq!(stream.wait(&mut sim, &mut values).unwrap(), 8);
+        assert!(values.iter().all(|v| matches!(v, fifo::Value::Accel(_))));
+        assert_eq!(stream.poll(&mut sim, &mut values).unwrap(), 2);
+        assert_eq!(sim.fifo_level(), 0);
+
+        let (_, mut pin) = stream.release(&mut sim).unwrap();
+        pin.done();
+    }
+
+    #[test]
+    fn active_low() {
+        let mut sim = Simulator::new();
+        let mut sensor = streaming(&mut sim);
+        sensor.ctrl3c.set_h_lactive(&mut sim, true).unwrap();
+        let pin = PinMock::new(&[
+            PinTransaction::get(State::High),
+            PinTransaction::get(State::Low).with_error(MockError::Io(ErrorKind::BrokenPipe)),
+        ]);
+
+        let mut stream = Stream::new(&mut sim, sensor, pin, IntPin::Int1, 4).unwrap();
+        let mut values = [fifo::Value::Empty; 8];
+
+        assert_eq!(stream.poll(&mut sim, &mut values).unwrap(), 0);
+        assert!(matches!(
+            stream.poll(&mut sim, &mut values),
+            Err(Error::Pin(MockError::Io(ErrorKind::BrokenPipe)))
+        ));
+
+        let (_, mut pin) = stream.release(&mut sim).unwrap();
+        pin.done();
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn next() {
+        use core::future::Future;
+        use core::pin::pin;
+        use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |_| RawWaker::new(core::ptr::null(), &VTABLE),
+            |_| {},
+            |_| {},
+            |_| {},
+        );
+
+        let mut sim = Simulator::new();
+        let sensor = streaming(&mut sim);
+        let pin = PinMock::new(&[PinTransaction::wait_for_state(State::High)]);
+
+        let mut stream = Stream::new(&mut sim, sensor, pin, IntPin::Int1, 4).unwrap();
+        sim.advance_us(50_000);
+
+        let mut values = [fifo::Value::Empty; 8];
+        let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+        let n = {
+            let mut future = pin!(stream.next(&mut sim, &mut values));
+            match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+                Poll::Ready(n) => n.unwrap(),
+                Poll::Pending => panic!("mock pin is always ready"),
+            }
+        };
+        assert_eq!(n, 5);
+
+        let (_, mut pin) = stream.release(&mut sim).unwrap();
+        pin.done();
+    }
+}
@@ -0,0 +1,329 @@
+//! Typestate layer over [`Ism330Dhcx`].
+//!
+//! A [`Sensor`] tracks in its type whether the accelerometer and gyroscope are running and whether
+//! the FIFO is enabled, so reading a sensor that is off or compressing a FIFO in bypass mode does
+//! not compile. The dynamic [`Ism330Dhcx`] API stays available through [`Sensor::into_inner`].
+//!
+//! ```no_run
+//! # fn run<I2C: embedded_hal::i2c::I2c>(i2c: &mut I2C) -> Result<(), ism330dhcx::Error<I2C::Error>> {
+//! use ism330dhcx::ctrl1xl::Odr_Xl;
+//! use ism330dhcx::power::PowerMode;
+//! use ism330dhcx::typestate::Sensor;
+//! use ism330dhcx::Ism330Dhcx;
+//!
+//! let sensor = Ism330Dhcx::new(i2c)?;
+//! let sensor = Sensor::new(i2c, sensor)?;
+//! let mut sensor = sensor.enable_accel(i2c, Odr_Xl::Hz104, PowerMode::HighPerformance)?;
+//! let accel = sensor.get_accelerometer(i2c)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The gyroscope is still off, so it cannot be read:
+//!
+//! ```compile_fail
+//! # fn run<I2C: embedded_hal::i2c::I2c>(i2c: &mut I2C) -> Result<(), ism330dhcx::Error<I2C::Error>> {
+//! use ism330dhcx::ctrl1xl::Odr_Xl;
+//! use ism330dhcx::power::PowerMode;
+//! use ism330dhcx::typestate::Sensor;
+//! use ism330dhcx::Ism330Dhcx;
+//!
+//! let sensor = Ism330Dhcx::new(i2c)?;
+//! let sensor = Sensor::new(i2c, sensor)?;
+//! let mut sensor = sensor.enable_accel(i2c, Odr_Xl::Hz104, PowerMode::HighPerformance)?;
+//! let gyro = sensor.get_gyroscope(i2c)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Transitions consume the sensor. On a bus error it is dropped, and [`Ism330Dhcx::new`] reads the
+//! state back from the device.
+
+use core::marker::PhantomData;
+
+use embedded_hal::i2c::I2c;
+
+use crate::config::ConfigError;
+use crate::ctrl1xl::Odr_Xl;
+use crate::ctrl2g::Odr;
+use crate::ctrl3c::InterruptOutput;
+use crate::fifoctrl::{BdrGy, BdrXl, FifoMode};
+use crate::power::PowerMode;
+use crate::{fifo, AccelValue, Error, GyroValue, ImuSample, Ism330Dhcx};
+
+/// The accelerometer is powered down.
+pub struct AccelOff;
+/// The accelerometer is running.
+pub struct AccelOn;
+/// The gyroscope is powered down.
+pub struct GyroOff;
+/// The gyroscope is running.
+pub struct GyroOn;
+/// The FIFO is in bypass mode.
+pub struct FifoBypass;
+/// The FIFO is in continuous mode.
+pub struct FifoContinuous;
+
+/// An [`Ism330Dhcx`] whose accelerometer, gyroscope and FIFO states are known at compile time.
+pub struct Sensor<A, G, F> {
+    inner: Ism330Dhcx,
+    state: PhantomData<(A, G, F)>,
+}
+
+impl Sensor<AccelOff, GyroOff, FifoBypass> {
+    /// Take over `sensor`, powering both sensors down and putting the FIFO in bypass mode.
+    pub fn new<I2C>(i2c: &mut I2C, mut sensor: Ism330Dhcx) -> Result<Self, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        sensor.ctrl2g.set_gyroscope_data_rate(i2c, Odr::Off)?;
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(i2c, Odr_Xl::Off)?;
+        sensor.fifoctrl.mode(i2c, FifoMode::Bypass)?;
+
+        Ok(wrap(sensor))
+    }
+}
+
+fn wrap<A, G, F>(inner: Ism330Dhcx) -> Sensor<A, G, F> {
+    Sensor {
+        inner,
+        state: PhantomData,
+    }
+}
+
+impl<A, G, F> Sensor<A, G, F> {
+    /// The underlying sensor, for settings that do not change the state.
+    pub fn inner(&self) -> &Ism330Dhcx {
+        &self.inner
+    }
+
+    /// Give up the typestate and go back to the dynamic API.
+    pub fn into_inner(self) -> Ism330Dhcx {
+        self.inner
+    }
+
+    /// Get temperature in Celsius.
+    pub fn get_temperature<I2C>(&mut self, i2c: &mut I2C) -> Result<f32, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        self.inner.get_temperature(i2c)
+    }
+
+    /// Set the electrical mode of the interrupt pins, see [`InterruptOutput`].
+    pub fn set_interrupt_output<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+        output: InterruptOutput,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: I2c,
+    {
+        self.inner.ctrl3c.set_interrupt_output(i2c, output)
+    }
+}
+
+impl<G, F> Sensor<AccelOff, G, F> {
+    /// Start the accelerometer, see [`Ism330Dhcx::set_accel_power_mode`].
+    pub fn enable_accel<I2C>(
+        mut self,
+        i2c: &mut I2C,
+        odr: Odr_Xl,
+        mode: PowerMode,
+    ) -> Result<Sensor<AccelOn, G, F>, Error<I2C::Error>>
+    where
+        I2C: I2c,
+    {
+        if odr == Odr_Xl::Off {
+            return Err(Error::InvalidConfig(ConfigError::SensorOff));
+        }
+        self.inner.set_accel_power_mode(i2c, odr, mode)?;
+
+        Ok(wrap(self.inner))
+    }
+}
+
+impl<G, F> Sensor<AccelOn, G, F> {
+    pub fn get_accelerometer<I2C>(&mut self, i2c: &mut I2C) -> Result<AccelValue, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        self.inner.get_accelerometer(i2c)
+    }
+
+    pub fn disable_accel<I2C>(mut self, i2c: &mut I2C) -> Result<Sensor<AccelOff, G, F>, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        self.inner
+            .ctrl1xl
+            .set_accelerometer_data_rate(i2c, Odr_Xl::Off)?;
+
+        Ok(wrap(self.inner))
+    }
+}
+
+impl<A, F> Sensor<A, GyroOff, F> {
+    /// Start the gyroscope, see [`Ism330Dhcx::set_gyro_power_mode`].
+    pub fn enable_gyro<I2C>(
+        mut self,
+        i2c: &mut I2C,
+        odr: Odr,
+        mode: PowerMode,
+    ) -> Result<Sensor<A, GyroOn, F>, Error<I2C::Error>>
+    where
+        I2C: I2c,
+    {
+        if odr == Odr::Off {
+            return Err(Error::InvalidConfig(ConfigError::SensorOff));
+        }
+        self.inner.set_gyro_power_mode(i2c, odr, mode)?;
+
+        Ok(wrap(self.inner))
+    }
+}
+
+impl<A, F> Sensor<A, GyroOn, F> {
+    pub fn get_gyroscope<I2C>(&mut self, i2c: &mut I2C) -> Result<GyroValue, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        self.inner.get_gyroscope(i2c)
+    }
+
+    pub fn disable_gyro<I2C>(mut self, i2c: &mut I2C) -> Result<Sensor<A, GyroOff, F>, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        self.inner.ctrl2g.set_gyroscope_data_rate(i2c, Odr::Off)?;
+
+        Ok(wrap(self.inner))
+    }
+}
+
+impl<F> Sensor<AccelOn, GyroOn, F> {
+    /// See [`Ism330Dhcx::read_all`].
+    pub fn read_all<I2C>(&mut self, i2c: &mut I2C) -> Result<ImuSample, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        self.inner.read_all(i2c)
+    }
+}
+
+impl<A, G> Sensor<A, G, FifoBypass> {
+    /// Batch the sensors at the given rates in continuous mode.
+    pub fn start_fifo<I2C>(
+        mut self,
+        i2c: &mut I2C,
+        accel_bdr: BdrXl,
+        gyro_bdr: BdrGy,
+    ) -> Result<Sensor<A, G, FifoContinuous>, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        let fifoctrl = &mut self.inner.fifoctrl;
+        fifoctrl.set_accelerometer_batch_data_rate(i2c, accel_bdr)?;
+        fifoctrl.set_gyroscope_batch_data_rate(i2c, gyro_bdr)?;
+        fifoctrl.mode(i2c, FifoMode::Continuous)?;
+
+        Ok(wrap(self.inner))
+    }
+}
+
+impl<A, G> Sensor<A, G, FifoContinuous> {
+    pub fn fifo_pop<I2C>(&mut self, i2c: &mut I2C) -> Result<fifo::Value, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        self.inner.fifo_pop(i2c)
+    }
+
+    pub fn set_compression<I2C>(&mut self, i2c: &mut I2C, value: bool) -> Result<(), I2C::Error>
+    where
+        I2C: I2c,
+    {
+        self.inner.fifoctrl.compression(i2c, value)
+    }
+
+    /// Go back to bypass mode, which empties the FIFO.
+    pub fn stop_fifo<I2C>(mut self, i2c: &mut I2C) -> Result<Sensor<A, G, FifoBypass>, I2C::Error>
+    where
+        I2C: I2c,
+    {
+        self.inner.fifoctrl.compression(i2c, false)?;
+        self.inner.fifoctrl.mode(i2c, FifoMode::Bypass)?;
+
+        Ok(wrap(self.inner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::Simulator;
+    use crate::{ctrl1xl, ctrl2g, ctrl3c};
+    use embedded_hal::i2c::I2c;
+
+    #[test]
+    fn transitions() {
+        let mut sim = Simulator::new();
+        sim.write(0x6b, &[ctrl2g::ADDR, 0x40]).unwrap();
+
+        let sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        let sensor = Sensor::new(&mut sim, sensor).unwrap();
+        assert_eq!(sim.register(ctrl2g::ADDR), 0);
+
+        assert!(matches!(
+            sensor.enable_accel(&mut sim, Odr_Xl::Off, PowerMode::HighPerformance),
+            Err(Error::InvalidConfig(ConfigError::SensorOff))
+        ));
+
+        let sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        let sensor = Sensor::new(&mut sim, sensor).unwrap();
+        let sensor = sensor
+            .enable_accel(&mut sim, Odr_Xl::Hz104, PowerMode::HighPerformance)
+            .unwrap();
+        let mut sensor = sensor
+            .enable_gyro(&mut sim, Odr::Hz104, PowerMode::Normal)
+            .unwrap()
+            .start_fifo(&mut sim, BdrXl::Hz104, BdrGy::Off)
+            .unwrap();
+        assert_eq!(sim.register(ctrl1xl::ADDR) >> 4, 4);
+        assert_eq!(sim.register(ctrl2g::ADDR) >> 4, 4);
+
+        sim.advance_us(20_000);
+        sensor.read_all(&mut sim).unwrap();
+        sensor.set_compression(&mut sim, true).unwrap();
+        assert!(matches!(
+            sensor.fifo_pop(&mut sim).unwrap(),
+            fifo::Value::Accel(_)
+        ));
+
+        let mut sensor = sensor
+            .stop_fifo(&mut sim)
+            .unwrap()
+            .disable_gyro(&mut sim)
+            .unwrap();
+        assert_eq!(sim.fifo_level(), 0);
+        assert_eq!(sim.register(ctrl2g::ADDR), 0);
+        sensor.get_accelerometer(&mut sim).unwrap();
+
+        sensor
+            .set_interrupt_output(&mut sim, InterruptOutput::PushPullActiveLow)
+            .unwrap();
+        assert_eq!(
+            sim.register(ctrl3c::ADDR) & 0b11_0000,
+            1 << ctrl3c::H_LACTIVE
+        );
+        assert_eq!(
+            sensor.inner().ctrl3c.interrupt_output(),
+            Some(InterruptOutput::PushPullActiveLow)
+        );
+
+        let sensor = sensor.disable_accel(&mut sim).unwrap().into_inner();
+        assert_eq!(sensor.power_state(), crate::power::PowerState::PowerDown);
+    }
+}
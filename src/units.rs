@@ -0,0 +1,134 @@
+//! Readings as typed physical quantities from the `uom` crate.
+//!
+//! ```no_run
+//! # fn run<I2C: embedded_hal::i2c::I2c>(i2c: &mut I2C) -> Result<(), ism330dhcx::Error<I2C::Error>> {
+//! use ism330dhcx::Ism330Dhcx;
+//! use uom::si::{acceleration::meter_per_second_squared, thermodynamic_temperature::degree_celsius};
+//!
+//! let mut sensor = Ism330Dhcx::new(i2c)?;
+//! let accel = sensor.get_accelerometer(i2c)?.acceleration();
+//! let z = accel.z.get::<meter_per_second_squared>();
+//! let temp = sensor.get_temperature_quantity(i2c)?.get::<degree_celsius>();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Quantities are `f32`, computed from the `f32` conversions of the samples.
+
+use uom::si::acceleration::standard_gravity;
+use uom::si::angular_velocity::degree_per_second;
+use uom::si::f32::{Acceleration, AngularVelocity, ThermodynamicTemperature};
+use uom::si::thermodynamic_temperature::degree_celsius;
+
+use crate::{AccelValue, GyroValue, Ism330Dhcx};
+
+/// A quantity along the three axes of the sensor.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Vector3<Q> {
+    pub x: Q,
+    pub y: Q,
+    pub z: Q,
+}
+
+impl<Q> Vector3<Q> {
+    pub fn new(x: Q, y: Q, z: Q) -> Self {
+        Vector3 { x, y, z }
+    }
+
+    /// Apply `f` to each axis.
+    pub fn map<R>(self, mut f: impl FnMut(Q) -> R) -> Vector3<R> {
+        Vector3::new(f(self.x), f(self.y), f(self.z))
+    }
+}
+
+impl<Q> From<[Q; 3]> for Vector3<Q> {
+    fn from([x, y, z]: [Q; 3]) -> Self {
+        Vector3::new(x, y, z)
+    }
+}
+
+impl<Q> From<Vector3<Q>> for [Q; 3] {
+    fn from(v: Vector3<Q>) -> Self {
+        [v.x, v.y, v.z]
+    }
+}
+
+impl AccelValue {
+    /// As typed accelerations
+    pub fn acceleration(&self) -> Vector3<Acceleration> {
+        Vector3::from(self.as_g_f32()).map(Acceleration::new::<standard_gravity>)
+    }
+}
+
+impl GyroValue {
+    /// As typed angular velocities
+    pub fn angular_velocity(&self) -> Vector3<AngularVelocity> {
+        Vector3::from(self.as_dps_f32()).map(AngularVelocity::new::<degree_per_second>)
+    }
+}
+
+impl Ism330Dhcx {
+    /// Get temperature as a typed quantity.
+    pub fn get_temperature_quantity<I2C>(
+        &mut self,
+        i2c: &mut I2C,
+    ) -> Result<ThermodynamicTemperature, I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c,
+    {
+        let celsius = self.get_temperature(i2c)?;
+        Ok(ThermodynamicTemperature::new::<degree_celsius>(celsius))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ctrl1xl::Fs_Xl, ctrl2g::Fs, sim::Simulator};
+    use approx::*;
+    use embedded_hal::i2c::I2c;
+    use uom::si::acceleration::meter_per_second_squared;
+    use uom::si::angular_velocity::radian_per_second;
+    use uom::si::thermodynamic_temperature::kelvin;
+
+    #[test]
+    fn acceleration() {
+        let value = AccelValue::new(Fs_Xl::G2, [0x4009, 0, -0x4009]);
+        let a = value.acceleration();
+
+        assert_abs_diff_eq!(a.x.get::<standard_gravity>(), 1., epsilon = 0.001);
+        assert_abs_diff_eq!(a.y.get::<meter_per_second_squared>(), 0.);
+        assert_abs_diff_eq!(
+            a.z.get::<meter_per_second_squared>(),
+            value.as_m_ss_f32()[2],
+            epsilon = 1e-4
+        );
+    }
+
+    #[test]
+    fn angular_velocity() {
+        let value = GyroValue::new(Fs::Dps250, [0x2ca4, 0, 0]);
+        let w: [AngularVelocity; 3] = value.angular_velocity().into();
+
+        assert_abs_diff_eq!(w[0].get::<degree_per_second>(), 100., epsilon = 0.01);
+        assert_abs_diff_eq!(
+            w[0].get::<radian_per_second>(),
+            value.as_rad_f32()[0],
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn temperature() {
+        let mut sim = Simulator::new();
+        sim.set_temperature_signal(|_| 30.);
+        sim.write(0x6b, &[0x10, 0x40]).unwrap();
+        sim.advance_us(100_000);
+
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        let t = sensor.get_temperature_quantity(&mut sim).unwrap();
+
+        assert_abs_diff_eq!(t.get::<degree_celsius>(), 30., epsilon = 0.01);
+        assert_abs_diff_eq!(t.get::<kelvin>(), 303.15, epsilon = 0.01);
+    }
+}
@@ -0,0 +1,295 @@
+//! Check the register caches against the device.
+//!
+//! [`crate::Ism330Dhcx`] keeps a copy of the control registers it writes, so a reset, a brown-out
+//! or another master on the bus can leave it out of step with the device.
+//! [`crate::Ism330Dhcx::verify`] reports where the two disagree, and [`crate::Ism330Dhcx::sync`]
+//! reloads the caches.
+//!
+//! [`WriteVerify`] wraps the bus and reads every register write back, catching a bad write where
+//! it happens instead of at the next `verify`.
+//!
+//! Only the bits listed as writable in [`dump::REGISTERS`] are compared, so self-clearing bits
+//! such as `SW_RESET` and `BOOT` never count as a mismatch.
+
+use core::fmt;
+
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, Operation, SevenBitAddress};
+
+use crate::dump::{self, Change, Page};
+use crate::{config, CONTROL_BLOCK};
+
+/// Registers held in the caches, as (address, value) pairs.
+pub(crate) type Cached = [(u8, u8); 17];
+
+/// Cached registers that differ from the device, see [`crate::Ism330Dhcx::verify`].
+#[derive(Clone, Debug)]
+pub struct Mismatches {
+    cached: Cached,
+    device: [u8; CONTROL_BLOCK],
+}
+
+impl Mismatches {
+    pub(crate) fn new(cached: Cached, device: [u8; CONTROL_BLOCK]) -> Self {
+        Mismatches { cached, device }
+    }
+
+    /// The mismatching registers, `before` being the cached value and `after` the device value.
+    pub fn iter(&self) -> impl Iterator<Item = Change> + '_ {
+        self.cached.iter().filter_map(|&(addr, before)| {
+            let register = dump::find(Page::Main, addr)?;
+            let after = self.device[(addr - config::CONTROL_START) as usize];
+            ((before ^ after) & register.writable != 0).then_some(Change {
+                register,
+                before,
+                after,
+            })
+        })
+    }
+
+    /// `true` if the caches match the device.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+impl fmt::Display for Mismatches {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in self.iter() {
+            writeln!(f, "{}", change)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Error of a [`WriteVerify`] bus.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum VerifyError<E> {
+    Bus(E),
+    /// A register read back differently from what was written, in its writable bits.
+    Mismatch {
+        addr: u8,
+        written: u8,
+        read: u8,
+    },
+}
+
+impl<E> From<E> for VerifyError<E> {
+    fn from(e: E) -> Self {
+        VerifyError::Bus(e)
+    }
+}
+
+impl<E: embedded_hal::i2c::Error> embedded_hal::i2c::Error for VerifyError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            VerifyError::Bus(e) => e.kind(),
+            VerifyError::Mismatch { .. } => ErrorKind::Other,
+        }
+    }
+}
+
+/// An I²C bus reading back every register write.
+///
+/// A write of a register address followed by data is read back from the same address, relying on
+/// `IF_INC` for bursts. Registers missing from [`dump::REGISTERS`] are not checked. The page
+/// selected through `FUNC_CFG_ACCESS` is tracked so embedded functions and sensor hub registers
+/// are checked too.
+///
+/// ```no_run
+/// # use ism330dhcx::verify::VerifyError;
+/// # fn run<I2C: embedded_hal::i2c::I2c>(i2c: I2C) -> Result<(), ism330dhcx::Error<VerifyError<I2C::Error>>> {
+/// use ism330dhcx::verify::WriteVerify;
+/// use ism330dhcx::{ctrl1xl, Ism330Dhcx};
+///
+/// let mut i2c = WriteVerify::new(i2c);
+/// let mut sensor = Ism330Dhcx::new(&mut i2c)?;
+/// sensor.ctrl1xl.set_accelerometer_data_rate(&mut i2c, ctrl1xl::Odr_Xl::Hz104)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct WriteVerify<I2C> {
+    i2c: I2C,
+    page: Page,
+}
+
+impl<I2C: I2c> WriteVerify<I2C> {
+    pub fn new(i2c: I2C) -> Self {
+        WriteVerify {
+            i2c,
+            page: Page::Main,
+        }
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    fn check(&mut self, address: u8, write: &[u8]) -> Result<(), VerifyError<I2C::Error>> {
+        let (&start, data) = match write.split_first() {
+            Some(split) if !split.1.is_empty() => split,
+            _ => return Ok(()),
+        };
+
+        let mut read = [0u8; 16];
+        for (chunk, written) in data.chunks(read.len()).enumerate() {
+            let reg = start.wrapping_add((chunk * read.len()) as u8);
+            let read = &mut read[..written.len()];
+            self.i2c.write_read(address, &[reg], read)?;
+
+            for (offset, (&written, &read)) in written.iter().zip(read.iter()).enumerate() {
+                let addr = reg.wrapping_add(offset as u8);
+                let writable = dump::find(self.page, addr).map_or(0, |r| r.writable);
+                if (written ^ read) & writable != 0 {
+                    return Err(VerifyError::Mismatch {
+                        addr,
+                        written,
+                        read,
+                    });
+                }
+            }
+        }
+
+        // FUNC_CFG_ACCESS is at 01h on every page
+        if start == 0x01 {
+            self.page = Page::ALL
+                .into_iter()
+                .find(|page| page.access() == data[0] & 0b1100_0000)
+                .unwrap_or(Page::Main);
+        }
+
+        Ok(())
+    }
+}
+
+impl<I2C: I2c> ErrorType for WriteVerify<I2C> {
+    type Error = VerifyError<I2C::Error>;
+}
+
+impl<I2C: I2c> I2c for WriteVerify<I2C> {
+    fn read(&mut self, address: SevenBitAddress, read: &mut [u8]) -> Result<(), Self::Error> {
+        Ok(self.i2c.read(address, read)?)
+    }
+
+    fn write(&mut self, address: SevenBitAddress, write: &[u8]) -> Result<(), Self::Error> {
+        self.i2c.write(address, write)?;
+        self.check(address, write)
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddress,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        Ok(self.i2c.write_read(address, write, read)?)
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.i2c.transaction(address, operations)?;
+
+        for operation in operations.iter() {
+            if let Operation::Write(write) = operation {
+                self.check(address, write)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ctrl10c, ctrl1xl, ctrl3c, sim, Ism330Dhcx};
+
+    #[test]
+    fn verify_and_sync() {
+        let mut sim = sim::Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor
+            .ctrl1xl
+            .set_accelerometer_data_rate(&mut sim, ctrl1xl::Odr_Xl::Hz104)
+            .unwrap();
+        assert!(sensor.verify(&mut sim).unwrap().is_empty());
+
+        // another master changes the data rate behind the driver's back
+        sim.write(0x6b, &[ctrl1xl::ADDR, 0b0101_0000]).unwrap();
+        let mismatches = sensor.verify(&mut sim).unwrap();
+        let changes: Vec<_> = mismatches.iter().collect();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].register.name, "CTRL1_XL");
+        assert_eq!(
+            (changes[0].before, changes[0].after),
+            (0b0100_0000, 0b0101_0000)
+        );
+
+        sensor.sync(&mut sim).unwrap();
+        assert_eq!(sensor.ctrl1xl.value(), 0b0101_0000);
+        assert!(sensor.verify(&mut sim).unwrap().is_empty());
+    }
+
+    #[test]
+    fn timestamp_enable_is_cached() {
+        let mut sim = sim::Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor.enable_timestamp(&mut sim, true).unwrap();
+        assert!(sensor.ctrl10c.timestamp_en());
+        assert!(sensor.verify(&mut sim).unwrap().is_empty());
+
+        sim.write(0x6b, &[ctrl10c::ADDR, 0]).unwrap();
+        let mismatches = sensor.verify(&mut sim).unwrap();
+        let changes: Vec<_> = mismatches.iter().collect();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].register.name, "CTRL10_C");
+
+        sensor.sync(&mut sim).unwrap();
+        assert!(!sensor.ctrl10c.timestamp_en());
+    }
+
+    #[test]
+    fn self_clearing_bits_are_ignored() {
+        let mut sim = sim::Simulator::new();
+        let mut sensor = Ism330Dhcx::new(&mut sim).unwrap();
+        sensor.ctrl3c.set_boot(&mut sim, true).unwrap();
+
+        assert_ne!(sensor.ctrl3c.value() & (1 << ctrl3c::BOOT), 0);
+        assert!(sensor.verify(&mut sim).unwrap().is_empty());
+    }
+
+    #[test]
+    fn write_verify() {
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+        let expectations = [
+            Transaction::write(0x6b, vec![0x10, 0x40]),
+            Transaction::write_read(0x6b, vec![0x10], vec![0x40]),
+            Transaction::write(0x6b, vec![0x10, 0x50]),
+            Transaction::write_read(0x6b, vec![0x10], vec![0x40]),
+            // BOOT self-clears and is not compared
+            Transaction::write(0x6b, vec![0x12, 0x84]),
+            Transaction::write_read(0x6b, vec![0x12], vec![0x04]),
+            // register address only, nothing to check
+            Transaction::write_read(0x6b, vec![0x20], vec![0, 0]),
+        ];
+        let mut i2c = WriteVerify::new(Mock::new(&expectations));
+
+        i2c.write(0x6b, &[0x10, 0x40]).unwrap();
+        assert_eq!(
+            i2c.write(0x6b, &[0x10, 0x50]),
+            Err(VerifyError::Mismatch {
+                addr: 0x10,
+                written: 0x50,
+                read: 0x40
+            })
+        );
+        i2c.write(0x6b, &[0x12, 0x84]).unwrap();
+        i2c.write_read(0x6b, &[0x20], &mut [0; 2]).unwrap();
+
+        i2c.release().done();
+    }
+}
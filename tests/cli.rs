@@ -0,0 +1,107 @@
+use std::process::Command;
+
+fn cli(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_ism330dhcx-cli"))
+        .args(args)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn dump() {
+    let out = cli(&["--sim", "--accel-odr", "104", "dump"]);
+
+    assert!(out.contains("WHO_AM_I                 0x0f = 0x6b\n"));
+    assert!(out
+        .contains("CTRL1_XL                 0x10 = 0x40 ODR_XL=0b0100 FS_XL=0b00 LPF2_XL_EN=0\n"));
+}
+
+#[test]
+fn dump_over_spi() {
+    let out = cli(&["--sim", "--interface", "spi", "--accel-odr", "104", "dump"]);
+
+    assert!(out.contains("WHO_AM_I                 0x0f = 0x6b\n"));
+    assert!(out
+        .contains("CTRL1_XL                 0x10 = 0x40 ODR_XL=0b0100 FS_XL=0b00 LPF2_XL_EN=0\n"));
+}
+
+#[test]
+fn stream_csv() {
+    let out = cli(&[
+        "--sim",
+        "--accel-odr",
+        "104",
+        "--gyro-odr",
+        "104",
+        "--count",
+        "3",
+        "stream",
+    ]);
+    let lines: Vec<&str> = out.lines().collect();
+
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "t_us,temp_c,gx_dps,gy_dps,gz_dps,ax_g,ay_g,az_g");
+    assert_eq!(
+        lines[1],
+        "100000,25.00,0.0000,0.0000,0.0000,0.0000,0.0000,1.0000"
+    );
+}
+
+#[test]
+fn stream_json() {
+    let out = cli(&[
+        "--sim",
+        "--accel-odr",
+        "104",
+        "--count",
+        "1",
+        "--format",
+        "json",
+        "stream",
+    ]);
+
+    assert_eq!(
+        out.trim(),
+        r#"{"t_us":100000,"temp_c":25.00,"gyro_dps":[0.0000,0.0000,0.0000],"accel_g":[0.0000,0.0000,1.0000]}"#
+    );
+}
+
+#[test]
+fn fifo() {
+    let out = cli(&[
+        "--sim",
+        "--accel-odr",
+        "104",
+        "--accel-bdr",
+        "52",
+        "--fifo-mode",
+        "continuous",
+        "--count",
+        "1",
+        "fifo",
+    ]);
+    let lines: Vec<&str> = out.lines().collect();
+
+    assert_eq!(lines.len(), 1 + 5);
+    assert!(lines[1..].iter().all(|l| l.starts_with("accel_g,")));
+}
+
+#[test]
+fn bad_argument() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ism330dhcx-cli"))
+        .args(["--sim", "--accel-odr", "100", "dump"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn help() {
+    let out = cli(&["--help"]);
+
+    assert!(out.starts_with("usage: ism330dhcx-cli"));
+}